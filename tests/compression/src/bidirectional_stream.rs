@@ -6,6 +6,8 @@ util::parametrized_tests! {
     client_enabled_server_enabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -33,7 +35,9 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
         pub fn call<B: Body>(self, req: http::Request<B>) -> http::Request<B> {
             let expected = match self.encoding {
                 CompressionEncoding::Gzip => "gzip",
+                CompressionEncoding::Deflate => "deflate",
                 CompressionEncoding::Zstd => "zstd",
+                CompressionEncoding::Snappy => "snappy",
                 _ => panic!("unexpected encoding {:?}", self.encoding),
             };
             assert_eq!(req.headers().get("grpc-encoding").unwrap(), expected);
@@ -85,7 +89,9 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
 
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
@@ -6,6 +6,8 @@ util::parametrized_tests! {
     client_enabled_server_enabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -30,7 +32,9 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
         pub fn call<B: Body>(self, req: http::Request<B>) -> http::Request<B> {
             let expected = match self.encoding {
                 CompressionEncoding::Gzip => "gzip",
+                CompressionEncoding::Deflate => "deflate",
                 CompressionEncoding::Zstd => "zstd",
+                CompressionEncoding::Snappy => "snappy",
                 _ => panic!("unexpected encoding {:?}", self.encoding),
             };
             assert_eq!(req.headers().get("grpc-encoding").unwrap(), expected);
@@ -77,6 +81,8 @@ util::parametrized_tests! {
     client_disabled_server_enabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -127,6 +133,8 @@ util::parametrized_tests! {
     client_enabled_server_disabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -155,7 +163,9 @@ async fn client_enabled_server_disabled(encoding: CompressionEncoding) {
     assert_eq!(status.code(), tonic::Code::Unimplemented);
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(
@@ -171,6 +181,8 @@ util::parametrized_tests! {
     compressing_response_from_client_stream,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -210,7 +222,9 @@ async fn compressing_response_from_client_stream(encoding: CompressionEncoding)
     let res = client.compress_output_client_stream(req).await.unwrap();
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
@@ -5,6 +5,8 @@ util::parametrized_tests! {
     client_enabled_server_enabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -35,7 +37,9 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
         fn call(&mut self, req: http::Request<B>) -> Self::Future {
             let expected = match self.encoding {
                 CompressionEncoding::Gzip => "gzip",
+                CompressionEncoding::Deflate => "deflate",
                 CompressionEncoding::Zstd => "zstd",
+                CompressionEncoding::Snappy => "snappy",
                 _ => panic!("unexpected encoding {:?}", self.encoding),
             };
             assert_eq!(
@@ -84,7 +88,9 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
 
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
 
@@ -96,10 +102,51 @@ async fn client_enabled_server_enabled(encoding: CompressionEncoding) {
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn client_enabled_all_encodings() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .send_compressed(CompressionEncoding::Zstd)
+        .send_compressed(CompressionEncoding::Snappy);
+
+    fn assert_accept_encoding<B>(req: http::Request<B>) -> http::Request<B> {
+        assert_eq!(
+            req.headers().get("grpc-accept-encoding").unwrap(),
+            "gzip,deflate,zstd,snappy,identity"
+        );
+        req
+    }
+
+    tokio::spawn(async move {
+        Server::builder()
+            .layer(
+                ServiceBuilder::new()
+                    .map_request(assert_accept_encoding)
+                    .into_inner(),
+            )
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+            .await
+            .unwrap();
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .accept_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Deflate)
+        .accept_compressed(CompressionEncoding::Zstd)
+        .accept_compressed(CompressionEncoding::Snappy);
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(res.metadata().get("grpc-encoding").unwrap(), "zstd");
+}
+
 util::parametrized_tests! {
     client_enabled_server_disabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -189,6 +236,8 @@ util::parametrized_tests! {
     client_disabled,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -259,6 +308,8 @@ util::parametrized_tests! {
     server_replying_with_unsupported_encoding,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -302,6 +353,8 @@ util::parametrized_tests! {
     disabling_compression_on_single_response,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -343,7 +396,9 @@ async fn disabling_compression_on_single_response(encoding: CompressionEncoding)
 
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
@@ -356,6 +411,8 @@ util::parametrized_tests! {
     disabling_compression_on_response_but_keeping_compression_on_stream,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -399,7 +456,9 @@ async fn disabling_compression_on_response_but_keeping_compression_on_stream(
 
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
@@ -425,6 +484,8 @@ util::parametrized_tests! {
     disabling_compression_on_response_from_client_stream,
     zstd: CompressionEncoding::Zstd,
     gzip: CompressionEncoding::Gzip,
+    deflate: CompressionEncoding::Deflate,
+    snappy: CompressionEncoding::Snappy,
 }
 
 #[allow(dead_code)]
@@ -468,7 +529,9 @@ async fn disabling_compression_on_response_from_client_stream(encoding: Compress
 
     let expected = match encoding {
         CompressionEncoding::Gzip => "gzip",
+        CompressionEncoding::Deflate => "deflate",
         CompressionEncoding::Zstd => "zstd",
+        CompressionEncoding::Snappy => "snappy",
         _ => panic!("unexpected encoding {:?}", encoding),
     };
     assert_eq!(res.metadata().get("grpc-encoding").unwrap(), expected);
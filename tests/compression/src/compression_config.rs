@@ -0,0 +1,91 @@
+use super::*;
+use tonic::codec::{CompressionConfig, CompressionEncoding, CompressionLevel};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn request_under_min_size_sent_uncompressed() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc =
+        test_server::TestServer::new(Svc::default()).accept_compressed(CompressionEncoding::Gzip);
+
+    let request_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let request_bytes_counter = request_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(
+                    ServiceBuilder::new()
+                        .map_request(|req| {
+                            AssertRightEncoding::new(CompressionEncoding::Gzip).call(req)
+                        })
+                        .layer(measure_request_body_size_layer(request_bytes_counter))
+                        .into_inner(),
+                )
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let config =
+        CompressionConfig::new(CompressionEncoding::Gzip).min_size(UNCOMPRESSED_MIN_BODY_SIZE * 2);
+    let mut client =
+        test_client::TestClient::new(mock_io_channel(client).await).send_compressed(config);
+
+    client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap();
+    let bytes_sent = request_bytes_counter.load(SeqCst);
+    assert!(bytes_sent > UNCOMPRESSED_MIN_BODY_SIZE);
+}
+
+util::parametrized_tests! {
+    response_compressed_at_level,
+    fastest: CompressionLevel::Fastest,
+    best: CompressionLevel::Best,
+    precise: CompressionLevel::Precise(100),
+}
+
+#[allow(dead_code)]
+async fn response_compressed_at_level(level: CompressionLevel) {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let config = CompressionConfig::new(CompressionEncoding::Zstd)
+        .level(level)
+        .min_size(16);
+    let svc = test_server::TestServer::new(Svc::default()).send_compressed(config);
+
+    let response_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn({
+        let response_bytes_counter = response_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(MapResponseBodyLayer::new(move |body| {
+                    util::CountBytesBody {
+                        inner: body,
+                        counter: response_bytes_counter.clone(),
+                    }
+                }))
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .accept_compressed(CompressionEncoding::Zstd);
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(res.metadata().get("grpc-encoding").unwrap(), "zstd");
+    assert_eq!(res.into_inner().data.len(), UNCOMPRESSED_MIN_BODY_SIZE);
+
+    let bytes_sent = response_bytes_counter.load(SeqCst);
+    assert!(bytes_sent < UNCOMPRESSED_MIN_BODY_SIZE);
+}
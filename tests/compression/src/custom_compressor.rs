@@ -0,0 +1,121 @@
+use super::*;
+use bytes::{BufMut, BytesMut};
+use tonic::codec::{CompressionEncoding, Compressor};
+
+/// Run-length encoding, as pairs of a count and a byte.
+struct RunLength;
+
+impl Compressor for RunLength {
+    fn name(&self) -> &'static str {
+        "x-run-length"
+    }
+
+    fn compress(&self, input: &[u8], out: &mut BytesMut) -> std::io::Result<()> {
+        let mut input = input.iter().peekable();
+        while let Some(&byte) = input.next() {
+            let mut count = 1u8;
+            while count < u8::MAX && input.next_if_eq(&&byte).is_some() {
+                count += 1;
+            }
+            out.put_slice(&[count, byte]);
+        }
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], out: &mut BytesMut, max_len: usize) -> std::io::Result<()> {
+        let start = out.len();
+        for run in input.chunks(2) {
+            let [count, byte] = *run else {
+                return Err(std::io::ErrorKind::InvalidData.into());
+            };
+            out.put_bytes(byte, count.into());
+            if out.len() - start > max_len {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+const ENCODING: CompressionEncoding = CompressionEncoding::Custom(&RunLength);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compressing_request() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default())
+        .accept_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(ENCODING);
+
+    let request_bytes_counter = Arc::new(AtomicUsize::new(0));
+
+    fn assert_right_encoding<B>(req: http::Request<B>) -> http::Request<B> {
+        assert_eq!(req.headers().get("grpc-encoding").unwrap(), "x-run-length");
+        req
+    }
+
+    tokio::spawn({
+        let request_bytes_counter = request_bytes_counter.clone();
+        async move {
+            Server::builder()
+                .layer(
+                    ServiceBuilder::new()
+                        .map_request(assert_right_encoding)
+                        .layer(measure_request_body_size_layer(request_bytes_counter))
+                        .into_inner(),
+                )
+                .add_service(svc)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client =
+        test_client::TestClient::new(mock_io_channel(client).await).send_compressed(ENCODING);
+
+    client
+        .compress_input_unary(SomeData {
+            data: [0_u8; UNCOMPRESSED_MIN_BODY_SIZE].to_vec(),
+        })
+        .await
+        .unwrap();
+    let bytes_sent = request_bytes_counter.load(SeqCst);
+    assert!(bytes_sent < UNCOMPRESSED_MIN_BODY_SIZE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compressing_response() {
+    let (client, server) = tokio::io::duplex(UNCOMPRESSED_MIN_BODY_SIZE * 10);
+
+    let svc = test_server::TestServer::new(Svc::default()).send_compressed(ENCODING);
+
+    fn assert_accept_encoding<B>(req: http::Request<B>) -> http::Request<B> {
+        assert_eq!(
+            req.headers().get("grpc-accept-encoding").unwrap(),
+            "gzip,x-run-length,identity"
+        );
+        req
+    }
+
+    tokio::spawn(async move {
+        Server::builder()
+            .layer(
+                ServiceBuilder::new()
+                    .map_request(assert_accept_encoding)
+                    .into_inner(),
+            )
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+            .await
+            .unwrap();
+    });
+
+    let mut client = test_client::TestClient::new(mock_io_channel(client).await)
+        .accept_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(ENCODING);
+
+    let res = client.compress_output_unary(()).await.unwrap();
+    assert_eq!(res.metadata().get("grpc-encoding").unwrap(), "x-run-length");
+    assert_eq!(res.into_inner().data.len(), UNCOMPRESSED_MIN_BODY_SIZE);
+}
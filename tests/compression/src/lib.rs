@@ -22,6 +22,8 @@ mod bidirectional_stream;
 mod client_stream;
 mod compressing_request;
 mod compressing_response;
+mod compression_config;
+mod custom_compressor;
 mod server_stream;
 mod util;
 
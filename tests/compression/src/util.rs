@@ -160,7 +160,9 @@ impl AssertRightEncoding {
     pub fn call<B: Body>(self, req: http::Request<B>) -> http::Request<B> {
         let expected = match self.encoding {
             CompressionEncoding::Gzip => "gzip",
+            CompressionEncoding::Deflate => "deflate",
             CompressionEncoding::Zstd => "zstd",
+            CompressionEncoding::Snappy => "snappy",
             _ => panic!("unexpected encoding {:?}", self.encoding),
         };
         assert_eq!(req.headers().get("grpc-encoding").unwrap(), expected);
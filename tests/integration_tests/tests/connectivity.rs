@@ -0,0 +1,108 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{net::TcpListener, sync::oneshot};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::ConnectivityState, Endpoint, Server},
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+async fn run_server() -> (SocketAddr, oneshot::Sender<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(test_server::TestServer::new(Svc))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                drop(rx.await)
+            })
+            .await
+            .unwrap();
+    });
+
+    (addr, tx)
+}
+
+#[tokio::test]
+async fn lazy_channel_becomes_ready_on_first_call() {
+    let (addr, _shutdown) = run_server().await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect_lazy();
+    assert_eq!(channel.state(), ConnectivityState::Idle);
+
+    let mut client = TestClient::new(channel.clone());
+    client.unary_call(Input {}).await.unwrap();
+
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+}
+
+#[tokio::test]
+async fn failed_connect_is_transient_failure() {
+    // Bind and drop a listener to find a port nothing listens on.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect_lazy();
+
+    let mut client = TestClient::new(channel.clone());
+    client.unary_call(Input {}).await.unwrap_err();
+
+    assert_eq!(channel.state(), ConnectivityState::TransientFailure);
+}
+
+#[tokio::test]
+async fn lost_connection_leaves_ready() {
+    let (addr, shutdown) = run_server().await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+
+    shutdown.send(()).unwrap();
+
+    let state = tokio::time::timeout(
+        Duration::from_secs(5),
+        channel.wait_for_state_change(ConnectivityState::Ready),
+    )
+    .await
+    .expect("state did not change");
+    assert_eq!(state, ConnectivityState::Idle);
+}
+
+#[tokio::test]
+async fn dropped_channel_is_shutdown() {
+    let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+    let mut state = channel.watch_state();
+
+    drop(channel);
+
+    // The connection is dropped by the buffer task in the background.
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        state.wait_for(|state| *state == ConnectivityState::Shutdown),
+    )
+    .await
+    .expect("channel was not shut down")
+    .unwrap();
+}
@@ -0,0 +1,92 @@
+use integration_tests::pb::{
+    test_client::TestClient, test_server, test_stream_client::TestStreamClient, test_stream_server,
+    Input, InputStream, Output, OutputStream,
+};
+use std::time::Duration;
+use tokio::{net::TcpListener, sync::oneshot};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Endpoint, Server},
+    Code, Request, Response, Status,
+};
+
+type Stream<T> = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = std::result::Result<T, Status>> + Send + 'static>,
+>;
+
+struct Unary;
+
+#[tonic::async_trait]
+impl test_server::Test for Unary {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        Ok(Response::new(Output {}))
+    }
+}
+
+struct Endless;
+
+#[tonic::async_trait]
+impl test_stream_server::TestStream for Endless {
+    type StreamCallStream = Stream<OutputStream>;
+
+    async fn stream_call(
+        &self,
+        _: Request<InputStream>,
+    ) -> Result<Response<Self::StreamCallStream>, Status> {
+        let first = tokio_stream::once(Ok(OutputStream {}));
+        let stream = tokio_stream::StreamExt::chain(first, tokio_stream::pending());
+        Ok(Response::new(Box::pin(stream) as Self::StreamCallStream))
+    }
+}
+
+#[tokio::test]
+async fn drains_then_aborts_calls_in_flight() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .shutdown_grace_period(Duration::from_millis(500))
+            .add_service(test_server::TestServer::new(Unary))
+            .add_service(test_stream_server::TestStreamServer::new(Endless))
+            .serve_with_incoming_shutdown_report(TcpListenerStream::new(listener), async {
+                drop(shutdown_rx.await)
+            })
+            .await
+            .unwrap()
+    });
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+
+    let mut stream = TestStreamClient::new(channel.clone())
+        .stream_call(InputStream {})
+        .await
+        .unwrap()
+        .into_inner();
+    stream.message().await.unwrap().unwrap();
+
+    let mut client = TestClient::new(channel);
+    let unary = tokio::spawn(async move { client.unary_call(Input {}).await });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown_tx.send(()).unwrap();
+
+    // The unary call completes within the grace period, the stream is
+    // cancelled once it ends.
+    unary.await.unwrap().unwrap();
+    let status = stream.message().await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+
+    let report = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap();
+    assert_eq!(report.drained(), 1);
+    assert_eq!(report.aborted(), 1);
+}
@@ -0,0 +1,116 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+struct Svc(Arc<AtomicUsize>);
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(Response::new(Output {}))
+    }
+}
+
+struct Backend {
+    addr: SocketAddr,
+    calls: Arc<AtomicUsize>,
+    reporter: HealthReporter,
+}
+
+impl Backend {
+    async fn run(status: ServingStatus) -> Self {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (mut reporter, health) = tonic_health::server::health_reporter();
+        reporter.set_service_status("test.Test", status).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let svc = test_server::TestServer::new(Svc(calls.clone()));
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(health)
+                .add_service(svc)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+
+        Self {
+            addr,
+            calls,
+            reporter,
+        }
+    }
+
+    fn take_calls(&self) -> usize {
+        self.calls.swap(0, Ordering::SeqCst)
+    }
+}
+
+fn balance(backends: &[&Backend]) -> TestClient<Channel> {
+    let endpoints = backends.iter().map(|backend| {
+        Endpoint::from_shared(format!("http://{}", backend.addr))
+            .unwrap()
+            .health_check("test.Test")
+    });
+
+    TestClient::new(Channel::balance_list(endpoints))
+}
+
+#[tokio::test]
+async fn calls_skip_endpoints_not_serving() {
+    let serving = Backend::run(ServingStatus::Serving).await;
+    let not_serving = Backend::run(ServingStatus::NotServing).await;
+    let mut client = balance(&[&serving, &not_serving]);
+
+    for _ in 0..10 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert_eq!(serving.take_calls(), 10);
+    assert_eq!(not_serving.take_calls(), 0);
+}
+
+#[tokio::test]
+async fn endpoints_follow_health_changes() {
+    let mut first = Backend::run(ServingStatus::Serving).await;
+    let mut second = Backend::run(ServingStatus::NotServing).await;
+    let mut client = balance(&[&first, &second]);
+
+    client.unary_call(Request::new(Input {})).await.unwrap();
+    assert_eq!(first.take_calls(), 1);
+
+    first
+        .reporter
+        .set_service_status("test.Test", ServingStatus::NotServing)
+        .await;
+    second
+        .reporter
+        .set_service_status("test.Test", ServingStatus::Serving)
+        .await;
+
+    // Give the channel time to receive the new statuses.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    for _ in 0..10 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert_eq!(first.take_calls(), 0);
+    assert_eq!(second.take_calls(), 10);
+}
@@ -0,0 +1,145 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::net::TcpListener;
+use tonic::{
+    transport::{channel::HedgingPolicy, Channel, Endpoint, Server},
+    Code, Request, Response, Status,
+};
+
+struct Svc {
+    latency: Duration,
+    calls: Arc<AtomicUsize>,
+    status: Option<Code>,
+}
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.latency).await;
+
+        match self.status {
+            Some(code) => Err(Status::new(code, "failed")),
+            None => Ok(Response::new(Output {})),
+        }
+    }
+}
+
+#[tokio::test]
+async fn hedged_call_uses_fastest_endpoint() {
+    let slow = run_service_in_background(Svc {
+        latency: Duration::from_secs(5),
+        calls: Arc::default(),
+        status: None,
+    })
+    .await;
+    let fast = run_service_in_background(Svc {
+        latency: Duration::ZERO,
+        calls: Arc::default(),
+        status: None,
+    })
+    .await;
+
+    let channel = Channel::balance_list(
+        [slow, fast]
+            .into_iter()
+            .map(|addr| Endpoint::from_shared(format!("http://{}", addr)).unwrap()),
+    )
+    .hedging_policy(
+        "test.Test/UnaryCall",
+        HedgingPolicy::new()
+            .max_attempts(2)
+            .hedging_delay(Duration::from_millis(50)),
+    );
+    let mut client = TestClient::new(channel);
+
+    for _ in 0..4 {
+        let start = Instant::now();
+        client.unary_call(Request::new(Input {})).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
+
+#[tokio::test]
+async fn fatal_status_is_returned_immediately() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        latency: Duration::ZERO,
+        calls: calls.clone(),
+        status: Some(Code::InvalidArgument),
+    })
+    .await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+        .hedging_policy(
+            "test.Test",
+            HedgingPolicy::new()
+                .max_attempts(3)
+                .hedging_delay(Duration::from_millis(500))
+                .non_fatal_codes([Code::Unavailable]),
+        );
+    let mut client = TestClient::new(channel);
+
+    let status = client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn non_fatal_status_triggers_next_attempt() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        latency: Duration::ZERO,
+        calls: calls.clone(),
+        status: Some(Code::Unavailable),
+    })
+    .await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+        .hedging_policy(
+            "test.Test/UnaryCall",
+            HedgingPolicy::new()
+                .max_attempts(3)
+                .hedging_delay(Duration::from_secs(5))
+                .non_fatal_codes([Code::Unavailable]),
+        );
+    let mut client = TestClient::new(channel);
+
+    let start = Instant::now();
+    let status = client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+async fn run_service_in_background(svc: Svc) -> SocketAddr {
+    let svc = test_server::TestServer::new(svc);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
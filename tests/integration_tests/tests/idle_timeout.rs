@@ -0,0 +1,79 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::ConnectivityState, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+async fn run_server(mut server: Server) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let router = server.add_service(test_server::TestServer::new(Svc));
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
+async fn wait_until_idle(channel: &Channel) {
+    let state = tokio::time::timeout(
+        Duration::from_secs(5),
+        channel.wait_for_state_change(ConnectivityState::Ready),
+    )
+    .await
+    .expect("connection was not closed");
+    assert_eq!(state, ConnectivityState::Idle);
+}
+
+#[tokio::test]
+async fn channel_closes_idle_connection() {
+    let addr = run_server(Server::builder()).await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .idle_timeout(Duration::from_millis(200))
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel.clone());
+
+    client.unary_call(Input {}).await.unwrap();
+    wait_until_idle(&channel).await;
+
+    // The channel connects again for the next call.
+    client.unary_call(Input {}).await.unwrap();
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+}
+
+#[tokio::test]
+async fn server_closes_idle_connection() {
+    let addr = run_server(Server::builder().idle_timeout(Duration::from_millis(200))).await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel.clone());
+
+    client.unary_call(Input {}).await.unwrap();
+    wait_until_idle(&channel).await;
+
+    client.unary_call(Input {}).await.unwrap();
+}
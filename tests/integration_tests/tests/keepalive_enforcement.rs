@@ -0,0 +1,94 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::ConnectivityState, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+async fn run_server(mut server: Server) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let router = server.add_service(test_server::TestServer::new(Svc));
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
+async fn connect(addr: SocketAddr, keepalive_interval: Duration) -> Channel {
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .http2_keep_alive_interval(keepalive_interval)
+        .keep_alive_while_idle(true)
+        .connect()
+        .await
+        .unwrap();
+    TestClient::new(channel.clone())
+        .unary_call(Input {})
+        .await
+        .unwrap();
+    channel
+}
+
+#[tokio::test]
+async fn closes_connection_pinging_too_often() {
+    let server = Server::builder()
+        .http2_keepalive_min_interval(Some(Duration::from_secs(10)))
+        .http2_keepalive_permit_without_stream(true);
+    let addr = run_server(server).await;
+
+    let channel = connect(addr, Duration::from_millis(50)).await;
+
+    let state = tokio::time::timeout(
+        Duration::from_secs(5),
+        channel.wait_for_state_change(ConnectivityState::Ready),
+    )
+    .await
+    .expect("connection was not closed");
+    assert_eq!(state, ConnectivityState::Idle);
+}
+
+#[tokio::test]
+async fn keeps_connection_pinging_within_policy() {
+    let server = Server::builder()
+        .http2_keepalive_min_interval(Some(Duration::from_millis(50)))
+        .http2_keepalive_permit_without_stream(true);
+    let addr = run_server(server).await;
+
+    let channel = connect(addr, Duration::from_millis(200)).await;
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(channel.state(), ConnectivityState::Ready);
+}
+
+#[tokio::test]
+async fn closes_idle_connection_pinging() {
+    let server = Server::builder().http2_keepalive_min_interval(Some(Duration::from_millis(50)));
+    let addr = run_server(server).await;
+
+    let channel = connect(addr, Duration::from_millis(200)).await;
+
+    let state = tokio::time::timeout(
+        Duration::from_secs(5),
+        channel.wait_for_state_change(ConnectivityState::Ready),
+    )
+    .await
+    .expect("connection was not closed");
+    assert_eq!(state, ConnectivityState::Idle);
+}
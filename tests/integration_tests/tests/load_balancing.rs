@@ -0,0 +1,149 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::oneshot};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::LoadBalancingPolicy, Channel, Endpoint, Server},
+    Request, Response, Status,
+};
+
+struct Svc(Arc<AtomicUsize>);
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(Response::new(Output {}))
+    }
+}
+
+struct Backend {
+    addr: SocketAddr,
+    calls: Arc<AtomicUsize>,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl Backend {
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+async fn run_backends(n: usize) -> Vec<Backend> {
+    let mut backends = Vec::new();
+
+    for _ in 0..n {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = test_server::TestServer::new(Svc(calls.clone()));
+        let (shutdown, rx) = oneshot::channel();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(svc)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    drop(rx.await)
+                })
+                .await
+                .unwrap();
+        });
+
+        backends.push(Backend {
+            addr,
+            calls,
+            shutdown,
+        });
+    }
+
+    backends
+}
+
+fn balance(backends: &[Backend], policy: LoadBalancingPolicy) -> TestClient<Channel> {
+    let endpoints = backends
+        .iter()
+        .map(|backend| Endpoint::from_shared(format!("http://{}", backend.addr)).unwrap());
+
+    TestClient::new(Channel::balance_list(endpoints).load_balancing_policy(policy))
+}
+
+/// Make calls until every backend in `backends` has received one, which means
+/// the channel is connected to all of them.
+async fn warm_up(client: &mut TestClient<Channel>, backends: &[Backend]) {
+    for _ in 0..100 {
+        if backends.iter().all(|backend| backend.calls() > 0) {
+            for backend in backends {
+                backend.calls.store(0, Ordering::SeqCst);
+            }
+            return;
+        }
+
+        let _ = client.unary_call(Request::new(Input {})).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("backends never became ready");
+}
+
+#[tokio::test]
+async fn pick_first_sticks_to_first_endpoint() {
+    let backends = run_backends(3).await;
+    let mut client = balance(&backends, LoadBalancingPolicy::PickFirst);
+    warm_up(&mut client, &backends[..1]).await;
+
+    for _ in 0..6 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert_eq!(backends[0].calls(), 6);
+    assert_eq!(backends[1].calls(), 0);
+    assert_eq!(backends[2].calls(), 0);
+}
+
+#[tokio::test]
+async fn pick_first_fails_over_in_order() {
+    let mut backends = run_backends(3).await;
+    let mut client = balance(&backends, LoadBalancingPolicy::PickFirst);
+    warm_up(&mut client, &backends[..1]).await;
+
+    let first = backends.remove(0);
+    first.shutdown.send(()).unwrap();
+
+    // Calls may fail while the channel notices the endpoint is gone.
+    for _ in 0..100 {
+        if client.unary_call(Request::new(Input {})).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    for _ in 0..6 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    assert!(backends[0].calls() >= 6);
+    assert_eq!(backends[1].calls(), 0);
+}
+
+#[tokio::test]
+async fn round_robin_spreads_calls_evenly() {
+    let backends = run_backends(3).await;
+    let mut client = balance(&backends, LoadBalancingPolicy::RoundRobin);
+    warm_up(&mut client, &backends).await;
+
+    for _ in 0..6 {
+        client.unary_call(Request::new(Input {})).await.unwrap();
+    }
+
+    for backend in &backends {
+        assert_eq!(backend.calls(), 2);
+    }
+}
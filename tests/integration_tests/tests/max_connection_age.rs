@@ -0,0 +1,105 @@
+use integration_tests::pb::{
+    test_client::TestClient, test_server, test_stream_client::TestStreamClient, test_stream_server,
+    Input, InputStream, Output, OutputStream,
+};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::ConnectivityState, Endpoint, Server},
+    Request, Response, Status,
+};
+
+type Stream<T> = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = std::result::Result<T, Status>> + Send + 'static>,
+>;
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+#[tonic::async_trait]
+impl test_stream_server::TestStream for Svc {
+    type StreamCallStream = Stream<OutputStream>;
+
+    async fn stream_call(
+        &self,
+        _: Request<InputStream>,
+    ) -> Result<Response<Self::StreamCallStream>, Status> {
+        let first = tokio_stream::once(Ok(OutputStream {}));
+        let stream = tokio_stream::StreamExt::chain(first, tokio_stream::pending());
+        Ok(Response::new(Box::pin(stream) as Self::StreamCallStream))
+    }
+}
+
+async fn run_server(mut server: Server) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let router = server
+        .add_service(test_server::TestServer::new(Svc))
+        .add_service(test_stream_server::TestStreamServer::new(Svc));
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
+#[tokio::test]
+async fn connection_closed_after_max_age() {
+    let server = Server::builder().max_connection_age(Duration::from_millis(300));
+    let addr = run_server(server).await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel.clone());
+    client.unary_call(Input {}).await.unwrap();
+
+    let state = tokio::time::timeout(
+        Duration::from_secs(5),
+        channel.wait_for_state_change(ConnectivityState::Ready),
+    )
+    .await
+    .expect("connection was not closed");
+    assert_eq!(state, ConnectivityState::Idle);
+
+    // The channel reconnects for the next call.
+    client.unary_call(Input {}).await.unwrap();
+}
+
+#[tokio::test]
+async fn calls_aborted_after_grace() {
+    let server = Server::builder()
+        .max_connection_age(Duration::from_millis(200))
+        .max_connection_age_grace(Duration::from_millis(200));
+    let addr = run_server(server).await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut stream = TestStreamClient::new(channel)
+        .stream_call(InputStream {})
+        .await
+        .unwrap()
+        .into_inner();
+    stream.message().await.unwrap().unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("stream was not aborted")
+        .unwrap_err();
+}
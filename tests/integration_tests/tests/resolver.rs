@@ -0,0 +1,75 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tonic::{
+    transport::{
+        channel::{InMemoryResolver, StaticResolver},
+        Channel, Endpoint, Server,
+    },
+    Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+#[tokio::test]
+async fn static_resolver() {
+    let addr = run_service_in_background().await;
+
+    let endpoint = Endpoint::from_shared(format!("http://{}", addr)).unwrap();
+    let channel = Channel::balance_resolver(StaticResolver::new([endpoint]));
+    let mut client = TestClient::new(channel);
+
+    client.unary_call(Request::new(Input {})).await.unwrap();
+}
+
+#[tokio::test]
+async fn in_memory_resolver_updates_endpoints() {
+    let addr = run_service_in_background().await;
+    let dead = unused_addr().await;
+
+    let (resolver, handle) = InMemoryResolver::new();
+    let channel = Channel::balance_resolver(resolver);
+    let mut client = TestClient::new(channel);
+
+    handle.update([Endpoint::from_shared(format!("http://{}", dead)).unwrap()]);
+
+    // The only endpoint refuses connections, which should also ask the
+    // resolver to re-resolve.
+    client.unary_call(Request::new(Input {})).await.unwrap_err();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(handle.resolve_now_count() > 0);
+
+    handle.update([Endpoint::from_shared(format!("http://{}", addr)).unwrap()]);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    client.unary_call(Request::new(Input {})).await.unwrap();
+}
+
+async fn unused_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap()
+}
+
+async fn run_service_in_background() -> SocketAddr {
+    let svc = test_server::TestServer::new(Svc);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,156 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tonic::{
+    metadata::MetadataValue,
+    transport::{channel::RetryPolicy, Endpoint, Server},
+    Code, Request, Response, Status,
+};
+
+struct Svc {
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+    pushback: Option<&'static str>,
+}
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+
+        if call < self.failures {
+            let mut status = Status::unavailable("not yet");
+            if let Some(pushback) = self.pushback {
+                status.metadata_mut().insert(
+                    "grpc-retry-pushback-ms",
+                    MetadataValue::from_static(pushback),
+                );
+            }
+            return Err(status);
+        }
+
+        Ok(Response::new(Output {}))
+    }
+}
+
+fn policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .initial_backoff(Duration::from_millis(10))
+        .max_backoff(Duration::from_millis(50))
+}
+
+#[tokio::test]
+async fn retries_unavailable_until_success() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        calls: calls.clone(),
+        failures: 2,
+        pushback: None,
+    })
+    .await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .retry_policy(policy())
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    client.unary_call(Request::new(Input {})).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        calls: calls.clone(),
+        failures: usize::MAX,
+        pushback: None,
+    })
+    .await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .retry_policy(policy())
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    let status = client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn negative_pushback_disables_retry() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        calls: calls.clone(),
+        failures: usize::MAX,
+        pushback: Some("-1"),
+    })
+    .await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .retry_policy(policy())
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn throttling_stops_retries() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = run_service_in_background(Svc {
+        calls: calls.clone(),
+        failures: usize::MAX,
+        pushback: None,
+    })
+    .await;
+
+    // Two tokens: the first failure leaves one, which is not more than half.
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .retry_policy(policy())
+        .retry_throttling(2, 0.1)
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+async fn run_service_in_background(svc: Svc) -> SocketAddr {
+    let svc = test_server::TestServer::new(svc);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(svc)
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,194 @@
+use integration_tests::pb::{
+    test1_client::Test1Client, test1_server, test_client::TestClient, test_server, Input, Input1,
+    Output, Output1,
+};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::ServiceConfig, server::Router, Endpoint, Server},
+    Code, Request, Response, Status,
+};
+
+struct Svc {
+    calls: Arc<AtomicUsize>,
+    latency: Duration,
+    failures: usize,
+}
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.latency).await;
+
+        if call < self.failures {
+            return Err(Status::unavailable("not yet"));
+        }
+
+        Ok(Response::new(Output {}))
+    }
+}
+
+struct Echo;
+
+#[tonic::async_trait]
+impl test1_server::Test1 for Echo {
+    async fn unary_call(&self, req: Request<Input1>) -> Result<Response<Output1>, Status> {
+        Ok(Response::new(Output1 {
+            buf: req.into_inner().buf,
+        }))
+    }
+
+    type StreamCallStream = tokio_stream::Empty<Result<Output1, Status>>;
+
+    async fn stream_call(
+        &self,
+        _: Request<Input1>,
+    ) -> Result<Response<Self::StreamCallStream>, Status> {
+        Ok(Response::new(tokio_stream::empty()))
+    }
+}
+
+#[tokio::test]
+async fn retry_policy_from_config() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = test_server::TestServer::new(Svc {
+        calls: calls.clone(),
+        latency: Duration::ZERO,
+        failures: 2,
+    });
+    let addr = run_in_background(Server::builder().add_service(svc)).await;
+
+    let config = ServiceConfig::from_json(
+        r#"{
+            "methodConfig": [{
+                "name": [{ "service": "test.Test", "method": "UnaryCall" }],
+                "retryPolicy": {
+                    "maxAttempts": 3,
+                    "initialBackoff": "0.01s",
+                    "maxBackoff": "0.05s",
+                    "backoffMultiplier": 2,
+                    "retryableStatusCodes": ["UNAVAILABLE"]
+                }
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .service_config(config)
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    client.unary_call(Request::new(Input {})).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn timeout_from_config() {
+    let svc = test_server::TestServer::new(Svc {
+        calls: Arc::default(),
+        latency: Duration::from_secs(5),
+        failures: 0,
+    });
+    let addr = run_in_background(Server::builder().add_service(svc)).await;
+
+    let config = ServiceConfig::from_json(
+        r#"{ "methodConfig": [{ "name": [{ "service": "test.Test" }], "timeout": "0.1s" }] }"#,
+    )
+    .unwrap();
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .service_config(config)
+        .connect()
+        .await
+        .unwrap();
+    let mut client = TestClient::new(channel);
+
+    let status = client.unary_call(Request::new(Input {})).await.unwrap_err();
+    assert_eq!(status.code(), Code::Cancelled);
+}
+
+#[tokio::test]
+async fn request_size_limit_from_config() {
+    let svc = test1_server::Test1Server::new(Echo);
+    let addr = run_in_background(Server::builder().add_service(svc)).await;
+    let config = ServiceConfig::from_json(
+        r#"{ "methodConfig": [{ "name": [{}], "maxRequestMessageBytes": 512 }] }"#,
+    )
+    .unwrap();
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .service_config(config)
+        .connect()
+        .await
+        .unwrap();
+    let mut client = Test1Client::new(channel);
+
+    client
+        .unary_call(Request::new(Input1 { buf: vec![0; 256] }))
+        .await
+        .unwrap();
+
+    let status = client
+        .unary_call(Request::new(Input1 { buf: vec![0; 1024] }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn response_size_limit_from_config() {
+    let svc = test1_server::Test1Server::new(Echo);
+    let addr = run_in_background(Server::builder().add_service(svc)).await;
+    let config = ServiceConfig::from_json(
+        r#"{ "methodConfig": [{ "name": [{}], "maxResponseMessageBytes": 512 }] }"#,
+    )
+    .unwrap();
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .service_config(config)
+        .connect()
+        .await
+        .unwrap();
+    let mut client = Test1Client::new(channel);
+
+    client
+        .unary_call(Request::new(Input1 { buf: vec![0; 256] }))
+        .await
+        .unwrap();
+
+    let status = client
+        .unary_call(Request::new(Input1 { buf: vec![0; 1024] }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::OutOfRange);
+}
+
+async fn run_in_background(router: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    addr
+}
@@ -0,0 +1,114 @@
+use integration_tests::pb::{test_client::TestClient, test_server, Input, Output};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{channel::WaitForReady, Endpoint, Server},
+    Code, Request, Response, Status,
+};
+
+struct Svc;
+
+#[tonic::async_trait]
+impl test_server::Test for Svc {
+    async fn unary_call(&self, _: Request<Input>) -> Result<Response<Output>, Status> {
+        Ok(Response::new(Output {}))
+    }
+}
+
+/// An address nothing listens on yet.
+async fn unused_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Start serving on `addr` after `delay`.
+fn serve_later(addr: SocketAddr, delay: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        Server::builder()
+            .add_service(test_server::TestServer::new(Svc))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+}
+
+fn request(wait_for_ready: bool, timeout: Duration) -> Request<Input> {
+    let mut req = Request::new(Input {});
+    req.set_timeout(timeout);
+    req.extensions_mut().insert(WaitForReady(wait_for_ready));
+    req
+}
+
+#[tokio::test]
+async fn call_waits_for_server() {
+    let addr = unused_addr().await;
+    serve_later(addr, Duration::from_millis(500));
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect_lazy();
+    let mut client = TestClient::new(channel);
+
+    client
+        .unary_call(request(true, Duration::from_secs(10)))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn call_fails_fast_by_default() {
+    let addr = unused_addr().await;
+    serve_later(addr, Duration::from_secs(1));
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect_lazy();
+    let mut client = TestClient::new(channel);
+
+    let status = client
+        .unary_call(request(false, Duration::from_secs(10)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn channel_default_waits_for_server() {
+    let addr = unused_addr().await;
+    serve_later(addr, Duration::from_millis(500));
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .wait_for_ready(true)
+        .connect_lazy();
+    let mut client = TestClient::new(channel);
+
+    let mut req = Request::new(Input {});
+    req.set_timeout(Duration::from_secs(10));
+    client.unary_call(req).await.unwrap();
+}
+
+#[tokio::test]
+async fn wait_ends_at_deadline() {
+    let addr = unused_addr().await;
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect_lazy();
+    let mut client = TestClient::new(channel);
+
+    let status = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.unary_call(request(true, Duration::from_millis(500))),
+    )
+    .await
+    .expect("call did not end at its deadline")
+    .unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+}
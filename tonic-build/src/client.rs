@@ -94,13 +94,13 @@ pub(crate) fn generate_internal<T: Service>(
                     #service_ident::new(InterceptedService::new(inner, interceptor))
                 }
 
-                /// Compress requests with the given encoding.
+                /// Compress requests with the given encoding, or `CompressionConfig`.
                 ///
                 /// This requires the server to support it otherwise it might respond with an
                 /// error.
                 #[must_use]
-                pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-                    self.inner = self.inner.send_compressed(encoding);
+                pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+                    self.inner = self.inner.send_compressed(config);
                     self
                 }
 
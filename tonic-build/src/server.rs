@@ -64,10 +64,11 @@ pub(crate) fn generate_internal<T: Service>(
             self
         }
 
-        /// Compress responses with the given encoding, if the client supports it.
+        /// Compress responses with the given encoding, or `CompressionConfig`, if the client
+        /// supports it.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
+        pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+            self.send_compression_encodings.enable(config);
             self
         }
     };
@@ -100,13 +100,13 @@ pub mod health_client {
         {
             HealthClient::new(InterceptedService::new(inner, interceptor))
         }
-        /// Compress requests with the given encoding.
+        /// Compress requests with the given encoding, or `CompressionConfig`.
         ///
         /// This requires the server to support it otherwise it might respond with an
         /// error.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.send_compressed(encoding);
+        pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+            self.inner = self.inner.send_compressed(config);
             self
         }
         /// Enable decompressing responses.
@@ -278,10 +278,11 @@ pub mod health_server {
             self.accept_compression_encodings.enable(encoding);
             self
         }
-        /// Compress responses with the given encoding, if the client supports it.
+        /// Compress responses with the given encoding, or `CompressionConfig`, if the client
+        /// supports it.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
+        pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+            self.send_compression_encodings.enable(config);
             self
         }
         /// Limits the maximum size of a decoded message.
@@ -1,9 +1,12 @@
-use crate::codec::compression::{CompressionEncoding, EnabledCompressionEncodings};
+use crate::codec::compression::{
+    CompressionConfig, CompressionEncoding, EnabledCompressionEncodings,
+};
 use crate::metadata::GRPC_CONTENT_TYPE;
 use crate::{
     body::BoxBody,
     client::GrpcService,
     codec::{encode_client, Codec, Decoder, Streaming},
+    extensions::MaxDecodingMessageSize,
     request::SanitizeHeaders,
     Code, Request, Response, Status,
 };
@@ -38,7 +41,7 @@ struct GrpcConfig {
     /// Which compression encodings does the client accept?
     accept_compression_encodings: EnabledCompressionEncodings,
     /// The compression encoding that will be applied to requests.
-    send_compression_encodings: Option<CompressionEncoding>,
+    send_compression_encodings: Option<CompressionConfig>,
     /// Limits the maximum size of a decoded message.
     max_decoding_message_size: Option<usize>,
     /// Limits the maximum size of an encoded message.
@@ -94,8 +97,20 @@ impl<T> Grpc<T> {
     /// let client = TestClient::new(channel).send_compressed(CompressionEncoding::Gzip);
     /// # };
     /// ```
-    pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-        self.config.send_compression_encodings = Some(encoding);
+    ///
+    /// A [`CompressionConfig`] can be given instead of the encoding, to set the compression level
+    /// and the size under which requests are sent uncompressed.
+    ///
+    /// Custom encodings that cannot be enabled, as described in
+    /// [`EnabledCompressionEncodings::enable`], are ignored with a warning.
+    ///
+    /// [`CompressionConfig`]: crate::codec::CompressionConfig
+    pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+        let config = config.into();
+        match config.encoding.check_name() {
+            Ok(()) => self.config.send_compression_encodings = Some(config),
+            Err(err) => tracing::warn!("{}", err),
+        }
         self
     }
 
@@ -349,15 +364,18 @@ impl<T> Grpc<T> {
             true
         };
 
+        // The channel may impose a tighter limit for this method.
+        let max_message_size = match (
+            self.config.max_decoding_message_size,
+            response.extensions().get::<MaxDecodingMessageSize>(),
+        ) {
+            (Some(limit), Some(channel)) => Some(limit.min(channel.0)),
+            (limit, channel) => limit.or(channel.map(|channel| channel.0)),
+        };
+
         let response = response.map(|body| {
             if expect_additional_trailers {
-                Streaming::new_response(
-                    decoder,
-                    body,
-                    status_code,
-                    encoding,
-                    self.config.max_decoding_message_size,
-                )
+                Streaming::new_response(decoder, body, status_code, encoding, max_message_size)
             } else {
                 Streaming::new_empty(decoder, body)
             }
@@ -407,11 +425,10 @@ impl GrpcConfig {
             .headers_mut()
             .insert(CONTENT_TYPE, GRPC_CONTENT_TYPE);
 
-        #[cfg(any(feature = "gzip", feature = "zstd"))]
-        if let Some(encoding) = self.send_compression_encodings {
+        if let Some(config) = self.send_compression_encodings {
             request.headers_mut().insert(
                 crate::codec::compression::ENCODING_HEADER,
-                encoding.into_header_value(),
+                config.encoding.into_header_value(),
             );
         }
 
@@ -2,7 +2,9 @@ use crate::{metadata::MetadataValue, Status};
 use bytes::{Buf, BufMut, BytesMut};
 #[cfg(feature = "gzip")]
 use flate2::read::{GzDecoder, GzEncoder};
-use std::fmt;
+#[cfg(feature = "deflate")]
+use flate2::read::{ZlibDecoder, ZlibEncoder};
+use std::{error::Error, fmt, io};
 #[cfg(feature = "zstd")]
 use zstd::stream::read::{Decoder, Encoder};
 
@@ -11,29 +13,67 @@ pub(crate) const ACCEPT_ENCODING_HEADER: &str = "grpc-accept-encoding";
 
 /// Struct used to configure which encodings are enabled on a server or channel.
 ///
-/// Represents an ordered list of compression encodings that are enabled.
+/// Represents an ordered list of compression encodings that are enabled. All
+/// the encodings compiled in, and up to four custom encodings, can be enabled
+/// at once, as the list has a fixed size for it to be `Copy`.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct EnabledCompressionEncodings {
-    inner: [Option<CompressionEncoding>; 2],
+    inner: [Option<CompressionConfig>; CompressionEncoding::ENCODINGS.len() + MAX_CUSTOM_ENCODINGS],
 }
 
 impl EnabledCompressionEncodings {
-    /// Enable a [`CompressionEncoding`].
+    /// Enable a [`CompressionEncoding`], with the given [`CompressionConfig`]
+    /// for sending messages.
     ///
-    /// Adds the new encoding to the end of the encoding list.
-    pub fn enable(&mut self, encoding: CompressionEncoding) {
-        for e in self.inner.iter_mut() {
-            match e {
-                Some(e) if *e == encoding => return,
-                None => {
-                    *e = Some(encoding);
-                    return;
-                }
-                _ => continue,
-            }
+    /// Adds the new encoding to the end of the encoding list, or updates its
+    /// configuration if it is already enabled.
+    ///
+    /// Custom encodings that cannot be enabled, past the fourth one, with a
+    /// name that is not an HTTP token or named after a built-in encoding, are
+    /// ignored with a warning. Use
+    /// [`EnabledCompressionEncodings::try_enable`] to handle them instead.
+    pub fn enable(&mut self, config: impl Into<CompressionConfig>) {
+        if let Err(err) = self.try_enable(config) {
+            tracing::warn!("{}", err);
         }
     }
 
+    /// Like [`EnabledCompressionEncodings::enable`], returning an error for
+    /// custom encodings that cannot be enabled.
+    pub fn try_enable(
+        &mut self,
+        config: impl Into<CompressionConfig>,
+    ) -> Result<(), EnableEncodingError> {
+        let config = config.into();
+        config.encoding.check_name()?;
+        let name = config.encoding.as_str();
+        let custom = config.encoding.is_custom();
+
+        if let Some(e) = self
+            .inner
+            .iter_mut()
+            .flatten()
+            .find(|e| e.encoding == config.encoding)
+        {
+            *e = config;
+            return Ok(());
+        }
+
+        if custom && self.iter().filter(|e| e.encoding.is_custom()).count() >= MAX_CUSTOM_ENCODINGS
+        {
+            return Err(EnableEncodingError {
+                name,
+                kind: EnableEncodingErrorKind::TooManyCustomEncodings,
+            });
+        }
+
+        // There is room for every built-in encoding and the custom ones.
+        if let Some(e) = self.inner.iter_mut().find(|e| e.is_none()) {
+            *e = Some(config);
+        }
+        Ok(())
+    }
+
     /// Remove the last [`CompressionEncoding`].
     pub fn pop(&mut self) -> Option<CompressionEncoding> {
         self.inner
@@ -41,15 +81,16 @@ impl EnabledCompressionEncodings {
             .rev()
             .find(|entry| entry.is_some())?
             .take()
+            .map(|config| config.encoding)
     }
 
     pub(crate) fn into_accept_encoding_header_value(self) -> Option<http::HeaderValue> {
         let mut value = BytesMut::new();
-        for encoding in self.inner.into_iter().flatten() {
+        for config in self.iter() {
             if !value.is_empty() {
                 value.put_slice(b",");
             }
-            value.put_slice(encoding.as_str().as_bytes());
+            value.put_slice(config.encoding.as_str().as_bytes());
         }
 
         if value.is_empty() {
@@ -62,41 +103,280 @@ impl EnabledCompressionEncodings {
 
     /// Check if a [`CompressionEncoding`] is enabled.
     pub fn is_enabled(&self, encoding: CompressionEncoding) -> bool {
-        self.inner.iter().any(|e| *e == Some(encoding))
+        self.get(encoding.as_str()).is_some()
     }
 
     /// Check if any [`CompressionEncoding`]s are enabled.
     pub fn is_empty(&self) -> bool {
         self.inner.iter().all(|e| e.is_none())
     }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = CompressionConfig> + '_ {
+        self.inner.iter().flatten().copied()
+    }
+
+    /// Get the configuration of the enabled encoding named `name`.
+    fn get(&self, name: &str) -> Option<CompressionConfig> {
+        self.iter().find(|config| config.encoding.as_str() == name)
+    }
 }
 
+/// Error returned by [`EnabledCompressionEncodings::try_enable`] for custom
+/// encodings that cannot be enabled.
+#[derive(Debug)]
+pub struct EnableEncodingError {
+    name: &'static str,
+    kind: EnableEncodingErrorKind,
+}
+
+#[derive(Debug)]
+enum EnableEncodingErrorKind {
+    InvalidName,
+    BuiltInName,
+    TooManyCustomEncodings,
+}
+
+impl fmt::Display for EnableEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            EnableEncodingErrorKind::InvalidName => write!(
+                f,
+                "custom compression encoding name {:?} is not an HTTP token",
+                self.name
+            ),
+            EnableEncodingErrorKind::BuiltInName => write!(
+                f,
+                "custom compression encoding `{}` is named after a built-in encoding",
+                self.name
+            ),
+            EnableEncodingErrorKind::TooManyCustomEncodings => write!(
+                f,
+                "at most {} custom compression encodings can be enabled, `{}` was not",
+                MAX_CUSTOM_ENCODINGS, self.name
+            ),
+        }
+    }
+}
+
+impl Error for EnableEncodingError {}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct CompressionSettings {
     pub(crate) encoding: CompressionEncoding,
+    pub(crate) level: CompressionLevel,
     /// buffer_growth_interval controls memory growth for internal buffers to balance resizing cost against memory waste.
     /// The default buffer growth interval is 8 kilobytes.
     pub(crate) buffer_growth_interval: usize,
 }
 
 /// The compression encodings Tonic supports.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy)]
 #[non_exhaustive]
 pub enum CompressionEncoding {
     #[allow(missing_docs)]
     #[cfg(feature = "gzip")]
     Gzip,
     #[allow(missing_docs)]
+    #[cfg(feature = "deflate")]
+    Deflate,
+    /// Zstd, without a dictionary.
+    ///
+    /// Compressing with a pre-trained dictionary is a separate encoding, as
+    /// both peers need the dictionary, see [`ZstdDictionary`].
+    ///
+    /// [`ZstdDictionary`]: crate::codec::ZstdDictionary
     #[cfg(feature = "zstd")]
     Zstd,
+    /// The [snappy] framing format.
+    ///
+    /// [snappy]: https://github.com/google/snappy/blob/main/framing_format.txt
+    #[cfg(feature = "snappy")]
+    Snappy,
+    /// An encoding implemented by a [`Compressor`].
+    ///
+    /// Custom encodings are equal when their names are, and never equal to
+    /// built-in encodings.
+    Custom(&'static dyn Compressor),
+}
+
+/// A compression encoding implemented outside of tonic, used through
+/// [`CompressionEncoding::Custom`].
+///
+/// # Example
+///
+/// ```
+/// use bytes::BytesMut;
+/// use tonic::codec::{CompressionEncoding, Compressor};
+///
+/// struct Reverse;
+///
+/// impl Compressor for Reverse {
+///     fn name(&self) -> &'static str {
+///         "x-reverse"
+///     }
+///
+///     fn compress(&self, input: &[u8], out: &mut BytesMut) -> std::io::Result<()> {
+///         out.extend(input.iter().rev());
+///         Ok(())
+///     }
+///
+///     fn decompress(
+///         &self,
+///         input: &[u8],
+///         out: &mut BytesMut,
+///         max_len: usize,
+///     ) -> std::io::Result<()> {
+///         out.extend(input.iter().rev().take(max_len.saturating_add(1)));
+///         Ok(())
+///     }
+/// }
+///
+/// static REVERSE: Reverse = Reverse;
+/// let encoding = CompressionEncoding::Custom(&REVERSE);
+/// ```
+pub trait Compressor: Send + Sync + 'static {
+    /// The name of the encoding, as found in the `grpc-encoding` and
+    /// `grpc-accept-encoding` headers.
+    ///
+    /// This must be a non-empty HTTP token, such as `x-snappy-framed`, and
+    /// differ from the names of the other encodings. Encodings with other
+    /// names, or named after a built-in encoding such as `gzip` or
+    /// `identity`, whether or not it is compiled in, cannot be enabled.
+    fn name(&self) -> &'static str;
+
+    /// Compress `input`, appending the result to `out`.
+    fn compress(&self, input: &[u8], out: &mut BytesMut) -> io::Result<()>;
+
+    /// Decompress `input`, appending the result to `out`.
+    ///
+    /// Messages decompressing to more than `max_len` bytes, the decoding
+    /// limit, are rejected once this returns. Implementations should stop as
+    /// soon as more than `max_len` bytes are written, so that small malicious
+    /// messages cannot expand without bound.
+    fn decompress(&self, input: &[u8], out: &mut BytesMut, max_len: usize) -> io::Result<()>;
+}
+
+const MAX_CUSTOM_ENCODINGS: usize = 4;
+
+/// The names of the built-in encodings, compiled in or not, and of the lack
+/// of encoding.
+const BUILT_IN_NAMES: &[&str] = &["identity", "gzip", "deflate", "zstd", "snappy"];
+
+/// Whether `name` is a token as defined by RFC 9110, which can be used in the
+/// `grpc-encoding` and `grpc-accept-encoding` headers.
+fn is_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// How messages sent with a [`CompressionEncoding`] are compressed.
+///
+/// A [`CompressionEncoding`] converts into its default configuration, which
+/// compresses every message at the default level of the encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub(crate) encoding: CompressionEncoding,
+    pub(crate) level: CompressionLevel,
+    pub(crate) min_size: usize,
+}
+
+impl CompressionConfig {
+    /// Creates the default configuration of `encoding`.
+    pub fn new(encoding: CompressionEncoding) -> Self {
+        Self {
+            encoding,
+            level: CompressionLevel::Default,
+            min_size: 0,
+        }
+    }
+
+    /// Sets the level at which messages are compressed.
+    ///
+    /// Encodings without levels, such as custom encodings, ignore it.
+    pub fn level(self, level: CompressionLevel) -> Self {
+        Self { level, ..self }
+    }
+
+    /// Sets the size of encoded messages under which they are sent
+    /// uncompressed, as compressing small messages often makes them bigger.
+    ///
+    /// Default is zero, compressing every message.
+    pub fn min_size(self, min_size: usize) -> Self {
+        Self { min_size, ..self }
+    }
+
+    /// Returns the encoding being configured.
+    pub fn encoding(&self) -> CompressionEncoding {
+        self.encoding
+    }
+}
+
+impl From<CompressionEncoding> for CompressionConfig {
+    fn from(encoding: CompressionEncoding) -> Self {
+        Self::new(encoding)
+    }
+}
+
+/// The level at which messages are compressed, trading speed for the
+/// compression ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompressionLevel {
+    /// The fastest level of the encoding.
+    Fastest,
+    /// The default level of the encoding.
+    #[default]
+    Default,
+    /// The level of the encoding with the best compression ratio.
+    Best,
+    /// A level specific to the encoding, clamped to the range it supports.
+    Precise(i32),
+}
+
+impl CompressionLevel {
+    #[cfg(any(feature = "gzip", feature = "deflate"))]
+    fn into_flate2(self) -> flate2::Compression {
+        match self {
+            CompressionLevel::Fastest => flate2::Compression::fast(),
+            CompressionLevel::Default => flate2::Compression::new(6),
+            CompressionLevel::Best => flate2::Compression::best(),
+            CompressionLevel::Precise(level) => flate2::Compression::new(level.clamp(0, 9) as u32),
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    fn into_zstd(self) -> i32 {
+        let range = zstd::compression_level_range();
+        match self {
+            CompressionLevel::Fastest => 1,
+            CompressionLevel::Default => zstd::DEFAULT_COMPRESSION_LEVEL,
+            CompressionLevel::Best => *range.end(),
+            CompressionLevel::Precise(level) => level.clamp(*range.start(), *range.end()),
+        }
+    }
 }
 
 impl CompressionEncoding {
-    /// Based on the `grpc-accept-encoding` header, pick an encoding to use.
+    /// The built-in encodings compiled in.
+    pub(crate) const ENCODINGS: &'static [CompressionEncoding] = &[
+        #[cfg(feature = "gzip")]
+        CompressionEncoding::Gzip,
+        #[cfg(feature = "deflate")]
+        CompressionEncoding::Deflate,
+        #[cfg(feature = "zstd")]
+        CompressionEncoding::Zstd,
+        #[cfg(feature = "snappy")]
+        CompressionEncoding::Snappy,
+    ];
+
+    /// Based on the `grpc-accept-encoding` header, pick the first encoding that is also enabled,
+    /// and its configuration.
     pub(crate) fn from_accept_encoding_header(
         map: &http::HeaderMap,
         enabled_encodings: EnabledCompressionEncodings,
-    ) -> Option<Self> {
+    ) -> Option<CompressionConfig> {
         if enabled_encodings.is_empty() {
             return None;
         }
@@ -104,13 +384,7 @@ impl CompressionEncoding {
         let header_value = map.get(ACCEPT_ENCODING_HEADER)?;
         let header_value_str = header_value.to_str().ok()?;
 
-        split_by_comma(header_value_str).find_map(|value| match value {
-            #[cfg(feature = "gzip")]
-            "gzip" => Some(CompressionEncoding::Gzip),
-            #[cfg(feature = "zstd")]
-            "zstd" => Some(CompressionEncoding::Zstd),
-            _ => None,
-        })
+        split_by_comma(header_value_str).find_map(|value| enabled_encodings.get(value))
     }
 
     /// Get the value of `grpc-encoding` header. Returns an error if the encoding isn't supported.
@@ -130,78 +404,105 @@ impl CompressionEncoding {
             return Ok(None);
         };
 
-        match header_value_str {
-            #[cfg(feature = "gzip")]
-            "gzip" if enabled_encodings.is_enabled(CompressionEncoding::Gzip) => {
-                Ok(Some(CompressionEncoding::Gzip))
-            }
-            #[cfg(feature = "zstd")]
-            "zstd" if enabled_encodings.is_enabled(CompressionEncoding::Zstd) => {
-                Ok(Some(CompressionEncoding::Zstd))
-            }
-            "identity" => Ok(None),
-            other => {
-                let mut status = Status::unimplemented(format!(
-                    "Content is compressed with `{}` which isn't supported",
-                    other
-                ));
-
-                let header_value = enabled_encodings
-                    .into_accept_encoding_header_value()
-                    .map(MetadataValue::unchecked_from_header_value)
-                    .unwrap_or_else(|| MetadataValue::from_static("identity"));
-                status
-                    .metadata_mut()
-                    .insert(ACCEPT_ENCODING_HEADER, header_value);
-
-                Err(status)
-            }
+        if header_value_str == "identity" {
+            return Ok(None);
         }
+        if let Some(config) = enabled_encodings.get(header_value_str) {
+            return Ok(Some(config.encoding));
+        }
+
+        let mut status = Status::unimplemented(format!(
+            "Content is compressed with `{}` which isn't supported",
+            header_value_str
+        ));
+
+        let header_value = enabled_encodings
+            .into_accept_encoding_header_value()
+            .map(MetadataValue::unchecked_from_header_value)
+            .unwrap_or_else(|| MetadataValue::from_static("identity"));
+        status
+            .metadata_mut()
+            .insert(ACCEPT_ENCODING_HEADER, header_value);
+
+        Err(status)
     }
 
     pub(crate) fn as_str(self) -> &'static str {
         match self {
             #[cfg(feature = "gzip")]
             CompressionEncoding::Gzip => "gzip",
+            #[cfg(feature = "deflate")]
+            CompressionEncoding::Deflate => "deflate",
             #[cfg(feature = "zstd")]
             CompressionEncoding::Zstd => "zstd",
+            #[cfg(feature = "snappy")]
+            CompressionEncoding::Snappy => "snappy",
+            CompressionEncoding::Custom(compressor) => compressor.name(),
         }
     }
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn is_custom(self) -> bool {
+        matches!(self, CompressionEncoding::Custom(_))
+    }
+
+    /// Checks that custom encodings have a name they can be sent under.
+    pub(crate) fn check_name(self) -> Result<(), EnableEncodingError> {
+        let name = self.as_str();
+        let kind = if !self.is_custom() {
+            return Ok(());
+        } else if !is_token(name) {
+            EnableEncodingErrorKind::InvalidName
+        } else if BUILT_IN_NAMES.iter().any(|b| b.eq_ignore_ascii_case(name)) {
+            EnableEncodingErrorKind::BuiltInName
+        } else {
+            return Ok(());
+        };
+        Err(EnableEncodingError { name, kind })
+    }
+
     pub(crate) fn into_header_value(self) -> http::HeaderValue {
         http::HeaderValue::from_static(self.as_str())
     }
+}
 
-    pub(crate) fn encodings() -> &'static [Self] {
-        &[
-            #[cfg(feature = "gzip")]
-            CompressionEncoding::Gzip,
-            #[cfg(feature = "zstd")]
-            CompressionEncoding::Zstd,
-        ]
+impl PartialEq for CompressionEncoding {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_custom() == other.is_custom() && self.as_str() == other.as_str()
     }
 }
 
-impl fmt::Display for CompressionEncoding {
-    #[allow(unused_variables)]
+impl Eq for CompressionEncoding {}
+
+impl fmt::Debug for CompressionEncoding {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             #[cfg(feature = "gzip")]
-            CompressionEncoding::Gzip => write!(f, "gzip"),
+            CompressionEncoding::Gzip => f.write_str("Gzip"),
+            #[cfg(feature = "deflate")]
+            CompressionEncoding::Deflate => f.write_str("Deflate"),
             #[cfg(feature = "zstd")]
-            CompressionEncoding::Zstd => write!(f, "zstd"),
+            CompressionEncoding::Zstd => f.write_str("Zstd"),
+            #[cfg(feature = "snappy")]
+            CompressionEncoding::Snappy => f.write_str("Snappy"),
+            CompressionEncoding::Custom(compressor) => {
+                f.debug_tuple("Custom").field(&compressor.name()).finish()
+            }
         }
     }
 }
 
+impl fmt::Display for CompressionEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 fn split_by_comma(s: &str) -> impl Iterator<Item = &str> {
     s.trim().split(',').map(|s| s.trim())
 }
 
 /// Compress `len` bytes from `decompressed_buf` into `out_buf`.
 /// buffer_size_increment is a hint to control the growth of out_buf versus the cost of resizing it.
-#[allow(unused_variables, unreachable_code)]
 pub(crate) fn compress(
     settings: CompressionSettings,
     decompressed_buf: &mut BytesMut,
@@ -212,28 +513,35 @@ pub(crate) fn compress(
     let capacity = ((len / buffer_growth_interval) + 1) * buffer_growth_interval;
     out_buf.reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
     let mut out_writer = bytes::BufMut::writer(out_buf);
 
     match settings.encoding {
         #[cfg(feature = "gzip")]
         CompressionEncoding::Gzip => {
-            let mut gzip_encoder = GzEncoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                flate2::Compression::new(6),
-            );
+            let mut gzip_encoder =
+                GzEncoder::new(&decompressed_buf[0..len], settings.level.into_flate2());
             std::io::copy(&mut gzip_encoder, &mut out_writer)?;
         }
+        #[cfg(feature = "deflate")]
+        CompressionEncoding::Deflate => {
+            let mut deflate_encoder =
+                ZlibEncoder::new(&decompressed_buf[0..len], settings.level.into_flate2());
+            std::io::copy(&mut deflate_encoder, &mut out_writer)?;
+        }
         #[cfg(feature = "zstd")]
         CompressionEncoding::Zstd => {
-            let mut zstd_encoder = Encoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                zstd::DEFAULT_COMPRESSION_LEVEL,
-            )?;
+            let mut zstd_encoder =
+                Encoder::new(&decompressed_buf[0..len], settings.level.into_zstd())?;
             std::io::copy(&mut zstd_encoder, &mut out_writer)?;
         }
+        #[cfg(feature = "snappy")]
+        CompressionEncoding::Snappy => {
+            let mut snappy_encoder = snap::read::FrameEncoder::new(&decompressed_buf[0..len]);
+            std::io::copy(&mut snappy_encoder, &mut out_writer)?;
+        }
+        CompressionEncoding::Custom(compressor) => {
+            compressor.compress(&decompressed_buf[0..len], out_writer.get_mut())?;
+        }
     }
 
     decompressed_buf.advance(len);
@@ -242,32 +550,59 @@ pub(crate) fn compress(
 }
 
 /// Decompress `len` bytes from `compressed_buf` into `out_buf`.
-#[allow(unused_variables, unreachable_code)]
+///
+/// Decompression stops once more than `max_len` bytes have been written, so
+/// callers must compare the length of `out_buf` against their limit.
 pub(crate) fn decompress(
     settings: CompressionSettings,
     compressed_buf: &mut BytesMut,
     out_buf: &mut BytesMut,
     len: usize,
+    max_len: usize,
 ) -> Result<(), std::io::Error> {
     let buffer_growth_interval = settings.buffer_growth_interval;
-    let estimate_decompressed_len = len * 2;
+    let estimate_decompressed_len = (len * 2).min(max_len);
     let capacity =
         ((estimate_decompressed_len / buffer_growth_interval) + 1) * buffer_growth_interval;
     out_buf.reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "zstd"))]
     let mut out_writer = bytes::BufMut::writer(out_buf);
 
     match settings.encoding {
         #[cfg(feature = "gzip")]
         CompressionEncoding::Gzip => {
-            let mut gzip_decoder = GzDecoder::new(&compressed_buf[0..len]);
-            std::io::copy(&mut gzip_decoder, &mut out_writer)?;
+            copy_limited(
+                GzDecoder::new(&compressed_buf[0..len]),
+                &mut out_writer,
+                max_len,
+            )?;
+        }
+        #[cfg(feature = "deflate")]
+        CompressionEncoding::Deflate => {
+            copy_limited(
+                ZlibDecoder::new(&compressed_buf[0..len]),
+                &mut out_writer,
+                max_len,
+            )?;
         }
         #[cfg(feature = "zstd")]
         CompressionEncoding::Zstd => {
-            let mut zstd_decoder = Decoder::new(&compressed_buf[0..len])?;
-            std::io::copy(&mut zstd_decoder, &mut out_writer)?;
+            copy_limited(
+                Decoder::new(&compressed_buf[0..len])?,
+                &mut out_writer,
+                max_len,
+            )?;
+        }
+        #[cfg(feature = "snappy")]
+        CompressionEncoding::Snappy => {
+            copy_limited(
+                snap::read::FrameDecoder::new(&compressed_buf[0..len]),
+                &mut out_writer,
+                max_len,
+            )?;
+        }
+        CompressionEncoding::Custom(compressor) => {
+            compressor.decompress(&compressed_buf[0..len], out_writer.get_mut(), max_len)?;
         }
     }
 
@@ -276,6 +611,21 @@ pub(crate) fn decompress(
     Ok(())
 }
 
+/// Copy from `reader` to `writer` until more than `max_len` bytes are copied.
+#[cfg(any(
+    feature = "gzip",
+    feature = "deflate",
+    feature = "zstd",
+    feature = "snappy"
+))]
+pub(super) fn copy_limited(
+    reader: impl io::Read,
+    writer: &mut impl io::Write,
+    max_len: usize,
+) -> io::Result<u64> {
+    io::copy(&mut reader.take((max_len as u64).saturating_add(1)), writer)
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum SingleMessageCompressionOverride {
     /// Inherit whatever compression is already configured. If the stream is compressed this
@@ -287,3 +637,173 @@ pub(crate) enum SingleMessageCompressionOverride {
     /// Don't compress this message, even if compression is enabled on the stream.
     Disable,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str);
+
+    impl Compressor for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn compress(&self, input: &[u8], out: &mut BytesMut) -> io::Result<()> {
+            out.put_slice(input);
+            Ok(())
+        }
+
+        fn decompress(&self, input: &[u8], out: &mut BytesMut, _: usize) -> io::Result<()> {
+            out.put_slice(input);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_encodings_past_the_limit_are_rejected() {
+        static NAMED: [Named; 5] = [
+            Named("x-1"),
+            Named("x-2"),
+            Named("x-3"),
+            Named("x-4"),
+            Named("x-5"),
+        ];
+
+        let mut encodings = EnabledCompressionEncodings::default();
+        for named in &NAMED[..4] {
+            encodings
+                .try_enable(CompressionEncoding::Custom(named))
+                .unwrap();
+        }
+        // Enabling an encoding again only updates it.
+        encodings
+            .try_enable(CompressionEncoding::Custom(&NAMED[0]))
+            .unwrap();
+
+        let fifth = CompressionEncoding::Custom(&NAMED[4]);
+        assert!(encodings.try_enable(fifth).is_err());
+        encodings.enable(fifth);
+        assert!(!encodings.is_enabled(fifth));
+        assert_eq!(encodings.iter().count(), 4);
+    }
+
+    #[test]
+    fn custom_encodings_named_after_built_ins_are_rejected() {
+        static GZIP: Named = Named("gzip");
+        static IDENTITY: Named = Named("identity");
+
+        let mut encodings = EnabledCompressionEncodings::default();
+        for named in [&GZIP, &IDENTITY] {
+            let encoding = CompressionEncoding::Custom(named);
+            assert!(encodings.try_enable(encoding).is_err());
+            encodings.enable(encoding);
+        }
+        assert!(encodings.is_empty());
+
+        #[cfg(feature = "gzip")]
+        assert_ne!(
+            CompressionEncoding::Custom(&GZIP),
+            CompressionEncoding::Gzip
+        );
+    }
+
+    #[test]
+    fn custom_encodings_with_invalid_names_are_rejected() {
+        static EMPTY: Named = Named("");
+        static LIST: Named = Named("x-a,x-b");
+        static SPACE: Named = Named("x a");
+        static NON_ASCII: Named = Named("x-\u{e9}");
+        static VALID: Named = Named("x-snappy_framed.v1");
+
+        let mut encodings = EnabledCompressionEncodings::default();
+        for named in [&EMPTY, &LIST, &SPACE, &NON_ASCII] {
+            let encoding = CompressionEncoding::Custom(named);
+            let err = encodings.try_enable(encoding).unwrap_err();
+            assert!(matches!(err.kind, EnableEncodingErrorKind::InvalidName));
+            encodings.enable(encoding);
+        }
+        assert!(encodings.is_empty());
+
+        encodings
+            .try_enable(CompressionEncoding::Custom(&VALID))
+            .unwrap();
+        assert_eq!(
+            encodings.into_accept_encoding_header_value().unwrap(),
+            "x-snappy_framed.v1,identity"
+        );
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    const BOMB_LEN: usize = 16 * 1024 * 1024;
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    const MAX_LEN: usize = 64 * 1024;
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn bomb(encoding: CompressionEncoding) -> BytesMut {
+        let mut uncompressed = BytesMut::new();
+        uncompressed.put_bytes(0, BOMB_LEN);
+        let mut compressed = BytesMut::new();
+        compress(
+            CompressionSettings {
+                encoding,
+                level: CompressionLevel::Best,
+                buffer_growth_interval: 8 * 1024,
+            },
+            &mut uncompressed,
+            &mut compressed,
+            BOMB_LEN,
+        )
+        .unwrap();
+        compressed
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn assert_decompression_bounded(encoding: CompressionEncoding) {
+        let mut compressed = bomb(encoding);
+        let len = compressed.len();
+        assert!(len < MAX_LEN);
+
+        let mut out = BytesMut::new();
+        decompress(
+            CompressionSettings {
+                encoding,
+                level: CompressionLevel::Default,
+                buffer_growth_interval: 8 * 1024,
+            },
+            &mut compressed,
+            &mut out,
+            len,
+            MAX_LEN,
+        )
+        .unwrap();
+
+        assert_eq!(out.len(), MAX_LEN + 1);
+        assert!(out.capacity() < 4 * MAX_LEN);
+        assert!(compressed.is_empty());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_bomb_decompression_bounded() {
+        assert_decompression_bounded(CompressionEncoding::Gzip);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_bomb_decompression_bounded() {
+        assert_decompression_bounded(CompressionEncoding::Zstd);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_dictionary_bomb_decompression_bounded() {
+        use crate::codec::ZstdDictionary;
+        use std::sync::OnceLock;
+
+        static DICTIONARY: OnceLock<ZstdDictionary> = OnceLock::new();
+        let dictionary =
+            DICTIONARY.get_or_init(|| ZstdDictionary::new("zstd-dict-bomb", &[0; 1024]));
+        assert_decompression_bounded(dictionary.encoding());
+    }
+}
@@ -1,4 +1,4 @@
-use super::compression::{decompress, CompressionEncoding, CompressionSettings};
+use super::compression::{decompress, CompressionEncoding, CompressionLevel, CompressionSettings};
 use super::{BufferSettings, DecodeBuf, Decoder, DEFAULT_MAX_RECV_MESSAGE_SIZE, HEADER_SIZE};
 use crate::{body::BoxBody, metadata::MetadataMap, Code, Status};
 use bytes::{Buf, BufMut, BytesMut};
@@ -208,14 +208,20 @@ impl StreamingInner {
             let decode_buf = if let Some(encoding) = compression {
                 self.decompress_buf.clear();
 
+                let limit = self
+                    .max_message_size
+                    .unwrap_or(DEFAULT_MAX_RECV_MESSAGE_SIZE);
+
                 if let Err(err) = decompress(
                     CompressionSettings {
                         encoding,
+                        level: CompressionLevel::default(),
                         buffer_growth_interval: buffer_settings.buffer_size,
                     },
                     &mut self.buf,
                     &mut self.decompress_buf,
                     len,
+                    limit,
                 ) {
                     let message = if let Direction::Response(status) = self.direction {
                         format!(
@@ -228,6 +234,12 @@ impl StreamingInner {
                     return Err(Status::new(Code::Internal, message));
                 }
                 let decompressed_len = self.decompress_buf.len();
+                if decompressed_len > limit {
+                    return Err(Status::resource_exhausted(format!(
+                        "Error, decompressed message length too large: the limit is: {} bytes",
+                        limit
+                    )));
+                }
                 DecodeBuf::new(&mut self.decompress_buf, decompressed_len)
             } else {
                 DecodeBuf::new(&mut self.buf, len)
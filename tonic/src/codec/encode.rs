@@ -1,5 +1,5 @@
 use super::compression::{
-    compress, CompressionEncoding, CompressionSettings, SingleMessageCompressionOverride,
+    compress, CompressionConfig, CompressionSettings, SingleMessageCompressionOverride,
 };
 use super::{BufferSettings, EncodeBuf, Encoder, DEFAULT_MAX_SEND_MESSAGE_SIZE, HEADER_SIZE};
 use crate::{Code, Status};
@@ -16,7 +16,7 @@ use tokio_stream::{Stream, StreamExt};
 pub(crate) fn encode_server<T, U>(
     encoder: T,
     source: U,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<CompressionConfig>,
     compression_override: SingleMessageCompressionOverride,
     max_message_size: Option<usize>,
 ) -> EncodeBody<impl Stream<Item = Result<Bytes, Status>>>
@@ -27,7 +27,7 @@ where
     let stream = EncodedBytes::new(
         encoder,
         source.fuse(),
-        compression_encoding,
+        compression,
         compression_override,
         max_message_size,
     );
@@ -38,7 +38,7 @@ where
 pub(crate) fn encode_client<T, U>(
     encoder: T,
     source: U,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<CompressionConfig>,
     max_message_size: Option<usize>,
 ) -> EncodeBody<impl Stream<Item = Result<Bytes, Status>>>
 where
@@ -48,7 +48,7 @@ where
     let stream = EncodedBytes::new(
         encoder,
         source.fuse().map(Ok),
-        compression_encoding,
+        compression,
         SingleMessageCompressionOverride::default(),
         max_message_size,
     );
@@ -70,7 +70,7 @@ where
     #[pin]
     source: U,
     encoder: T,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<CompressionConfig>,
     max_message_size: Option<usize>,
     buf: BytesMut,
     uncompression_buf: BytesMut,
@@ -86,21 +86,20 @@ where
     fn new(
         encoder: T,
         source: U,
-        compression_encoding: Option<CompressionEncoding>,
+        compression: Option<CompressionConfig>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
     ) -> Self {
         let buffer_settings = encoder.buffer_settings();
         let buf = BytesMut::with_capacity(buffer_settings.buffer_size);
 
-        let compression_encoding =
-            if compression_override == SingleMessageCompressionOverride::Disable {
-                None
-            } else {
-                compression_encoding
-            };
+        let compression = if compression_override == SingleMessageCompressionOverride::Disable {
+            None
+        } else {
+            compression
+        };
 
-        let uncompression_buf = if compression_encoding.is_some() {
+        let uncompression_buf = if compression.is_some() {
             BytesMut::with_capacity(buffer_settings.buffer_size)
         } else {
             BytesMut::new()
@@ -109,7 +108,7 @@ where
         Self {
             source,
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
             buf,
             uncompression_buf,
@@ -129,7 +128,7 @@ where
         let EncodedBytesProj {
             mut source,
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
             buf,
             uncompression_buf,
@@ -157,7 +156,7 @@ where
                         encoder,
                         buf,
                         uncompression_buf,
-                        *compression_encoding,
+                        *compression,
                         *max_message_size,
                         buffer_settings,
                         item,
@@ -185,7 +184,7 @@ fn encode_item<T>(
     encoder: &mut T,
     buf: &mut BytesMut,
     uncompression_buf: &mut BytesMut,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<CompressionConfig>,
     max_message_size: Option<usize>,
     buffer_settings: BufferSettings,
     item: T::Item,
@@ -200,7 +199,7 @@ where
         buf.advance_mut(HEADER_SIZE);
     }
 
-    if let Some(encoding) = compression_encoding {
+    let compressed = if let Some(config) = compression {
         uncompression_buf.clear();
 
         encoder
@@ -209,28 +208,37 @@ where
 
         let uncompressed_len = uncompression_buf.len();
 
-        compress(
-            CompressionSettings {
-                encoding,
-                buffer_growth_interval: buffer_settings.buffer_size,
-            },
-            uncompression_buf,
-            buf,
-            uncompressed_len,
-        )
-        .map_err(|err| Status::internal(format!("Error compressing: {}", err)))?;
+        if uncompressed_len < config.min_size {
+            // too small to be worth compressing, send it as is
+            buf.extend_from_slice(uncompression_buf);
+            false
+        } else {
+            compress(
+                CompressionSettings {
+                    encoding: config.encoding,
+                    level: config.level,
+                    buffer_growth_interval: buffer_settings.buffer_size,
+                },
+                uncompression_buf,
+                buf,
+                uncompressed_len,
+            )
+            .map_err(|err| Status::internal(format!("Error compressing: {}", err)))?;
+            true
+        }
     } else {
         encoder
             .encode(item, &mut EncodeBuf::new(buf))
             .map_err(|err| Status::internal(format!("Error encoding: {}", err)))?;
-    }
+        false
+    };
 
     // now that we know length, we can write the header
-    finish_encoding(compression_encoding, max_message_size, &mut buf[offset..])
+    finish_encoding(compressed, max_message_size, &mut buf[offset..])
 }
 
 fn finish_encoding(
-    compression_encoding: Option<CompressionEncoding>,
+    compressed: bool,
     max_message_size: Option<usize>,
     buf: &mut [u8],
 ) -> Result<(), Status> {
@@ -253,7 +261,7 @@ fn finish_encoding(
     }
     {
         let mut buf = &mut buf[..HEADER_SIZE];
-        buf.put_u8(compression_encoding.is_some() as u8);
+        buf.put_u8(compressed as u8);
         buf.put_u32(len as u32);
     }
 
@@ -9,6 +9,8 @@ mod decode;
 mod encode;
 #[cfg(feature = "prost")]
 mod prost;
+#[cfg(feature = "zstd")]
+mod zstd_dict;
 
 use crate::Status;
 use std::io;
@@ -16,10 +18,15 @@ use std::io;
 pub(crate) use self::encode::{encode_client, encode_server};
 
 pub use self::buffer::{DecodeBuf, EncodeBuf};
-pub use self::compression::{CompressionEncoding, EnabledCompressionEncodings};
+pub use self::compression::{
+    CompressionConfig, CompressionEncoding, CompressionLevel, Compressor, EnableEncodingError,
+    EnabledCompressionEncodings,
+};
 pub use self::decode::Streaming;
 #[cfg(feature = "prost")]
 pub use self::prost::ProstCodec;
+#[cfg(feature = "zstd")]
+pub use self::zstd_dict::ZstdDictionary;
 
 /// Unless overridden, this is the buffer size used for encoding requests.
 /// This is spent per-rpc, so you may wish to adjust it. The default is
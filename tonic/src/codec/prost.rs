@@ -222,6 +222,53 @@ mod tests {
         assert_eq!(actual.message(), expected.message());
     }
 
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn decode_decompressed_message_size_exceeded() {
+        use crate::codec::CompressionEncoding;
+        use flate2::{read::GzEncoder, Compression};
+        use std::io::Read;
+
+        let decoder = MockDecoder::default();
+
+        let mut compressed = Vec::new();
+        GzEncoder::new(
+            std::io::repeat(0).take(16 * 1024 * 1024),
+            Compression::best(),
+        )
+        .read_to_end(&mut compressed)
+        .unwrap();
+        assert!(compressed.len() < MAX_MESSAGE_SIZE);
+
+        let mut buf = BytesMut::new();
+
+        buf.reserve(compressed.len() + HEADER_SIZE);
+        buf.put_u8(1);
+        buf.put_u32(compressed.len() as u32);
+
+        buf.put(&compressed[..]);
+
+        let body = body::MockBody::new(&buf[..], buf.len(), 0);
+
+        let mut stream = Streaming::new_request(
+            decoder,
+            body,
+            Some(CompressionEncoding::Gzip),
+            Some(MAX_MESSAGE_SIZE),
+        );
+
+        let actual = stream.message().await.unwrap_err();
+
+        assert_eq!(actual.code(), Code::ResourceExhausted);
+        assert_eq!(
+            actual.message(),
+            format!(
+                "Error, decompressed message length too large: the limit is: {} bytes",
+                MAX_MESSAGE_SIZE
+            )
+        );
+    }
+
     #[tokio::test]
     async fn encode() {
         let encoder = MockEncoder::default();
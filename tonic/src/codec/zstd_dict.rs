@@ -0,0 +1,185 @@
+use super::compression::{copy_limited, CompressionEncoding, Compressor};
+use bytes::{BufMut, BytesMut};
+use std::{fmt, io};
+use zstd::{
+    dict::{DecoderDictionary, EncoderDictionary},
+    stream::read::{Decoder, Encoder},
+};
+
+/// Zstd compression with a pre-trained dictionary, which compresses small
+/// and repetitive messages much better than [`CompressionEncoding::Zstd`].
+///
+/// Both peers need the same dictionary, so it is used as a custom encoding
+/// with its own name. Enabling [`CompressionEncoding::Zstd`] next to it lets
+/// peers without the dictionary still receive compressed messages.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::OnceLock;
+/// use tonic::codec::{CompressionEncoding, ZstdDictionary};
+///
+/// static DICTIONARY: OnceLock<ZstdDictionary> = OnceLock::new();
+///
+/// let samples: Vec<Vec<u8>> = (0..1000)
+///     .map(|i| format!("{{\"id\": {}, \"status\": \"ok\"}}", i).into_bytes())
+///     .collect();
+/// let dictionary = ZstdDictionary::train(&samples, 4096).unwrap();
+///
+/// let encoding: CompressionEncoding = DICTIONARY
+///     .get_or_init(|| ZstdDictionary::new("zstd-dict-v1", &dictionary))
+///     .encoding();
+/// ```
+pub struct ZstdDictionary {
+    name: &'static str,
+    encoder: EncoderDictionary<'static>,
+    decoder: DecoderDictionary<'static>,
+}
+
+impl ZstdDictionary {
+    /// Creates the encoding named `name`, compressing with `dictionary` at the
+    /// default level.
+    ///
+    /// The name is sent in the `grpc-encoding` and `grpc-accept-encoding`
+    /// headers, so it should change with the dictionary. It must be a valid
+    /// encoding name, as described in [`Compressor::name`]: encodings with
+    /// other names are rejected by
+    /// [`EnabledCompressionEncodings::try_enable`], and ignored wherever they
+    /// are enabled.
+    ///
+    /// [`EnabledCompressionEncodings::try_enable`]: super::EnabledCompressionEncodings::try_enable
+    pub fn new(name: &'static str, dictionary: &[u8]) -> Self {
+        Self::with_level(name, dictionary, zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Creates the encoding named `name`, compressing with `dictionary` at
+    /// `level`.
+    pub fn with_level(name: &'static str, dictionary: &[u8], level: i32) -> Self {
+        Self {
+            name,
+            encoder: EncoderDictionary::copy(dictionary, level),
+            decoder: DecoderDictionary::copy(dictionary),
+        }
+    }
+
+    /// Trains a dictionary of up to `max_size` bytes from sample messages, as
+    /// encoded before compression.
+    ///
+    /// Samples should be representative of the messages sent; a few thousand
+    /// of them usually make a good dictionary.
+    pub fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> io::Result<Vec<u8>> {
+        zstd::dict::from_samples(samples, max_size)
+    }
+
+    /// Returns the [`CompressionEncoding`] compressing with this dictionary.
+    ///
+    /// Encodings borrow their compressor for as long as the process runs, so
+    /// the dictionary is kept in a `static`, or leaked once.
+    pub fn encoding(&'static self) -> CompressionEncoding {
+        CompressionEncoding::Custom(self)
+    }
+}
+
+impl Compressor for ZstdDictionary {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compress(&self, input: &[u8], out: &mut BytesMut) -> io::Result<()> {
+        let mut encoder = Encoder::with_prepared_dictionary(input, &self.encoder)?;
+        io::copy(&mut encoder, &mut out.writer())?;
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], out: &mut BytesMut, max_len: usize) -> io::Result<()> {
+        let decoder = Decoder::with_prepared_dictionary(input, &self.decoder)?;
+        copy_limited(decoder, &mut out.writer(), max_len)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ZstdDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdDictionary")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::EnabledCompressionEncodings;
+    use std::sync::OnceLock;
+
+    fn samples() -> Vec<Vec<u8>> {
+        (0..1000)
+            .map(|i| {
+                let region = ["eu-west", "us-east", "ap-south"][i % 3];
+                format!(
+                    "{{\"id\": {}, \"service\": \"telemetry\", \"region\": \"{}\"}}",
+                    i, region
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_with_trained_dictionary() {
+        let samples = samples();
+        let dictionary = ZstdDictionary::train(&samples, 4096).unwrap();
+        let compressor = ZstdDictionary::new("zstd-dict-test", &dictionary);
+
+        let message = &samples[42];
+        let mut compressed = BytesMut::new();
+        compressor.compress(message, &mut compressed).unwrap();
+
+        let mut plain = Vec::new();
+        zstd::stream::copy_encode(&message[..], &mut plain, zstd::DEFAULT_COMPRESSION_LEVEL)
+            .unwrap();
+        assert!(compressed.len() < plain.len());
+
+        let mut decompressed = BytesMut::new();
+        compressor
+            .decompress(&compressed, &mut decompressed, usize::MAX)
+            .unwrap();
+        assert_eq!(&decompressed[..], &message[..]);
+    }
+
+    #[test]
+    fn invalid_names_cannot_be_enabled() {
+        static INVALID: OnceLock<ZstdDictionary> = OnceLock::new();
+        static VALID: OnceLock<ZstdDictionary> = OnceLock::new();
+        let dictionary = ZstdDictionary::train(&samples(), 4096).unwrap();
+
+        let mut encodings = EnabledCompressionEncodings::default();
+        let invalid = INVALID.get_or_init(|| ZstdDictionary::new("zstd dict, v1", &dictionary));
+        assert!(encodings.try_enable(invalid.encoding()).is_err());
+        encodings.enable(invalid.encoding());
+        assert!(encodings.is_empty());
+
+        let valid = VALID.get_or_init(|| ZstdDictionary::new("zstd-dict-v1", &dictionary));
+        encodings.try_enable(valid.encoding()).unwrap();
+        assert_eq!(
+            encodings.into_accept_encoding_header_value().unwrap(),
+            "zstd-dict-v1,identity"
+        );
+    }
+
+    #[test]
+    fn rejects_other_dictionary() {
+        let samples = samples();
+        let dictionary = ZstdDictionary::train(&samples, 4096).unwrap();
+        let compressor = ZstdDictionary::new("zstd-dict-test", &dictionary);
+
+        let mut compressed = BytesMut::new();
+        compressor.compress(&samples[0], &mut compressed).unwrap();
+
+        let other = ZstdDictionary::new("zstd-dict-other", b"not the same dictionary at all");
+        let mut decompressed = BytesMut::new();
+        assert!(other
+            .decompress(&compressed, &mut decompressed, usize::MAX)
+            .is_err());
+    }
+}
@@ -21,3 +21,9 @@ impl<'a> GrpcMethod<'a> {
         self.method
     }
 }
+
+/// The largest response message a call may receive, set by the channel from
+/// the method's service config.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "service-config"), allow(dead_code))]
+pub(crate) struct MaxDecodingMessageSize(pub(crate) usize);
@@ -20,19 +20,30 @@
 //! implementation based on [`hyper`], [`tower`] and [`tokio`]. Enabled by default.
 //! - `server`: Enables just the full featured server portion of the `transport` feature.
 //! - `channel`: Enables just the full featured channel portion of the `transport` feature.
+//! - `service-config`: Enables configuring channels with a gRPC service config JSON
+//! document. Depends on [serde_json]. Not enabled by default.
 //! - `codegen`: Enables all the required exports and optional dependencies required
 //! for [`tonic-build`]. Enabled by default.
-//! - `tls`: Enables the `rustls` based TLS options for the `transport` feature. Not
-//! enabled by default.
+//! - `tls-ring`: Enables the `rustls` based TLS options for the `transport` feature,
+//! using the `ring` crypto provider. Not enabled by default.
+//! - `tls-aws-lc`: Enables the `rustls` based TLS options for the `transport` feature,
+//! using the `aws-lc-rs` crypto provider. Not enabled by default. If `tls-ring` is enabled
+//! too, `ring` is used unless a provider is installed or configured.
+//! - `tls`: Alias of `tls-ring`.
 //! - `tls-roots`: Adds system trust roots to `rustls`-based gRPC clients using the
-//! `rustls-native-certs` crate. Not enabled by default. `tls` must be enabled to use
-//! `tls-roots`.
+//! `rustls-native-certs` crate. Not enabled by default. Enables `tls-ring`.
 //! - `tls-webpki-roots`: Add the standard trust roots from the `webpki-roots` crate to
-//! `rustls`-based gRPC clients. Not enabled by default.
+//! `rustls`-based gRPC clients. Not enabled by default. Enables `tls-ring`.
 //! - `prost`: Enables the [`prost`] based gRPC [`Codec`] implementation.
 //! - `gzip`: Enables compressing requests, responses, and streams.
 //! Depends on [flate2]. Not enabled by default.
 //! Replaces the `compression` flag from earlier versions of `tonic` (<= 0.7).
+//! - `deflate`: Enables the `deflate` compression encoding. Depends on [flate2].
+//! Not enabled by default.
+//! - `zstd`: Enables the `zstd` compression encoding. Depends on [zstd].
+//! Not enabled by default.
+//! - `snappy`: Enables the `snappy` compression encoding, a fast codec with a
+//! lower compression ratio. Depends on [snap]. Not enabled by default.
 //!
 //! # Structure
 //!
@@ -51,7 +62,7 @@
 //! It also provides many of the features that the core gRPC libraries provide such as load balancing,
 //! tls, timeouts, and many more. This implementation can also be used as a reference implementation
 //! to build even more feature rich clients and servers. This module also provides the ability to
-//! enable TLS using [`rustls`], via the `tls` feature flag.
+//! enable TLS using [`rustls`], via the `tls-ring` or `tls-aws-lc` feature flags.
 //!
 //! # Code generated client/server configuration
 //!
@@ -76,6 +87,9 @@
 //! [`client`]: client/index.html
 //! [`transport`]: transport/index.html
 //! [flate2]: https://crates.io/crates/flate2
+//! [serde_json]: https://crates.io/crates/serde_json
+//! [snap]: https://crates.io/crates/snap
+//! [zstd]: https://crates.io/crates/zstd
 
 #![recursion_limit = "256"]
 #![warn(
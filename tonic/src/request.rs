@@ -1,15 +1,15 @@
 use crate::metadata::{MetadataMap, MetadataValue};
 #[cfg(feature = "server")]
 use crate::transport::server::TcpConnectInfo;
-#[cfg(all(feature = "server", feature = "tls"))]
-use crate::transport::server::TlsConnectInfo;
+#[cfg(all(feature = "server", feature = "_tls-any"))]
+use crate::transport::{san_uris, server::TlsConnectInfo};
 use http::Extensions;
 #[cfg(feature = "server")]
 use std::net::SocketAddr;
-#[cfg(all(feature = "server", feature = "tls"))]
+#[cfg(all(feature = "server", feature = "_tls-any"))]
 use std::sync::Arc;
 use std::time::Duration;
-#[cfg(all(feature = "server", feature = "tls"))]
+#[cfg(all(feature = "server", feature = "_tls-any"))]
 use tokio_rustls::rustls::pki_types::CertificateDer;
 use tokio_stream::Stream;
 
@@ -218,7 +218,7 @@ impl<T> Request<T> {
             .get::<TcpConnectInfo>()
             .and_then(|i| i.local_addr());
 
-        #[cfg(feature = "tls")]
+        #[cfg(feature = "_tls-any")]
         let addr = addr.or_else(|| {
             self.extensions()
                 .get::<TlsConnectInfo<TcpConnectInfo>>()
@@ -240,7 +240,7 @@ impl<T> Request<T> {
             .get::<TcpConnectInfo>()
             .and_then(|i| i.remote_addr());
 
-        #[cfg(feature = "tls")]
+        #[cfg(feature = "_tls-any")]
         let addr = addr.or_else(|| {
             self.extensions()
                 .get::<TlsConnectInfo<TcpConnectInfo>>()
@@ -256,13 +256,26 @@ impl<T> Request<T> {
     /// and is mostly used for mTLS. This currently only returns
     /// `Some` on the server side of the `transport` server with
     /// TLS enabled connections.
-    #[cfg(all(feature = "server", feature = "tls"))]
+    #[cfg(all(feature = "server", feature = "_tls-any"))]
     pub fn peer_certs(&self) -> Option<Arc<Vec<CertificateDer<'static>>>> {
         self.extensions()
             .get::<TlsConnectInfo<TcpConnectInfo>>()
             .and_then(|i| i.peer_certs())
     }
 
+    /// Get the URIs in the subject alternative names of the connected
+    /// client's certificate, such as its SPIFFE ID.
+    ///
+    /// This returns `None` under the same conditions as
+    /// [`Request::peer_certs`]. See also [`san_uris`].
+    ///
+    /// [`san_uris`]: crate::transport::san_uris
+    #[cfg(all(feature = "server", feature = "_tls-any"))]
+    pub fn peer_san_uris(&self) -> Option<Vec<String>> {
+        let certs = self.peer_certs()?;
+        Some(certs.first().map(san_uris).unwrap_or_default())
+    }
+
     /// Set the max duration the request is allowed to take.
     ///
     /// Requires the server to support the `grpc-timeout` metadata, which Tonic does.
@@ -394,7 +407,7 @@ mod sealed {
     pub trait Sealed {}
 }
 
-fn duration_to_grpc_timeout(duration: Duration) -> String {
+pub(crate) fn duration_to_grpc_timeout(duration: Duration) -> String {
     fn try_format<T: Into<u128>>(
         duration: Duration,
         unit: char,
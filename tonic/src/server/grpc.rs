@@ -1,5 +1,6 @@
 use crate::codec::compression::{
-    CompressionEncoding, EnabledCompressionEncodings, SingleMessageCompressionOverride,
+    CompressionConfig, CompressionEncoding, EnabledCompressionEncodings,
+    SingleMessageCompressionOverride,
 };
 use crate::metadata::GRPC_CONTENT_TYPE;
 use crate::{
@@ -115,8 +116,13 @@ where
     ///
     /// let service = ExampleServer::new(Svc).send_compressed(CompressionEncoding::Gzip);
     /// ```
-    pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-        self.send_compression_encodings.enable(encoding);
+    ///
+    /// A [`CompressionConfig`] can be given instead of the encoding, to set the compression level
+    /// and the size under which responses are sent uncompressed.
+    ///
+    /// [`CompressionConfig`]: crate::codec::CompressionConfig
+    pub fn send_compressed(mut self, config: impl Into<CompressionConfig>) -> Self {
+        self.send_compression_encodings.enable(config);
         self
     }
 
@@ -188,13 +194,11 @@ where
     ) -> Self {
         let mut this = self;
 
-        for &encoding in CompressionEncoding::encodings() {
-            if accept_encodings.is_enabled(encoding) {
-                this = this.accept_compressed(encoding);
-            }
-            if send_encodings.is_enabled(encoding) {
-                this = this.send_compressed(encoding);
-            }
+        for config in accept_encodings.iter() {
+            this = this.accept_compressed(config.encoding);
+        }
+        for config in send_encodings.iter() {
+            this = this.send_compressed(config);
         }
 
         this
@@ -422,7 +426,7 @@ where
     fn map_response<B>(
         &mut self,
         response: Result<crate::Response<B>, Status>,
-        accept_encoding: Option<CompressionEncoding>,
+        accept_encoding: Option<CompressionConfig>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
     ) -> http::Response<BoxBody>
@@ -438,12 +442,11 @@ where
             .headers
             .insert(http::header::CONTENT_TYPE, GRPC_CONTENT_TYPE);
 
-        #[cfg(any(feature = "gzip", feature = "zstd"))]
-        if let Some(encoding) = accept_encoding {
+        if let Some(config) = accept_encoding {
             // Set the content encoding
             parts.headers.insert(
                 crate::codec::compression::ENCODING_HEADER,
-                encoding.into_header_value(),
+                config.encoding.into_header_value(),
             );
         }
 
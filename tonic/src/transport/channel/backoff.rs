@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+/// Backoff between attempts to reconnect a [`Channel`](super::Channel).
+///
+/// Follows the [gRPC connection backoff protocol][spec]: after a failed
+/// attempt the channel waits for the current backoff before connecting
+/// again, and the backoff grows by [`multiplier`](Self::multiplier) up to
+/// [`max_backoff`](Self::max_backoff) with every failure. Each backoff is
+/// randomized by [`jitter`](Self::jitter) so that clients disconnected at the
+/// same time do not reconnect at the same time. A successful connection
+/// resets the backoff.
+///
+/// Calls made while the channel is backing off fail with
+/// [`Unavailable`](crate::Code::Unavailable), unless they
+/// [wait for ready](super::WaitForReady).
+///
+/// ```
+/// # use tonic::transport::{channel::ReconnectBackoff, Endpoint};
+/// # use std::time::Duration;
+/// # let mut builder = Endpoint::from_static("https://example.com");
+/// builder.reconnect_backoff(
+///     ReconnectBackoff::new()
+///         .initial_backoff(Duration::from_millis(500))
+///         .max_backoff(Duration::from_secs(30)),
+/// );
+/// ```
+///
+/// [spec]: https://github.com/grpc/grpc/blob/master/doc/connection-backoff.md
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    pub(crate) initial_backoff: Duration,
+    pub(crate) multiplier: f64,
+    pub(crate) jitter: f64,
+    pub(crate) max_backoff: Duration,
+    pub(crate) min_connect_timeout: Duration,
+}
+
+impl ReconnectBackoff {
+    /// Creates a new `ReconnectBackoff` with the default settings of the
+    /// gRPC connection backoff protocol.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the backoff after the first failed attempt.
+    ///
+    /// Defaults to 1 second.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        ReconnectBackoff {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the factor the backoff grows by after every failed attempt.
+    ///
+    /// Defaults to 1.6.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        ReconnectBackoff { multiplier, ..self }
+    }
+
+    /// Sets how much each backoff is randomized, as a fraction of the
+    /// backoff. A jitter of 0.2 picks a backoff between 80% and 120% of the
+    /// current one. The jitter is clamped between 0 and 1, and NaN disables
+    /// it.
+    ///
+    /// Defaults to 0.2.
+    pub fn jitter(self, jitter: f64) -> Self {
+        let jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        ReconnectBackoff { jitter, ..self }
+    }
+
+    /// Sets the upper bound on the backoff.
+    ///
+    /// Defaults to 120 seconds.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        ReconnectBackoff {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the least time an attempt to connect is given before it is
+    /// abandoned. Attempts are otherwise given until the end of the current
+    /// backoff.
+    ///
+    /// Defaults to 20 seconds.
+    pub fn min_connect_timeout(self, min_connect_timeout: Duration) -> Self {
+        ReconnectBackoff {
+            min_connect_timeout,
+            ..self
+        }
+    }
+
+    /// The backoff following `backoff`.
+    pub(crate) fn next(&self, backoff: Duration) -> Duration {
+        let next = backoff.as_secs_f64() * self.multiplier;
+
+        match Duration::try_from_secs_f64(next.max(0.0)) {
+            Ok(next) if next < self.max_backoff => next,
+            _ => self.max_backoff,
+        }
+    }
+
+    /// Randomize `backoff` by the jitter, given a uniformly distributed
+    /// `random` number in `[0, 1]`, saturating at `Duration::MAX`.
+    pub(crate) fn jittered(&self, backoff: Duration, random: f64) -> Duration {
+        let factor = 1.0 + self.jitter * (2.0 * random - 1.0);
+        Duration::try_from_secs_f64(backoff.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+    }
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            multiplier: 1.6,
+            jitter: 0.2,
+            max_backoff: Duration::from_secs(120),
+            min_connect_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_to_max() {
+        let backoff = ReconnectBackoff::new()
+            .initial_backoff(Duration::from_secs(1))
+            .multiplier(2.0)
+            .max_backoff(Duration::from_secs(5));
+
+        let mut current = backoff.initial_backoff;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            current = backoff.next(current);
+            seen.push(current.as_secs());
+        }
+        assert_eq!(seen, [2, 4, 5, 5]);
+    }
+
+    #[test]
+    fn jitter_bounds() {
+        let backoff = ReconnectBackoff::new().jitter(0.2);
+        let current = Duration::from_secs(10);
+
+        assert_eq!(backoff.jittered(current, 0.0), Duration::from_secs(8));
+        assert_eq!(backoff.jittered(current, 0.5), Duration::from_secs(10));
+        assert_eq!(backoff.jittered(current, 1.0), Duration::from_secs(12));
+    }
+
+    #[test]
+    fn nan_jitter_is_disabled() {
+        let backoff = ReconnectBackoff::new().jitter(f64::NAN);
+        let current = Duration::from_secs(10);
+
+        assert_eq!(backoff.jitter, 0.0);
+        assert_eq!(backoff.jittered(current, 1.0), current);
+    }
+
+    #[test]
+    fn long_backoffs_saturate() {
+        let backoff = ReconnectBackoff::new()
+            .initial_backoff(Duration::MAX)
+            .multiplier(2.0)
+            .jitter(1.0)
+            .max_backoff(Duration::MAX);
+
+        assert_eq!(backoff.next(Duration::MAX), Duration::MAX);
+        assert_eq!(backoff.jittered(Duration::MAX, 1.0), Duration::MAX);
+    }
+}
@@ -0,0 +1,47 @@
+/// How a balanced [`Channel`](super::Channel) picks the endpoint each call is
+/// sent to.
+///
+/// Only endpoints that are ready to accept a call are considered, endpoints
+/// that are still connecting or whose connection failed are skipped until
+/// they become ready again.
+///
+/// ```
+/// # use tonic::transport::{channel::LoadBalancingPolicy, Channel, Endpoint};
+/// # fn f() {
+/// let endpoints = ["http://[::1]:50051", "http://[::1]:50052"]
+///     .into_iter()
+///     .map(Endpoint::from_static);
+/// let channel = Channel::balance_list(endpoints)
+///     .load_balancing_policy(LoadBalancingPolicy::RoundRobin);
+/// # drop(channel);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum LoadBalancingPolicy {
+    /// Send every call to the first ready endpoint, in the order endpoints
+    /// were added to the channel.
+    ///
+    /// Calls stick to one endpoint and only fail over to the next one when it
+    /// stops being ready.
+    PickFirst,
+    /// Send calls to each ready endpoint in turn.
+    RoundRobin,
+    /// Pick two ready endpoints at random and send the call to the one with
+    /// fewer calls in flight.
+    #[default]
+    PowerOfTwoChoices,
+}
+
+impl LoadBalancingPolicy {
+    /// The policy for a name used in a gRPC service config: `pick_first`,
+    /// `round_robin`, or `p2c` for [`PowerOfTwoChoices`](Self::PowerOfTwoChoices).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pick_first" => Some(LoadBalancingPolicy::PickFirst),
+            "round_robin" => Some(LoadBalancingPolicy::RoundRobin),
+            "p2c" => Some(LoadBalancingPolicy::PowerOfTwoChoices),
+            _ => None,
+        }
+    }
+}
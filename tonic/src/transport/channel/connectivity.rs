@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::watch;
+
+/// The connectivity state of a [`Channel`](super::Channel).
+///
+/// These are the states defined by the [gRPC connectivity semantics][spec].
+/// A balanced channel is [`Ready`](Self::Ready) when any of its endpoints is,
+/// and otherwise reports the most hopeful state of its endpoints.
+///
+/// [spec]: https://github.com/grpc/grpc/blob/master/doc/connectivity-semantics-and-api.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectivityState {
+    /// The channel is not connected and not trying to connect. Channels
+    /// created with [`Endpoint::connect_lazy`](super::Endpoint::connect_lazy)
+    /// start in this state and connect when the first call is made.
+    Idle,
+    /// The channel is establishing a connection.
+    Connecting,
+    /// The channel is connected and can send calls.
+    Ready,
+    /// The last attempt to connect failed. The channel tries again when the
+    /// next call is made.
+    TransientFailure,
+    /// The channel's connections have been shut down and it will not connect
+    /// again.
+    Shutdown,
+}
+
+impl ConnectivityState {
+    /// The order in which states are preferred when aggregating the states
+    /// of several connections.
+    fn rank(self) -> u8 {
+        match self {
+            ConnectivityState::Ready => 0,
+            ConnectivityState::Connecting => 1,
+            ConnectivityState::Idle => 2,
+            ConnectivityState::TransientFailure => 3,
+            ConnectivityState::Shutdown => 4,
+        }
+    }
+}
+
+/// Aggregates the states reported by the connections of a channel into the
+/// state of the channel.
+///
+/// The channel enters [`ConnectivityState::Shutdown`] once the tracker and
+/// every reporter created from it have been dropped.
+#[derive(Clone)]
+pub(crate) struct ConnectivityTracker {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    tx: watch::Sender<ConnectivityState>,
+    states: Mutex<HashMap<usize, ConnectivityState>>,
+    next_id: AtomicUsize,
+}
+
+impl ConnectivityTracker {
+    pub(crate) fn new() -> (Self, watch::Receiver<ConnectivityState>) {
+        let (tx, rx) = watch::channel(ConnectivityState::Idle);
+        let shared = Arc::new(Shared {
+            tx,
+            states: Mutex::new(HashMap::new()),
+            next_id: AtomicUsize::new(0),
+        });
+
+        (Self { shared }, rx)
+    }
+
+    /// Creates a reporter for a new connection, which starts out idle.
+    pub(crate) fn reporter(&self) -> ConnectivityReporter {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let reporter = ConnectivityReporter {
+            id,
+            shared: self.shared.clone(),
+        };
+        reporter.set(ConnectivityState::Idle);
+        reporter
+    }
+}
+
+impl Shared {
+    fn update(&self, states: &HashMap<usize, ConnectivityState>) {
+        let state = states
+            .values()
+            .copied()
+            .min_by_key(|state| state.rank())
+            .unwrap_or(ConnectivityState::Idle);
+
+        self.tx.send_if_modified(|current| {
+            let modified = *current != state;
+            *current = state;
+            modified
+        });
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        self.tx.send_replace(ConnectivityState::Shutdown);
+    }
+}
+
+/// Reports the state of one connection to a [`ConnectivityTracker`].
+pub(crate) struct ConnectivityReporter {
+    id: usize,
+    shared: Arc<Shared>,
+}
+
+impl ConnectivityReporter {
+    pub(crate) fn set(&self, state: ConnectivityState) {
+        let mut states = self.shared.states.lock().unwrap();
+        states.insert(self.id, state);
+        self.shared.update(&states);
+    }
+
+    /// Sets the state to `to` if it currently is `from`.
+    pub(crate) fn replace(&self, from: ConnectivityState, to: ConnectivityState) {
+        let mut states = self.shared.states.lock().unwrap();
+        if states.get(&self.id) == Some(&from) {
+            states.insert(self.id, to);
+            self.shared.update(&states);
+        }
+    }
+}
+
+impl Drop for ConnectivityReporter {
+    fn drop(&mut self) {
+        let mut states = self.shared.states.lock().unwrap();
+        states.remove(&self.id);
+        self.shared.update(&states);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_connection_states() {
+        let (tracker, rx) = ConnectivityTracker::new();
+        let a = tracker.reporter();
+        let b = tracker.reporter();
+        assert_eq!(*rx.borrow(), ConnectivityState::Idle);
+
+        a.set(ConnectivityState::TransientFailure);
+        b.set(ConnectivityState::Connecting);
+        assert_eq!(*rx.borrow(), ConnectivityState::Connecting);
+
+        b.set(ConnectivityState::Ready);
+        assert_eq!(*rx.borrow(), ConnectivityState::Ready);
+
+        drop(b);
+        assert_eq!(*rx.borrow(), ConnectivityState::TransientFailure);
+
+        drop((a, tracker));
+        assert_eq!(*rx.borrow(), ConnectivityState::Shutdown);
+    }
+}
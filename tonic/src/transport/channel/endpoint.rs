@@ -1,14 +1,19 @@
-#[cfg(feature = "tls")]
+use super::resolver::ResolveNow;
+#[cfg(feature = "_tls-any")]
 use super::service::TlsConnector;
-use super::service::{self, Executor, SharedExec};
-use super::Channel;
-#[cfg(feature = "tls")]
+use super::service::{self, Executor, RetryConfig, SharedExec};
+#[cfg(feature = "_tls-any")]
 use super::ClientTlsConfig;
+#[cfg(feature = "service-config")]
+use super::ServiceConfig;
+use super::{Channel, ReconnectBackoff, RetryPolicy};
 use crate::transport::Error;
 use bytes::Bytes;
 use http::{uri::Uri, HeaderValue};
 use hyper::rt;
 use hyper_util::client::legacy::connect::HttpConnector;
+#[cfg(feature = "service-config")]
+use std::sync::Arc;
 use std::{fmt, future::Future, pin::Pin, str::FromStr, time::Duration};
 use tower_service::Service;
 
@@ -23,7 +28,7 @@ pub struct Endpoint {
     pub(crate) timeout: Option<Duration>,
     pub(crate) concurrency_limit: Option<usize>,
     pub(crate) rate_limit: Option<(u64, Duration)>,
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     pub(crate) tls: Option<TlsConnector>,
     pub(crate) buffer_size: Option<usize>,
     pub(crate) init_stream_window_size: Option<u32>,
@@ -36,6 +41,15 @@ pub struct Endpoint {
     pub(crate) connect_timeout: Option<Duration>,
     pub(crate) http2_adaptive_window: Option<bool>,
     pub(crate) executor: SharedExec,
+    pub(crate) resolve_now: Option<ResolveNow>,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) retry_throttling: Option<(u32, f64)>,
+    pub(crate) health_check: Option<String>,
+    pub(crate) wait_for_ready: bool,
+    pub(crate) reconnect_backoff: Option<ReconnectBackoff>,
+    pub(crate) idle_timeout: Option<Duration>,
+    #[cfg(feature = "service-config")]
+    pub(crate) service_config: Option<Arc<ServiceConfig>>,
 }
 
 impl Endpoint {
@@ -204,6 +218,154 @@ impl Endpoint {
         }
     }
 
+    /// Retry failed calls according to `policy`.
+    ///
+    /// Retries are disabled by default. See [`RetryPolicy`] for which calls
+    /// are eligible for a retry. Balanced channels ignore the retry policy of
+    /// their endpoints, use [`Channel::retry_policy`] instead.
+    ///
+    /// ```
+    /// # use tonic::transport::{channel::RetryPolicy, Endpoint};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.retry_policy(RetryPolicy::new().max_attempts(3));
+    /// ```
+    pub fn retry_policy(self, policy: RetryPolicy) -> Self {
+        Endpoint {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Close the connection once no call has been in flight on it for `dur`.
+    ///
+    /// The channel then goes back to [`Idle`](super::ConnectivityState::Idle)
+    /// and connects again when the next call is made. A call is in flight
+    /// until its response has been read completely or dropped.
+    ///
+    /// Defaults to keeping connections open.
+    ///
+    /// ```
+    /// # use tonic::transport::Endpoint;
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.idle_timeout(Duration::from_secs(300));
+    /// ```
+    pub fn idle_timeout(self, dur: Duration) -> Self {
+        Endpoint {
+            idle_timeout: Some(dur),
+            ..self
+        }
+    }
+
+    /// Back off between attempts to reconnect, see [`ReconnectBackoff`].
+    ///
+    /// By default the channel connects again as soon as a call is made after
+    /// a failed attempt.
+    ///
+    /// ```
+    /// # use tonic::transport::{channel::ReconnectBackoff, Endpoint};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.reconnect_backoff(ReconnectBackoff::new());
+    /// ```
+    pub fn reconnect_backoff(self, backoff: ReconnectBackoff) -> Self {
+        Endpoint {
+            reconnect_backoff: Some(backoff),
+            ..self
+        }
+    }
+
+    /// Make calls wait for the channel to connect instead of failing fast.
+    ///
+    /// Calls that wait keep trying to connect until they succeed or their
+    /// deadline expires, see [`WaitForReady`] to choose per call. Defaults to
+    /// `false`.
+    ///
+    /// ```
+    /// # use tonic::transport::Endpoint;
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.wait_for_ready(true).timeout(Duration::from_secs(30));
+    /// ```
+    ///
+    /// [`WaitForReady`]: super::WaitForReady
+    pub fn wait_for_ready(self, enabled: bool) -> Self {
+        Endpoint {
+            wait_for_ready: enabled,
+            ..self
+        }
+    }
+
+    /// Throttle retries so they cannot amplify an outage.
+    ///
+    /// The channel keeps a bucket of `max_tokens` tokens. Every failed attempt
+    /// removes a token and every successful call adds back `token_ratio`
+    /// tokens. While the bucket is at most half full no retries or hedged
+    /// attempts are made. Balanced channels ignore the throttling of their
+    /// endpoints, use [`Channel::retry_throttling`] instead.
+    ///
+    /// ```
+    /// # use tonic::transport::Endpoint;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.retry_throttling(10, 0.1);
+    /// ```
+    pub fn retry_throttling(self, max_tokens: u32, token_ratio: f64) -> Self {
+        Endpoint {
+            retry_throttling: Some((max_tokens, token_ratio)),
+            ..self
+        }
+    }
+
+    /// Watch the health of `service` on this endpoint when it is part of a
+    /// balanced channel.
+    ///
+    /// The channel opens a `grpc.health.v1.Health/Watch` stream to the
+    /// endpoint, and only sends calls to the endpoint while it reports
+    /// `SERVING`. An empty service name asks for the health of the server as
+    /// a whole. Endpoints whose server does not implement the health service
+    /// are always considered serving.
+    ///
+    /// The stream shares the endpoint's connection, and is opened again
+    /// whenever the endpoint reconnects. It is not counted towards
+    /// [`Endpoint::idle_timeout`], so it does not keep an idle connection
+    /// open.
+    ///
+    /// This has no effect on channels connected to a single endpoint.
+    ///
+    /// ```
+    /// # use tonic::transport::Endpoint;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.health_check("helloworld.Greeter");
+    /// ```
+    pub fn health_check(self, service: impl Into<String>) -> Self {
+        Endpoint {
+            health_check: Some(service.into()),
+            ..self
+        }
+    }
+
+    /// Apply a gRPC [`ServiceConfig`] to the calls made on the channel.
+    ///
+    /// Policies set directly on the endpoint or the channel act as defaults:
+    /// a retry policy from the config replaces the endpoint's retry policy
+    /// for the methods it covers, while the endpoint's retry throttling and
+    /// the channel's hedging policies take precedence over the config.
+    ///
+    /// ```
+    /// # use tonic::transport::{channel::ServiceConfig, Endpoint};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// let config = ServiceConfig::from_json(r#"{
+    ///     "methodConfig": [{ "name": [{}], "timeout": "5s" }]
+    /// }"#).unwrap();
+    /// builder.service_config(config);
+    /// ```
+    #[cfg(feature = "service-config")]
+    pub fn service_config(self, config: ServiceConfig) -> Self {
+        Endpoint {
+            service_config: Some(Arc::new(config)),
+            ..self
+        }
+    }
+
     /// Sets the [`SETTINGS_INITIAL_WINDOW_SIZE`][spec] option for HTTP2
     /// stream-level flow control.
     ///
@@ -238,7 +400,7 @@ impl Endpoint {
     }
 
     /// Configures TLS for the endpoint.
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     pub fn tls_config(self, tls_config: ClientTlsConfig) -> Result<Self, Error> {
         Ok(Endpoint {
             tls: Some(
@@ -304,11 +466,40 @@ impl Endpoint {
     pub(crate) fn connector<C>(&self, c: C) -> service::Connector<C> {
         service::Connector::new(
             c,
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             self.tls.clone(),
         )
     }
 
+    pub(crate) fn retry_config(&self) -> Option<RetryConfig> {
+        #[cfg(feature = "service-config")]
+        let has_service_config = self.service_config.is_some();
+        #[cfg(not(feature = "service-config"))]
+        let has_service_config = false;
+
+        if self.retry_policy.is_none()
+            && self.retry_throttling.is_none()
+            && !self.wait_for_ready
+            && !has_service_config
+        {
+            return None;
+        }
+
+        let mut config = RetryConfig::new(
+            self.retry_policy.clone(),
+            self.retry_throttling,
+            self.timeout,
+        );
+        config.set_wait_for_ready(self.wait_for_ready);
+
+        #[cfg(feature = "service-config")]
+        if let Some(service_config) = &self.service_config {
+            config.set_service_config(service_config.clone());
+        }
+
+        Some(config)
+    }
+
     /// Create a channel from this config.
     pub async fn connect(&self) -> Result<Channel, Error> {
         let mut http = HttpConnector::new();
@@ -410,7 +601,7 @@ impl From<Uri> for Endpoint {
             concurrency_limit: None,
             rate_limit: None,
             timeout: None,
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             tls: None,
             buffer_size: None,
             init_stream_window_size: None,
@@ -423,6 +614,15 @@ impl From<Uri> for Endpoint {
             connect_timeout: None,
             http2_adaptive_window: None,
             executor: SharedExec::tokio(),
+            resolve_now: None,
+            retry_policy: None,
+            retry_throttling: None,
+            health_check: None,
+            wait_for_ready: false,
+            reconnect_backoff: None,
+            idle_timeout: None,
+            #[cfg(feature = "service-config")]
+            service_config: None,
         }
     }
 }
@@ -1,15 +1,37 @@
 //! Client implementation and builder.
 
+mod backoff;
+mod balance;
+mod connectivity;
 mod endpoint;
+mod resolver;
+mod retry;
 pub(crate) mod service;
-#[cfg(feature = "tls")]
+#[cfg(feature = "service-config")]
+mod service_config;
+#[cfg(feature = "_tls-any")]
 mod tls;
+mod wait_for_ready;
 
+pub use backoff::ReconnectBackoff;
+pub use balance::LoadBalancingPolicy;
+pub use connectivity::ConnectivityState;
+pub(crate) use connectivity::{ConnectivityReporter, ConnectivityTracker};
 pub use endpoint::Endpoint;
-#[cfg(feature = "tls")]
-pub use tls::ClientTlsConfig;
-
-use self::service::{Connection, DynamicServiceStream, Executor, SharedExec};
+pub use resolver::{
+    DnsResolver, InMemoryResolver, InMemoryResolverHandle, Resolver, StaticResolver,
+};
+pub use retry::{HedgingPolicy, RetryPolicy};
+#[cfg(feature = "service-config")]
+pub use service_config::{MethodConfig, ServiceConfig};
+#[cfg(feature = "_tls-any")]
+pub use tls::{ClientSessionCache, ClientTlsConfig};
+pub use wait_for_ready::WaitForReady;
+
+use self::service::{
+    Balance, Connection, DynamicServiceStream, Executor, Readiness, Retry, RetryResponseFuture,
+    SharedExec,
+};
 use crate::body::BoxBody;
 use bytes::Bytes;
 use http::{
@@ -22,12 +44,15 @@ use std::{
     future::Future,
     hash::Hash,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{ready, Context, Poll},
 };
-use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::{
+    mpsc::{channel, Sender},
+    watch,
+};
 
 use hyper::rt;
-use tower::balance::p2c::Balance;
 use tower::{
     buffer::{self, Buffer},
     discover::{Change, Discover},
@@ -65,14 +90,19 @@ const DEFAULT_BUFFER_SIZE: usize = 1024;
 /// cloning the `Channel` type is cheap and encouraged.
 #[derive(Clone)]
 pub struct Channel {
-    svc: Buffer<Svc, Request<BoxBody>>,
+    svc: Retry<Buffer<Svc, Request<BoxBody>>>,
+    /// The policy of the balancer, for balanced channels.
+    policy: Option<Arc<Mutex<LoadBalancingPolicy>>>,
+    connectivity: watch::Receiver<ConnectivityState>,
 }
 
 /// A future that resolves to an HTTP response.
 ///
 /// This is returned by the `Service::call` on [`Channel`].
 pub struct ResponseFuture {
-    inner: buffer::future::ResponseFuture<<Svc as Service<Request<BoxBody>>>::Future>,
+    inner: RetryResponseFuture<
+        buffer::future::ResponseFuture<<Svc as Service<Request<BoxBody>>>::Future>,
+    >,
 }
 
 impl Channel {
@@ -141,8 +171,183 @@ impl Channel {
         E: Executor<Pin<Box<dyn Future<Output = ()> + Send>>> + Send + Sync + 'static,
     {
         let (tx, rx) = channel(capacity);
-        let list = DynamicServiceStream::new(rx);
-        (Self::balance(list, DEFAULT_BUFFER_SIZE, executor), tx)
+        let (connectivity, state) = ConnectivityTracker::new();
+        let list = DynamicServiceStream::new(rx, connectivity);
+        (
+            Self::balance(list, DEFAULT_BUFFER_SIZE, executor, state),
+            tx,
+        )
+    }
+
+    /// Balance across the [`Endpoint`]s produced by a [`Resolver`].
+    ///
+    /// The resolver is driven by a background task, and every address set it
+    /// yields replaces the set of endpoints this [`Channel`] balances across.
+    ///
+    /// ```
+    /// # use tonic::transport::{Channel, Endpoint};
+    /// # use tonic::transport::channel::DnsResolver;
+    /// # async fn f() -> Result<(), tonic::transport::Error> {
+    /// let endpoint = Endpoint::from_static("http://example.com:50051");
+    /// let channel = Channel::balance_resolver(DnsResolver::new(endpoint)?);
+    /// # drop(channel);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn balance_resolver<R>(resolver: R) -> Self
+    where
+        R: Resolver,
+    {
+        Self::balance_resolver_with_executor(resolver, SharedExec::tokio())
+    }
+
+    /// Balance across the [`Endpoint`]s produced by a [`Resolver`].
+    ///
+    /// The [`Channel`] will use the given executor to spawn async tasks,
+    /// including the one driving the resolver.
+    pub fn balance_resolver_with_executor<R, E>(resolver: R, executor: E) -> Self
+    where
+        R: Resolver,
+        E: Executor<Pin<Box<dyn Future<Output = ()> + Send>>> + Send + Sync + 'static,
+    {
+        let executor = SharedExec::new(executor);
+        let (channel, tx) =
+            Self::balance_channel_with_executor(DEFAULT_BUFFER_SIZE, executor.clone());
+        executor.execute(Box::pin(resolver::drive(resolver, tx)));
+
+        channel
+    }
+
+    /// Retry failed calls according to `policy`.
+    ///
+    /// This replaces the policy set with [`Endpoint::retry_policy`], which
+    /// balanced channels do not use since they are not built from a single
+    /// [`Endpoint`].
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.svc.config_mut().set_retry_policy(policy);
+        self
+    }
+
+    /// Throttle retries so they cannot amplify an outage, see
+    /// [`Endpoint::retry_throttling`].
+    ///
+    /// This replaces the throttling set with [`Endpoint::retry_throttling`],
+    /// which balanced channels do not use since they are not built from a
+    /// single [`Endpoint`].
+    pub fn retry_throttling(mut self, max_tokens: u32, token_ratio: f64) -> Self {
+        self.svc
+            .config_mut()
+            .set_retry_throttling(max_tokens, token_ratio);
+        self
+    }
+
+    /// Hedge calls to `method` according to `policy`.
+    ///
+    /// `method` is either a fully qualified method name such as
+    /// `helloworld.Greeter/SayHello`, or a service name such as
+    /// `helloworld.Greeter` to hedge every method of that service. Method
+    /// names take precedence over service names. Calls to hedged methods are
+    /// never retried by the [`RetryPolicy`].
+    ///
+    /// Hedging is most useful on channels balancing across several endpoints,
+    /// see [`HedgingPolicy`].
+    pub fn hedging_policy(mut self, method: impl Into<String>, policy: HedgingPolicy) -> Self {
+        self.svc
+            .config_mut()
+            .set_hedging_policy(method.into(), policy);
+        self
+    }
+
+    /// Make calls wait for the channel to connect instead of failing fast,
+    /// unless overridden per call with [`WaitForReady`].
+    ///
+    /// This replaces the default set with [`Endpoint::wait_for_ready`].
+    pub fn wait_for_ready(mut self, enabled: bool) -> Self {
+        self.svc.config_mut().set_wait_for_ready(enabled);
+        self
+    }
+
+    /// Apply a gRPC [`ServiceConfig`] to the calls made on this channel.
+    ///
+    /// This replaces any config set with [`Endpoint::service_config`], which
+    /// is mostly useful for balanced channels that are not built from a
+    /// single [`Endpoint`]. The first load balancing policy of the config
+    /// that tonic supports becomes the channel's [`LoadBalancingPolicy`].
+    #[cfg(feature = "service-config")]
+    pub fn service_config(mut self, config: ServiceConfig) -> Self {
+        let policy = config
+            .load_balancing_policies()
+            .iter()
+            .find_map(|name| LoadBalancingPolicy::from_name(name));
+
+        self.svc.config_mut().set_service_config(Arc::new(config));
+
+        match policy {
+            Some(policy) => self.load_balancing_policy(policy),
+            None => self,
+        }
+    }
+
+    /// The current [`ConnectivityState`] of the channel.
+    ///
+    /// Channels only connect when a call is made, unless they were created
+    /// with [`Endpoint::connect`], so a new lazy or balanced channel stays
+    /// [`Idle`](ConnectivityState::Idle) until its first call.
+    pub fn state(&self) -> ConnectivityState {
+        *self.connectivity.borrow()
+    }
+
+    /// A [`watch::Receiver`] following the [`ConnectivityState`] of the
+    /// channel.
+    ///
+    /// The receiver keeps working after the channel is dropped, and sees
+    /// [`ConnectivityState::Shutdown`] once the channel's connections have
+    /// been shut down.
+    pub fn watch_state(&self) -> watch::Receiver<ConnectivityState> {
+        self.connectivity.clone()
+    }
+
+    /// Wait for the [`ConnectivityState`] of the channel to differ from
+    /// `current`, and return the new state.
+    ///
+    /// ```
+    /// # use tonic::transport::{channel::ConnectivityState, Endpoint};
+    /// # async fn f() -> Result<(), tonic::transport::Error> {
+    /// let channel = Endpoint::from_static("https://example.com").connect().await?;
+    ///
+    /// // Wait for the connection to be lost.
+    /// let state = channel
+    ///     .wait_for_state_change(ConnectivityState::Ready)
+    ///     .await;
+    /// println!("channel is now {:?}", state);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_state_change(&self, current: ConnectivityState) -> ConnectivityState {
+        let mut connectivity = self.connectivity.clone();
+
+        loop {
+            let state = *connectivity.borrow_and_update();
+            if state != current {
+                return state;
+            }
+
+            if connectivity.changed().await.is_err() {
+                return ConnectivityState::Shutdown;
+            }
+        }
+    }
+
+    /// Sets the [`LoadBalancingPolicy`] of a balanced channel.
+    ///
+    /// The policy is shared by all clones of the channel. It has no effect on
+    /// channels connected to a single [`Endpoint`]. Defaults to
+    /// [`LoadBalancingPolicy::PowerOfTwoChoices`].
+    pub fn load_balancing_policy(self, policy: LoadBalancingPolicy) -> Self {
+        if let Some(shared) = &self.policy {
+            *shared.lock().unwrap() = policy;
+        }
+        self
     }
 
     pub(crate) fn new<C>(connector: C, endpoint: Endpoint) -> Self
@@ -154,12 +359,19 @@ impl Channel {
     {
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let retry = endpoint.retry_config();
+        let (connectivity, state) = ConnectivityTracker::new();
+        let readiness = Readiness::new(state.clone(), endpoint.reconnect_backoff.clone());
 
-        let svc = Connection::lazy(connector, endpoint);
+        let svc = Connection::lazy(connector, endpoint, connectivity.reporter());
         let (svc, worker) = Buffer::pair(Either::A(svc), buffer_size);
         executor.execute(Box::pin(worker));
 
-        Channel { svc }
+        Channel {
+            svc: Retry::new(svc, retry, readiness),
+            policy: None,
+            connectivity: state,
+        }
     }
 
     pub(crate) async fn connect<C>(connector: C, endpoint: Endpoint) -> Result<Self, super::Error>
@@ -171,30 +383,47 @@ impl Channel {
     {
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let retry = endpoint.retry_config();
+        let (connectivity, state) = ConnectivityTracker::new();
+        let readiness = Readiness::new(state.clone(), endpoint.reconnect_backoff.clone());
 
-        let svc = Connection::connect(connector, endpoint)
+        let svc = Connection::connect(connector, endpoint, connectivity.reporter())
             .await
             .map_err(super::Error::from_source)?;
         let (svc, worker) = Buffer::pair(Either::A(svc), buffer_size);
         executor.execute(Box::pin(worker));
 
-        Ok(Channel { svc })
+        Ok(Channel {
+            svc: Retry::new(svc, retry, readiness),
+            policy: None,
+            connectivity: state,
+        })
     }
 
-    pub(crate) fn balance<D, E>(discover: D, buffer_size: usize, executor: E) -> Self
+    pub(crate) fn balance<D, E>(
+        discover: D,
+        buffer_size: usize,
+        executor: E,
+        connectivity: watch::Receiver<ConnectivityState>,
+    ) -> Self
     where
         D: Discover<Service = Connection> + Unpin + Send + 'static,
         D::Error: Into<crate::Error>,
         D::Key: Hash + Send + Clone,
         E: Executor<BoxFuture<'static, ()>> + Send + Sync + 'static,
     {
-        let svc = Balance::new(discover);
+        let policy = Arc::new(Mutex::new(LoadBalancingPolicy::default()));
+        let svc = Balance::new(discover, policy.clone());
 
         let svc = BoxService::new(svc);
         let (svc, worker) = Buffer::pair(Either::B(svc), buffer_size);
         executor.execute(Box::pin(worker));
 
-        Channel { svc }
+        Channel {
+            svc: Retry::new(svc, None, Readiness::new(connectivity.clone(), None)),
+            policy: Some(policy),
+            connectivity,
+        }
     }
 }
 
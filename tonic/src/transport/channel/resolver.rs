@@ -0,0 +1,487 @@
+use super::{Endpoint, ReconnectBackoff};
+use crate::transport::{service::random, Error};
+use http::Uri;
+use std::{
+    collections::HashSet,
+    fmt,
+    future::{poll_fn, Future},
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+    time::{Instant, Sleep},
+};
+use tower::discover::Change;
+
+const DEFAULT_DNS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_DNS_MIN_RESOLVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A source of [`Endpoint`]s that may change over time.
+///
+/// Each time the resolver produces a new address set the [`Channel`] it feeds
+/// is updated to balance across exactly those endpoints: endpoints that are no
+/// longer present are removed and new ones are connected lazily. Endpoints are
+/// identified by their [`Endpoint::uri`].
+///
+/// [`Channel`]: super::Channel
+pub trait Resolver: Send + 'static {
+    /// Poll for the next address set.
+    ///
+    /// Returning `Poll::Pending` keeps the last resolved set in place. Errors
+    /// are logged and also leave the previous set untouched.
+    fn poll_resolve(&mut self, cx: &mut Context<'_>) -> Poll<Result<Vec<Endpoint>, crate::Error>>;
+
+    /// Hint that the current address set may be stale.
+    ///
+    /// This is invoked when one of the resolved endpoints fails to connect.
+    /// Resolvers that can re-resolve should do so soon after this is called.
+    fn resolve_now(&mut self) {}
+}
+
+/// Handle given to connections so they can request re-resolution when
+/// connecting fails.
+#[derive(Clone)]
+pub(crate) struct ResolveNow {
+    tx: Sender<()>,
+}
+
+impl ResolveNow {
+    pub(crate) fn notify(&self) {
+        // A full channel means a request is already pending.
+        let _ = self.tx.try_send(());
+    }
+}
+
+/// Drive `resolver`, forwarding the difference between successive address
+/// sets to the balancer fed by `changes`.
+///
+/// After an error the resolver is polled again once it is asked to resolve
+/// now, or after a backoff following the gRPC connection backoff protocol.
+/// Driving stops once the balancer is gone.
+pub(crate) async fn drive<R>(mut resolver: R, changes: Sender<Change<Uri, Endpoint>>)
+where
+    R: Resolver,
+{
+    let (tx, mut rx): (_, Receiver<()>) = mpsc::channel(1);
+    let resolve_now = ResolveNow { tx };
+    let mut current = HashSet::new();
+    let config = ReconnectBackoff::default();
+    let mut backoff = config.initial_backoff;
+
+    loop {
+        let resolve = poll_fn(|cx| {
+            while let Poll::Ready(Some(())) = rx.poll_recv(cx) {
+                resolver.resolve_now();
+            }
+            resolver.poll_resolve(cx)
+        });
+        let resolved = tokio::select! {
+            resolved = resolve => resolved,
+            _ = changes.closed() => return,
+        };
+
+        let endpoints = match resolved {
+            Ok(endpoints) => {
+                backoff = config.initial_backoff;
+                endpoints
+            }
+            Err(error) => {
+                let delay = config.jittered(backoff, random());
+                backoff = config.next(backoff);
+                tracing::debug!(
+                    "resolver error: {:?}, resolving again in {:?}",
+                    error,
+                    delay
+                );
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    Some(()) = rx.recv() => resolver.resolve_now(),
+                    _ = changes.closed() => return,
+                }
+                continue;
+            }
+        };
+
+        let mut next = HashSet::with_capacity(endpoints.len());
+        let mut inserts = Vec::new();
+        for mut endpoint in endpoints {
+            if !next.insert(endpoint.uri.clone()) {
+                continue;
+            }
+            if !current.contains(&endpoint.uri) {
+                endpoint.resolve_now = Some(resolve_now.clone());
+                inserts.push(endpoint);
+            }
+        }
+
+        for uri in current.difference(&next) {
+            if changes.send(Change::Remove(uri.clone())).await.is_err() {
+                return;
+            }
+        }
+        for endpoint in inserts {
+            if changes
+                .send(Change::Insert(endpoint.uri.clone(), endpoint))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+
+        current = next;
+    }
+}
+
+/// A [`Resolver`] that yields a fixed set of endpoints once.
+pub struct StaticResolver {
+    endpoints: Option<Vec<Endpoint>>,
+}
+
+impl StaticResolver {
+    /// Create a resolver that always resolves to `endpoints`.
+    pub fn new(endpoints: impl IntoIterator<Item = Endpoint>) -> Self {
+        Self {
+            endpoints: Some(endpoints.into_iter().collect()),
+        }
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn poll_resolve(&mut self, _: &mut Context<'_>) -> Poll<Result<Vec<Endpoint>, crate::Error>> {
+        match self.endpoints.take() {
+            Some(endpoints) => Poll::Ready(Ok(endpoints)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl fmt::Debug for StaticResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticResolver").finish()
+    }
+}
+
+/// A [`Resolver`] whose address set is controlled through an
+/// [`InMemoryResolverHandle`].
+///
+/// This is mostly useful for tests, where it allows changing the endpoints a
+/// [`Channel`](super::Channel) balances across without touching the network.
+///
+/// ```
+/// # use tonic::transport::{Channel, Endpoint};
+/// # use tonic::transport::channel::InMemoryResolver;
+/// # async fn f() {
+/// let (resolver, handle) = InMemoryResolver::new();
+/// let channel = Channel::balance_resolver(resolver);
+///
+/// handle.update([Endpoint::from_static("http://127.0.0.1:50051")]);
+/// # drop(channel);
+/// # }
+/// ```
+pub struct InMemoryResolver {
+    updates: UnboundedReceiver<Vec<Endpoint>>,
+    resolve_now: Arc<AtomicUsize>,
+}
+
+/// Handle used to update the address set of an [`InMemoryResolver`].
+#[derive(Clone)]
+pub struct InMemoryResolverHandle {
+    updates: UnboundedSender<Vec<Endpoint>>,
+    resolve_now: Arc<AtomicUsize>,
+}
+
+impl InMemoryResolver {
+    /// Create a new resolver and the handle that controls it.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> (Self, InMemoryResolverHandle) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let resolve_now = Arc::new(AtomicUsize::new(0));
+
+        let resolver = Self {
+            updates: rx,
+            resolve_now: resolve_now.clone(),
+        };
+        let handle = InMemoryResolverHandle {
+            updates: tx,
+            resolve_now,
+        };
+
+        (resolver, handle)
+    }
+}
+
+impl InMemoryResolverHandle {
+    /// Replace the resolver's address set with `endpoints`.
+    pub fn update(&self, endpoints: impl IntoIterator<Item = Endpoint>) {
+        let _ = self.updates.send(endpoints.into_iter().collect());
+    }
+
+    /// The number of times re-resolution has been requested.
+    pub fn resolve_now_count(&self) -> usize {
+        self.resolve_now.load(Ordering::SeqCst)
+    }
+}
+
+impl Resolver for InMemoryResolver {
+    fn poll_resolve(&mut self, cx: &mut Context<'_>) -> Poll<Result<Vec<Endpoint>, crate::Error>> {
+        match ready!(self.updates.poll_recv(cx)) {
+            Some(endpoints) => Poll::Ready(Ok(endpoints)),
+            // The handle is gone so the address set can no longer change.
+            None => Poll::Pending,
+        }
+    }
+
+    fn resolve_now(&mut self) {
+        self.resolve_now.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl fmt::Debug for InMemoryResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryResolver").finish()
+    }
+}
+
+impl fmt::Debug for InMemoryResolverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryResolverHandle").finish()
+    }
+}
+
+type LookupFuture = Pin<Box<dyn Future<Output = io::Result<Vec<SocketAddr>>> + Send>>;
+
+enum DnsState {
+    Idle,
+    Waiting(Pin<Box<Sleep>>),
+    Resolving(LookupFuture),
+}
+
+/// A [`Resolver`] that periodically resolves the host of an [`Endpoint`]'s
+/// uri via DNS.
+///
+/// Every A and AAAA record becomes its own endpoint, configured like the one
+/// the resolver was created from. The original uri is kept as the origin so
+/// the `:authority` sent to the server is unchanged.
+///
+/// Records are re-resolved every [`refresh_interval`](Self::refresh_interval)
+/// and whenever connecting to one of the resolved addresses fails, but never
+/// more often than [`min_resolve_interval`](Self::min_resolve_interval).
+///
+/// ```
+/// # use tonic::transport::{Channel, Endpoint};
+/// # use tonic::transport::channel::DnsResolver;
+/// # async fn f() -> Result<(), tonic::transport::Error> {
+/// let endpoint = Endpoint::from_static("http://my-service.default.svc:50051");
+/// let channel = Channel::balance_resolver(DnsResolver::new(endpoint)?);
+/// # drop(channel);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DnsResolver {
+    endpoint: Endpoint,
+    host: String,
+    port: u16,
+    refresh_interval: Duration,
+    min_resolve_interval: Duration,
+    last_resolved: Option<Instant>,
+    state: DnsState,
+}
+
+impl DnsResolver {
+    /// Create a resolver for the host of `endpoint`'s uri.
+    ///
+    /// Fails if the uri has no host. When no port is given the default port
+    /// for the uri's scheme is used.
+    pub fn new(endpoint: Endpoint) -> Result<Self, Error> {
+        let uri = endpoint.uri();
+        let host = uri
+            .host()
+            .ok_or_else(Error::new_invalid_uri)?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = match uri.port_u16() {
+            Some(port) => port,
+            None if uri.scheme_str() == Some("https") => 443,
+            None => 80,
+        };
+
+        Ok(Self {
+            endpoint,
+            host,
+            port,
+            refresh_interval: DEFAULT_DNS_REFRESH_INTERVAL,
+            min_resolve_interval: DEFAULT_DNS_MIN_RESOLVE_INTERVAL,
+            last_resolved: None,
+            state: DnsState::Idle,
+        })
+    }
+
+    /// Set how often records are re-resolved.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn refresh_interval(self, interval: Duration) -> Self {
+        DnsResolver {
+            refresh_interval: interval,
+            ..self
+        }
+    }
+
+    /// Set the minimum time between two resolutions triggered by connection
+    /// failures.
+    ///
+    /// Defaults to 1 second.
+    pub fn min_resolve_interval(self, interval: Duration) -> Self {
+        DnsResolver {
+            min_resolve_interval: interval,
+            ..self
+        }
+    }
+
+    fn endpoint_for(&self, addr: SocketAddr) -> Option<Endpoint> {
+        let scheme = self.endpoint.uri.scheme_str().unwrap_or("http");
+        let uri = Uri::try_from(format!("{}://{}", scheme, addr)).ok()?;
+
+        let mut endpoint = self.endpoint.clone();
+        if endpoint.origin.is_none() {
+            endpoint.origin = Some(endpoint.uri.clone());
+        }
+        endpoint.uri = uri;
+
+        Some(endpoint)
+    }
+}
+
+impl Resolver for DnsResolver {
+    fn poll_resolve(&mut self, cx: &mut Context<'_>) -> Poll<Result<Vec<Endpoint>, crate::Error>> {
+        loop {
+            match &mut self.state {
+                DnsState::Idle => {
+                    let target = (self.host.clone(), self.port);
+                    let lookup = async move {
+                        tokio::net::lookup_host(target)
+                            .await
+                            .map(|addrs| addrs.collect::<Vec<_>>())
+                    };
+                    self.state = DnsState::Resolving(Box::pin(lookup));
+                }
+                DnsState::Waiting(sleep) => {
+                    ready!(sleep.as_mut().poll(cx));
+                    self.state = DnsState::Idle;
+                }
+                DnsState::Resolving(lookup) => {
+                    let result = ready!(lookup.as_mut().poll(cx));
+
+                    let now = Instant::now();
+                    self.last_resolved = Some(now);
+                    self.state = DnsState::Waiting(Box::pin(tokio::time::sleep_until(
+                        now + self.refresh_interval,
+                    )));
+
+                    return Poll::Ready(match result {
+                        Ok(addrs) => Ok(addrs
+                            .into_iter()
+                            .filter_map(|addr| self.endpoint_for(addr))
+                            .collect()),
+                        Err(e) => Err(e.into()),
+                    });
+                }
+            }
+        }
+    }
+
+    fn resolve_now(&mut self) {
+        if let DnsState::Waiting(sleep) = &mut self.state {
+            let earliest = self
+                .last_resolved
+                .map(|last| last + self.min_resolve_interval)
+                .unwrap_or_else(Instant::now);
+
+            if earliest < sleep.deadline() {
+                sleep.as_mut().reset(earliest);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsResolver")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("refresh_interval", &self.refresh_interval)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always fails, counting how often it was polled.
+    struct Failing {
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl Resolver for Failing {
+        fn poll_resolve(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<Vec<Endpoint>, crate::Error>> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            Poll::Ready(Err("no addresses".into()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_after_errors() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let (tx, _rx) = mpsc::channel(1);
+        let resolver = Failing {
+            polls: polls.clone(),
+        };
+        tokio::spawn(drive(resolver, tx));
+
+        // Backoffs of about 1, 1.6 and 2.56 seconds, the next one is over 3.
+        tokio::time::sleep(Duration::from_secs(7)).await;
+        assert_eq!(polls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn stops_once_channel_is_gone() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let endpoints = [Endpoint::from_static("http://127.0.0.1:50051")];
+        let task = tokio::spawn(drive(StaticResolver::new(endpoints), tx));
+
+        assert!(matches!(rx.recv().await, Some(Change::Insert(..))));
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("resolver task did not stop")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_once_channel_is_gone_without_handle() {
+        let (tx, rx) = mpsc::channel(8);
+        let (resolver, handle) = InMemoryResolver::new();
+        let task = tokio::spawn(drive(resolver, tx));
+
+        drop(handle);
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("resolver task did not stop")
+            .unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+use crate::Code;
+use std::time::Duration;
+
+/// Retry policy for calls made on a [`Channel`](super::Channel).
+///
+/// Follows the [gRPC retry design][spec]: a call is retried when the server
+/// answers with one of the retryable status codes before sending any
+/// response message, or when connecting to the server fails. Attempts are
+/// spaced out with a randomized exponential backoff, unless the server
+/// requests a specific delay through the `grpc-retry-pushback-ms` trailer.
+///
+/// Only requests whose body has been sent completely and fits the replay
+/// buffer can be retried, which covers unary and buffered client-streaming
+/// calls. Retries never extend a call past its `grpc-timeout` deadline or the
+/// [`Endpoint::timeout`](super::Endpoint::timeout).
+///
+/// ```
+/// # use tonic::transport::{channel::RetryPolicy, Endpoint};
+/// # use tonic::Code;
+/// # use std::time::Duration;
+/// # let mut builder = Endpoint::from_static("https://example.com");
+/// builder.retry_policy(
+///     RetryPolicy::new()
+///         .max_attempts(4)
+///         .initial_backoff(Duration::from_millis(50))
+///         .retryable_codes([Code::Unavailable, Code::ResourceExhausted]),
+/// );
+/// ```
+///
+/// [spec]: https://github.com/grpc/proposal/blob/master/A6-client-retries.md
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: usize,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) backoff_multiplier: f64,
+    pub(crate) retryable_codes: Vec<Code>,
+}
+
+impl RetryPolicy {
+    /// The largest number of attempts a policy may make, as set by the spec.
+    pub const MAX_ATTEMPTS_LIMIT: usize = 5;
+
+    /// Creates a new `RetryPolicy` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of attempts, including the original one.
+    ///
+    /// Values above [`MAX_ATTEMPTS_LIMIT`](Self::MAX_ATTEMPTS_LIMIT) are
+    /// treated as the limit. Defaults to 3.
+    pub fn max_attempts(self, max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.min(Self::MAX_ATTEMPTS_LIMIT),
+            ..self
+        }
+    }
+
+    /// Sets the backoff before the first retry.
+    ///
+    /// Defaults to 100 milliseconds.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the upper bound on the backoff between attempts.
+    ///
+    /// Defaults to 1 second.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the factor the backoff grows by after every attempt.
+    ///
+    /// Defaults to 2.
+    pub fn backoff_multiplier(self, backoff_multiplier: f64) -> Self {
+        RetryPolicy {
+            backoff_multiplier,
+            ..self
+        }
+    }
+
+    /// Sets the status codes that cause a call to be retried.
+    ///
+    /// Defaults to [`Code::Unavailable`].
+    pub fn retryable_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        RetryPolicy {
+            retryable_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub(crate) fn is_retryable(&self, code: Code) -> bool {
+        self.retryable_codes.contains(&code)
+    }
+
+    /// The backoff ceiling for the retry following attempt number `attempt`.
+    pub(crate) fn backoff(&self, attempt: usize) -> Duration {
+        let exp = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exp);
+
+        if backoff.is_finite() && backoff < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(backoff.max(0.0))
+        } else {
+            self.max_backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            backoff_multiplier: 2.0,
+            retryable_codes: vec![Code::Unavailable],
+        }
+    }
+}
+
+/// Hedging policy for calls made on a [`Channel`](super::Channel).
+///
+/// Follows the hedging part of the [gRPC retry design][spec]: the call is
+/// sent, and every time [`hedging_delay`](Self::hedging_delay) passes without
+/// a response another copy of it is sent, up to
+/// [`max_attempts`](Self::max_attempts) copies in total. The first response
+/// that does not carry one of the non-fatal status codes is returned and all
+/// other attempts are cancelled. A non-fatal failure immediately triggers the
+/// next attempt.
+///
+/// On a balanced channel each copy goes through the balancer, which favors
+/// endpoints with fewer calls in flight, so copies usually reach different
+/// endpoints. Since copies are sent concurrently the request body is buffered
+/// before the first attempt, which makes hedging suitable for unary calls
+/// only.
+///
+/// ```
+/// # use tonic::transport::{channel::HedgingPolicy, Endpoint};
+/// # use tonic::Code;
+/// # use std::time::Duration;
+/// # async fn f() -> Result<(), tonic::transport::Error> {
+/// let channel = Endpoint::from_static("https://example.com")
+///     .connect()
+///     .await?
+///     .hedging_policy(
+///         "helloworld.Greeter/SayHello",
+///         HedgingPolicy::new()
+///             .max_attempts(3)
+///             .hedging_delay(Duration::from_millis(20))
+///             .non_fatal_codes([Code::Unavailable]),
+///     );
+/// # drop(channel);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [spec]: https://github.com/grpc/proposal/blob/master/A6-client-retries.md
+#[derive(Debug, Clone)]
+pub struct HedgingPolicy {
+    pub(crate) max_attempts: usize,
+    pub(crate) hedging_delay: Duration,
+    pub(crate) non_fatal_codes: Vec<Code>,
+}
+
+impl HedgingPolicy {
+    /// Creates a new `HedgingPolicy` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of copies of a call that are sent.
+    ///
+    /// Values above [`RetryPolicy::MAX_ATTEMPTS_LIMIT`] are treated as the
+    /// limit. Defaults to 2.
+    pub fn max_attempts(self, max_attempts: usize) -> Self {
+        HedgingPolicy {
+            max_attempts: max_attempts.min(RetryPolicy::MAX_ATTEMPTS_LIMIT),
+            ..self
+        }
+    }
+
+    /// Sets how long to wait for a response before sending another copy.
+    ///
+    /// Defaults to 100 milliseconds.
+    pub fn hedging_delay(self, hedging_delay: Duration) -> Self {
+        HedgingPolicy {
+            hedging_delay,
+            ..self
+        }
+    }
+
+    /// Sets the status codes that do not end a hedged call.
+    ///
+    /// A response with any other status code is returned to the caller right
+    /// away. Defaults to none.
+    pub fn non_fatal_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        HedgingPolicy {
+            non_fatal_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub(crate) fn is_non_fatal(&self, code: Code) -> bool {
+        self.non_fatal_codes.contains(&code)
+    }
+}
+
+impl Default for HedgingPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 2,
+            hedging_delay: Duration::from_millis(100),
+            non_fatal_codes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_until_max() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(300))
+            .backoff_multiplier(2.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(usize::MAX), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn max_attempts_is_capped() {
+        let policy = RetryPolicy::new().max_attempts(100);
+        assert_eq!(policy.max_attempts, RetryPolicy::MAX_ATTEMPTS_LIMIT);
+    }
+}
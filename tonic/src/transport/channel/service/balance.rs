@@ -0,0 +1,213 @@
+use super::Connection;
+use crate::{
+    body::BoxBody,
+    transport::{channel::LoadBalancingPolicy, service::random},
+};
+use http::{Request, Response};
+use std::{
+    hash::Hash,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+use tower::{
+    discover::{Change, Discover},
+    load::Load,
+    ready_cache::{error::Failed, ReadyCache},
+};
+use tower_service::Service;
+
+/// Balances calls across the connections produced by `D` according to a
+/// [`LoadBalancingPolicy`].
+///
+/// The policy is shared with the [`Channel`](super::super::Channel) so that it
+/// can be changed after the balancer has been spawned.
+pub(crate) struct Balance<D: Discover>
+where
+    D::Key: Hash + Eq,
+{
+    discover: D,
+    services: ReadyCache<D::Key, Connection, Request<BoxBody>>,
+    /// The keys of all endpoints, in the order they were discovered.
+    order: Vec<D::Key>,
+    policy: Arc<Mutex<LoadBalancingPolicy>>,
+    /// The position in `order` where round robin resumes.
+    next: usize,
+    ready_index: Option<usize>,
+}
+
+impl<D> Balance<D>
+where
+    D: Discover<Service = Connection> + Unpin,
+    D::Key: Hash + Eq + Clone,
+    D::Error: Into<crate::Error>,
+{
+    pub(crate) fn new(discover: D, policy: Arc<Mutex<LoadBalancingPolicy>>) -> Self {
+        Self {
+            discover,
+            services: ReadyCache::default(),
+            order: Vec::new(),
+            policy,
+            next: 0,
+            ready_index: None,
+        }
+    }
+
+    fn update_pending_from_discover(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<(), crate::Error>>> {
+        loop {
+            let change = match ready!(Pin::new(&mut self.discover).poll_discover(cx)) {
+                Some(change) => change.map_err(Into::into)?,
+                None => return Poll::Ready(None),
+            };
+
+            // Evicting services invalidates ready indices.
+            self.ready_index = None;
+
+            match change {
+                Change::Remove(key) => {
+                    tracing::trace!("removing endpoint");
+                    self.services.evict(&key);
+                    self.order.retain(|k| *k != key);
+                }
+                Change::Insert(key, svc) => {
+                    tracing::trace!("inserting endpoint");
+                    self.order.retain(|k| *k != key);
+                    self.order.push(key.clone());
+                    self.services.push(key, svc);
+                }
+            }
+        }
+    }
+
+    fn promote_pending_to_ready(&mut self, cx: &mut Context<'_>) {
+        loop {
+            match self.services.poll_pending(cx) {
+                Poll::Ready(Ok(())) | Poll::Pending => return,
+                Poll::Ready(Err(Failed(key, error))) => self.remove_failed(key, error),
+            }
+        }
+    }
+
+    fn remove_failed(&mut self, key: D::Key, error: crate::Error) {
+        tracing::debug!(%error, "dropping failed endpoint");
+        self.order.retain(|k| *k != key);
+        self.ready_index = None;
+    }
+
+    /// Picks a ready service according to the policy.
+    ///
+    /// Services that are not serving according to their health check are
+    /// never picked. Services whose connection failed are only picked when no
+    /// other service is ready, so that the call fails with their error rather
+    /// than waiting.
+    fn select(&mut self) -> Option<usize> {
+        let policy = *self.policy.lock().unwrap();
+
+        self.select_with(policy, true)
+            .or_else(|| self.select_with(policy, false))
+    }
+
+    fn select_with(&mut self, policy: LoadBalancingPolicy, skip_failed: bool) -> Option<usize> {
+        let services = &self.services;
+        let ready = |key: &D::Key| {
+            services
+                .get_ready(key)
+                .filter(|(_, _, svc)| svc.is_serving() && !(skip_failed && svc.is_failed()))
+                .map(|(index, _, _)| index)
+        };
+
+        match policy {
+            LoadBalancingPolicy::PickFirst => self.order.iter().find_map(ready),
+            LoadBalancingPolicy::RoundRobin => {
+                let len = self.order.len();
+                let (position, index) = (0..len)
+                    .map(|i| (self.next + i) % len)
+                    .find_map(|position| Some((position, ready(&self.order[position])?)))?;
+
+                self.next = position + 1;
+                Some(index)
+            }
+            LoadBalancingPolicy::PowerOfTwoChoices => {
+                let candidates = (0..services.ready_len())
+                    .filter(|&index| {
+                        let (_, svc) = services.get_ready_index(index).expect("invalid index");
+                        svc.is_serving() && !(skip_failed && svc.is_failed())
+                    })
+                    .collect::<Vec<_>>();
+
+                match candidates.len() {
+                    0 => None,
+                    1 => Some(candidates[0]),
+                    len => {
+                        let a = random_index(len);
+                        let mut b = random_index(len - 1);
+                        if b >= a {
+                            b += 1;
+                        }
+
+                        let load = |index: usize| {
+                            let (_, svc) = services.get_ready_index(index).expect("invalid index");
+                            svc.load()
+                        };
+
+                        let (a, b) = (candidates[a], candidates[b]);
+                        Some(if load(a) <= load(b) { a } else { b })
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn random_index(len: usize) -> usize {
+    ((random() * len as f64) as usize).min(len - 1)
+}
+
+impl<D> Service<Request<BoxBody>> for Balance<D>
+where
+    D: Discover<Service = Connection> + Unpin,
+    D::Key: Hash + Eq + Clone,
+    D::Error: Into<crate::Error>,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::Error;
+    type Future = <Connection as Service<Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Discovery ending is not an error, the balancer keeps using the
+        // endpoints it knows about.
+        let _ = self.update_pending_from_discover(cx)?;
+        self.promote_pending_to_ready(cx);
+
+        loop {
+            if let Some(index) = self.ready_index.take() {
+                match self.services.check_ready_index(cx, index) {
+                    Ok(true) => {
+                        self.ready_index = Some(index);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(false) => {}
+                    Err(Failed(key, error)) => self.remove_failed(key, error),
+                }
+            }
+
+            self.ready_index = self.select();
+            if self.ready_index.is_none() {
+                // Try again once an endpoint starts serving.
+                for index in 0..self.services.ready_len() {
+                    let (_, svc) = self.services.get_ready_index(index).expect("invalid index");
+                    svc.register_health(cx.waker());
+                }
+                return Poll::Pending;
+            }
+        }
+    }
+
+    fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
+        let index = self.ready_index.take().expect("called before ready");
+        self.services.call_ready_index(index, req)
+    }
+}
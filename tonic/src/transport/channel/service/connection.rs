@@ -1,16 +1,31 @@
-use super::{AddOrigin, Reconnect, SharedExec, UserAgent};
+use super::{AddOrigin, HealthCheck, Reconnect, SharedExec, UserAgent};
 use crate::{
     body::{boxed, BoxBody},
-    transport::{channel::BoxFuture, service::GrpcTimeout, Endpoint},
+    transport::{
+        channel::{resolver::ResolveNow, BoxFuture, ConnectivityReporter, ConnectivityState},
+        service::{GrpcTimeout, IdleTracker},
+        Endpoint,
+    },
 };
 use http::Uri;
 use hyper::rt;
-use hyper::{client::conn::http2::Builder, rt::Executor};
+use hyper::{
+    client::conn::http2::{self, Builder},
+    rt::Executor,
+};
 use hyper_util::rt::TokioTimer;
 use std::{
     fmt,
-    task::{Context, Poll},
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
 };
+use tokio::sync::watch;
 use tower::load::Load;
 use tower::{
     layer::Layer,
@@ -23,12 +38,27 @@ use tower_service::Service;
 pub(crate) type Response<B = BoxBody> = http::Response<B>;
 pub(crate) type Request<B = BoxBody> = http::Request<B>;
 
+/// The HTTP/2 connection a [`Connection`] is currently established over, if
+/// any, with the number of the attempt that established it.
+pub(super) type Transport = Option<(u64, http2::SendRequest<BoxBody>)>;
+
 pub(crate) struct Connection {
     inner: BoxService<Request, Response, crate::Error>,
+    /// One reference is held by every call in flight, see [`Load`].
+    pending: Arc<()>,
+    /// Whether the last attempt to connect failed.
+    failed: Arc<AtomicBool>,
+    health: Option<HealthCheck>,
+    transport: watch::Receiver<Transport>,
 }
 
 impl Connection {
-    fn new<C>(connector: C, endpoint: Endpoint, is_lazy: bool) -> Self
+    fn new<C>(
+        connector: C,
+        endpoint: Endpoint,
+        is_lazy: bool,
+        connectivity: ConnectivityReporter,
+    ) -> Self
     where
         C: Service<Uri> + Send + 'static,
         C::Error: Into<crate::Error> + Send,
@@ -66,34 +96,94 @@ impl Connection {
             .option_layer(endpoint.rate_limit.map(|(l, d)| RateLimitLayer::new(l, d)))
             .into_inner();
 
-        let make_service =
-            MakeSendRequestService::new(connector, endpoint.executor.clone(), settings);
-
-        let conn = Reconnect::new(make_service, endpoint.uri.clone(), is_lazy);
+        let failed = Arc::new(AtomicBool::new(false));
+        let connectivity = Arc::new(connectivity);
+        let (transport_tx, transport) = watch::channel(None);
+        let make_service = MakeSendRequestService::new(
+            connector,
+            endpoint.executor.clone(),
+            settings,
+            endpoint.resolve_now.clone(),
+            failed.clone(),
+            connectivity.clone(),
+            endpoint.idle_timeout,
+            Arc::new(transport_tx),
+        );
+
+        let conn = Reconnect::new(
+            make_service,
+            endpoint.uri.clone(),
+            is_lazy,
+            connectivity,
+            endpoint.reconnect_backoff.clone(),
+        );
 
         Self {
             inner: BoxService::new(stack.layer(conn)),
+            pending: Arc::new(()),
+            failed,
+            health: None,
+            transport,
         }
     }
 
-    pub(crate) async fn connect<C>(connector: C, endpoint: Endpoint) -> Result<Self, crate::Error>
+    pub(crate) async fn connect<C>(
+        connector: C,
+        endpoint: Endpoint,
+        connectivity: ConnectivityReporter,
+    ) -> Result<Self, crate::Error>
     where
         C: Service<Uri> + Send + 'static,
         C::Error: Into<crate::Error> + Send,
         C::Future: Unpin + Send,
         C::Response: rt::Read + rt::Write + Unpin + Send + 'static,
     {
-        Self::new(connector, endpoint, false).ready_oneshot().await
+        Self::new(connector, endpoint, false, connectivity)
+            .ready_oneshot()
+            .await
     }
 
-    pub(crate) fn lazy<C>(connector: C, endpoint: Endpoint) -> Self
+    /// Whether the last attempt to connect to the endpoint failed, in which
+    /// case the next call will fail and the balancer should avoid it.
+    pub(crate) fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Watch the health of `service` on `endpoint`, the endpoint of this
+    /// connection, over the same HTTP/2 connection as the calls.
+    pub(crate) fn with_health_check(self, endpoint: &Endpoint, service: String) -> Self {
+        let health = HealthCheck::spawn(endpoint, service, self.transport.clone());
+        Connection {
+            health: Some(health),
+            ..self
+        }
+    }
+
+    /// Whether the endpoint is serving, according to its health check if it
+    /// has one.
+    pub(crate) fn is_serving(&self) -> bool {
+        !matches!(&self.health, Some(health) if !health.is_serving())
+    }
+
+    /// Wake `waker` once the endpoint's serving status changes.
+    pub(crate) fn register_health(&self, waker: &Waker) {
+        if let Some(health) = &self.health {
+            health.register(waker);
+        }
+    }
+
+    pub(crate) fn lazy<C>(
+        connector: C,
+        endpoint: Endpoint,
+        connectivity: ConnectivityReporter,
+    ) -> Self
     where
         C: Service<Uri> + Send + 'static,
         C::Error: Into<crate::Error> + Send,
         C::Future: Unpin + Send,
         C::Response: rt::Read + rt::Write + Unpin + Send + 'static,
     {
-        Self::new(connector, endpoint, true)
+        Self::new(connector, endpoint, true, connectivity)
     }
 }
 
@@ -107,15 +197,24 @@ impl Service<Request> for Connection {
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
-        self.inner.call(req)
+        let pending = self.pending.clone();
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+            drop(pending);
+            res
+        })
     }
 }
 
 impl Load for Connection {
     type Metric = usize;
 
+    /// The number of calls in flight, which lets the balancer steer calls
+    /// (such as hedged attempts) away from busy endpoints.
     fn load(&self) -> Self::Metric {
-        0
+        Arc::strong_count(&self.pending) - 1
     }
 }
 
@@ -125,13 +224,16 @@ impl fmt::Debug for Connection {
     }
 }
 
-struct SendRequest {
-    inner: hyper::client::conn::http2::SendRequest<BoxBody>,
+pub(super) struct SendRequest {
+    inner: http2::SendRequest<BoxBody>,
+    idle: Option<IdleTracker>,
 }
 
-impl From<hyper::client::conn::http2::SendRequest<BoxBody>> for SendRequest {
-    fn from(inner: hyper::client::conn::http2::SendRequest<BoxBody>) -> Self {
-        Self { inner }
+impl SendRequest {
+    /// Send calls over `inner` without counting them as activity on the
+    /// connection.
+    pub(super) fn untracked(inner: http2::SendRequest<BoxBody>) -> Self {
+        Self { inner, idle: None }
     }
 }
 
@@ -146,8 +248,12 @@ impl tower::Service<http::Request<BoxBody>> for SendRequest {
 
     fn call(&mut self, req: Request) -> Self::Future {
         let fut = self.inner.send_request(req);
+        let fut = async move { fut.await.map_err(Into::into).map(|res| res.map(boxed)) };
 
-        Box::pin(async move { fut.await.map_err(Into::into).map(|res| res.map(boxed)) })
+        match &self.idle {
+            Some(idle) => Box::pin(idle.track(fut)),
+            None => Box::pin(fut),
+        }
     }
 }
 
@@ -155,14 +261,36 @@ struct MakeSendRequestService<C> {
     connector: C,
     executor: SharedExec,
     settings: Builder<SharedExec>,
+    resolve_now: Option<ResolveNow>,
+    failed: Arc<AtomicBool>,
+    connectivity: Arc<ConnectivityReporter>,
+    idle_timeout: Option<Duration>,
+    transport: Arc<watch::Sender<Transport>>,
+    attempts: u64,
 }
 
 impl<C> MakeSendRequestService<C> {
-    fn new(connector: C, executor: SharedExec, settings: Builder<SharedExec>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        connector: C,
+        executor: SharedExec,
+        settings: Builder<SharedExec>,
+        resolve_now: Option<ResolveNow>,
+        failed: Arc<AtomicBool>,
+        connectivity: Arc<ConnectivityReporter>,
+        idle_timeout: Option<Duration>,
+        transport: Arc<watch::Sender<Transport>>,
+    ) -> Self {
         Self {
             connector,
             executor,
             settings,
+            resolve_now,
+            failed,
+            connectivity,
+            idle_timeout,
+            transport,
+            attempts: 0,
         }
     }
 }
@@ -186,21 +314,82 @@ where
         let fut = self.connector.call(req);
         let builder = self.settings.clone();
         let executor = self.executor.clone();
+        let resolve_now = self.resolve_now.clone();
+        let failed = self.failed.clone();
+        let connectivity = self.connectivity.clone();
+        let idle_timeout = self.idle_timeout;
+        let transport = self.transport.clone();
+        self.attempts += 1;
+        let attempt = self.attempts;
 
         Box::pin(async move {
-            let io = fut.await.map_err(Into::into)?;
-            let (send_request, conn) = builder.handshake(io).await?;
+            let io = match fut.await {
+                Ok(io) => io,
+                Err(e) => {
+                    failed.store(true, Ordering::Relaxed);
+                    if let Some(resolve_now) = resolve_now {
+                        resolve_now.notify();
+                    }
+                    return Err(e.into());
+                }
+            };
+            let (send_request, conn) = match builder.handshake(io).await {
+                Ok(handshake) => handshake,
+                Err(e) => {
+                    failed.store(true, Ordering::Relaxed);
+                    return Err(e.into());
+                }
+            };
+            failed.store(false, Ordering::Relaxed);
+            transport.send_replace(Some((attempt, send_request.clone())));
+
+            let idle = idle_timeout.map(|timeout| (IdleTracker::new(), timeout));
+            let tracker = idle.as_ref().map(|(tracker, _)| tracker.clone());
 
             Executor::<BoxFuture<'static, ()>>::execute(
                 &executor,
                 Box::pin(async move {
-                    if let Err(e) = conn.await {
-                        tracing::debug!("connection task error: {:?}", e);
+                    let mut conn = pin!(conn);
+                    let mut idle = pin!(idle
+                        .as_ref()
+                        .map(|(tracker, timeout)| tracker.idle(*timeout)));
+
+                    let result = poll_fn(|cx| {
+                        if let Poll::Ready(result) = conn.as_mut().poll(cx) {
+                            return Poll::Ready(Some(result));
+                        }
+                        if let Some(idle) = idle.as_mut().as_pin_mut() {
+                            if idle.poll(cx).is_ready() {
+                                return Poll::Ready(None);
+                            }
+                        }
+                        Poll::Pending
+                    })
+                    .await;
+
+                    match result {
+                        Some(Err(e)) => tracing::debug!("connection task error: {:?}", e),
+                        Some(Ok(())) => {}
+                        // Dropping the connection closes it.
+                        None => tracing::debug!("closing idle connection"),
                     }
+                    // The connection is gone, report it right away rather
+                    // than on the next call.
+                    connectivity.replace(ConnectivityState::Ready, ConnectivityState::Idle);
+                    transport.send_if_modified(|transport| match transport {
+                        Some((current, _)) if *current == attempt => {
+                            *transport = None;
+                            true
+                        }
+                        _ => false,
+                    });
                 }) as _,
             );
 
-            Ok(SendRequest::from(send_request))
+            Ok(SendRequest {
+                inner: send_request,
+                idle: tracker,
+            })
         })
     }
 }
@@ -1,4 +1,4 @@
-use super::super::{Connection, Endpoint};
+use super::super::{Connection, ConnectivityTracker, Endpoint};
 
 use hyper_util::client::legacy::connect::HttpConnector;
 use std::{
@@ -15,11 +15,18 @@ type DiscoverResult<K, S, E> = Result<Change<K, S>, E>;
 
 pub(crate) struct DynamicServiceStream<K: Hash + Eq + Clone> {
     changes: Receiver<Change<K, Endpoint>>,
+    connectivity: ConnectivityTracker,
 }
 
 impl<K: Hash + Eq + Clone> DynamicServiceStream<K> {
-    pub(crate) fn new(changes: Receiver<Change<K, Endpoint>>) -> Self {
-        Self { changes }
+    pub(crate) fn new(
+        changes: Receiver<Change<K, Endpoint>>,
+        connectivity: ConnectivityTracker,
+    ) -> Self {
+        Self {
+            changes,
+            connectivity,
+        }
     }
 }
 
@@ -38,7 +45,18 @@ impl<K: Hash + Eq + Clone> Stream for DynamicServiceStream<K> {
                     http.set_connect_timeout(endpoint.connect_timeout);
                     http.enforce_http(false);
 
-                    let connection = Connection::lazy(endpoint.connector(http), endpoint);
+                    let health = endpoint
+                        .health_check
+                        .clone()
+                        .map(|service| (endpoint.clone(), service));
+                    let mut connection = Connection::lazy(
+                        endpoint.connector(http),
+                        endpoint,
+                        self.connectivity.reporter(),
+                    );
+                    if let Some((endpoint, service)) = health {
+                        connection = connection.with_health_check(&endpoint, service);
+                    }
                     let change = Ok(Change::Insert(k, connection));
                     Poll::Ready(Some(change))
                 }
@@ -0,0 +1,290 @@
+use super::{
+    connection::{SendRequest, Transport},
+    retry::jitter,
+    AddOrigin, Executor, UserAgent,
+};
+use crate::{
+    client::Grpc,
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    transport::Endpoint,
+    Code, Request, Status,
+};
+use bytes::{Buf, BufMut};
+use http::{uri::PathAndQuery, HeaderValue, Uri};
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Poll, Waker},
+    time::Duration,
+};
+use tokio::sync::{oneshot, watch};
+
+const WATCH_PATH: &str = "/grpc.health.v1.Health/Watch";
+
+/// `grpc.health.v1.HealthCheckResponse.ServingStatus.SERVING`
+const SERVING: u64 = 1;
+
+/// Delay before watching the health of an endpoint again after the stream
+/// ended.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+type HealthClient = Grpc<AddOrigin<UserAgent<SendRequest>>>;
+
+/// The health of an endpoint, as reported by its `grpc.health.v1.Health`
+/// service.
+///
+/// The health is watched by a background task over the connection to the
+/// endpoint, whenever it is established, and stops once the `HealthCheck` is
+/// dropped.
+pub(crate) struct HealthCheck {
+    state: Arc<State>,
+    _cancel: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct State {
+    serving: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl State {
+    fn set_serving(&self, serving: bool) {
+        if self.serving.swap(serving, Ordering::AcqRel) != serving {
+            if let Some(waker) = self.waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl HealthCheck {
+    /// Start watching the health of `service` on `endpoint` over `transport`,
+    /// the connection to the endpoint.
+    pub(super) fn spawn(
+        endpoint: &Endpoint,
+        service: String,
+        transport: watch::Receiver<Transport>,
+    ) -> Self {
+        let origin = endpoint.origin.as_ref().unwrap_or(&endpoint.uri).clone();
+        let user_agent = endpoint.user_agent.clone();
+        let state = Arc::new(State::default());
+        let (cancel, cancelled) = oneshot::channel();
+
+        let task = watch_health(transport, origin, user_agent, service, state.clone());
+        endpoint.executor.execute(async move {
+            let mut task = pin!(task);
+            let mut cancelled = pin!(cancelled);
+            poll_fn(|cx| match cancelled.as_mut().poll(cx) {
+                Poll::Ready(_) => Poll::Ready(()),
+                Poll::Pending => task.as_mut().poll(cx),
+            })
+            .await
+        });
+
+        Self {
+            state,
+            _cancel: cancel,
+        }
+    }
+
+    /// Whether the endpoint last reported `SERVING`.
+    ///
+    /// Endpoints are not serving until their first report.
+    pub(crate) fn is_serving(&self) -> bool {
+        self.state.serving.load(Ordering::Acquire)
+    }
+
+    /// Wake `waker` once the health of the endpoint changes.
+    pub(crate) fn register(&self, waker: &Waker) {
+        *self.state.waker.lock().unwrap() = Some(waker.clone());
+    }
+}
+
+async fn watch_health(
+    mut transport: watch::Receiver<Transport>,
+    origin: Uri,
+    user_agent: Option<HeaderValue>,
+    service: String,
+    state: Arc<State>,
+) {
+    loop {
+        // Wait for the endpoint to be connected, the health check does not
+        // connect on its own.
+        let send_request = match transport
+            .wait_for(Option::is_some)
+            .await
+            .map(|transport| transport.as_ref().map(|(_, inner)| inner.clone()))
+        {
+            Ok(Some(send_request)) => send_request,
+            // The connection is gone.
+            _ => return,
+        };
+        let mut client = Grpc::new(AddOrigin::new(
+            UserAgent::new(SendRequest::untracked(send_request), user_agent.clone()),
+            origin.clone(),
+        ));
+
+        match watch_once(&mut client, service.clone(), &state).await {
+            Ok(()) => tracing::debug!("health watch stream ended"),
+            Err(status) if status.code() == Code::Unimplemented => {
+                // Health checking is disabled for servers that do not
+                // implement the health service.
+                tracing::debug!("health service not implemented, assuming serving");
+                state.set_serving(true);
+                return;
+            }
+            Err(status) => tracing::debug!("health watch failed: {}", status),
+        }
+
+        state.set_serving(false);
+        tokio::time::sleep(RETRY_BACKOFF + jitter(RETRY_BACKOFF)).await;
+    }
+}
+
+async fn watch_once(
+    client: &mut HealthClient,
+    service: String,
+    state: &State,
+) -> Result<(), Status> {
+    client.ready().await.map_err(Status::from_error)?;
+
+    let mut stream = client
+        .server_streaming(
+            Request::new(service),
+            PathAndQuery::from_static(WATCH_PATH),
+            HealthCodec,
+        )
+        .await?
+        .into_inner();
+
+    while let Some(status) = stream.message().await? {
+        state.set_serving(status == SERVING);
+    }
+
+    Ok(())
+}
+
+/// Encodes `grpc.health.v1.HealthCheckRequest` messages from their service
+/// name and decodes the status of `grpc.health.v1.HealthCheckResponse`
+/// messages, without depending on generated code.
+#[derive(Debug, Clone, Copy)]
+struct HealthCodec;
+
+impl Codec for HealthCodec {
+    type Encode = String;
+    type Decode = u64;
+    type Encoder = HealthCodec;
+    type Decoder = HealthCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        *self
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        *self
+    }
+}
+
+impl Encoder for HealthCodec {
+    type Item = String;
+    type Error = Status;
+
+    fn encode(&mut self, service: String, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        if !service.is_empty() {
+            // Field 1, length delimited.
+            dst.put_u8(0x0a);
+            put_varint(dst, service.len() as u64);
+            dst.put_slice(service.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for HealthCodec {
+    type Item = u64;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<u64>, Status> {
+        let invalid = || Status::internal("invalid health check response");
+        // Proto3 omits fields with the default value, `UNKNOWN`.
+        let mut status = 0;
+
+        while src.has_remaining() {
+            let key = get_varint(src).ok_or_else(invalid)?;
+            match (key >> 3, key & 0x7) {
+                (1, 0) => status = get_varint(src).ok_or_else(invalid)?,
+                // Skip unknown fields.
+                (_, 0) => {
+                    get_varint(src).ok_or_else(invalid)?;
+                }
+                (_, 1) => skip(src, 8).ok_or_else(invalid)?,
+                (_, 2) => {
+                    let len = get_varint(src).ok_or_else(invalid)?;
+                    skip(src, len as usize).ok_or_else(invalid)?;
+                }
+                (_, 5) => skip(src, 4).ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Some(status))
+    }
+}
+
+fn put_varint(dst: &mut impl BufMut, mut value: u64) {
+    while value >= 0x80 {
+        dst.put_u8(value as u8 | 0x80);
+        value >>= 7;
+    }
+    dst.put_u8(value as u8);
+}
+
+fn get_varint(src: &mut impl Buf) -> Option<u64> {
+    let mut value = 0;
+
+    for shift in (0..64).step_by(7) {
+        if !src.has_remaining() {
+            return None;
+        }
+        let byte = src.get_u8();
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+fn skip(src: &mut impl Buf, len: usize) -> Option<()> {
+    if src.remaining() < len {
+        return None;
+    }
+    src.advance(len);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    #[test]
+    fn varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut buf = BytesMut::new();
+            put_varint(&mut buf, value);
+            assert_eq!(get_varint(&mut buf.freeze()), Some(value));
+        }
+    }
+
+    #[test]
+    fn truncated_varint() {
+        assert_eq!(get_varint(&mut &[0x80u8][..]), None);
+    }
+}
@@ -0,0 +1,285 @@
+use super::retry::{attempt_code, call_timeout, clone_parts, pushback, RetryConfig};
+use crate::{
+    body::{boxed, BoxBody},
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::duration_to_grpc_timeout,
+    transport::channel::{BoxFuture, HedgingPolicy},
+    Code,
+};
+use http::{request, HeaderValue, Request, Response};
+use http_body_util::{BodyExt, Full};
+use std::{
+    future::{poll_fn, Future},
+    sync::Arc,
+    task::Poll,
+};
+use tokio::time::Instant;
+use tower::ServiceExt;
+use tower_service::Service;
+
+type Attempt = BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>;
+
+enum Event {
+    Hedge,
+    Done(usize, Result<Response<BoxBody>, crate::Error>),
+}
+
+/// Send `req` according to a hedging `policy`, returning the first response
+/// that is not a non-fatal failure.
+///
+/// No more attempts are sent once the call's deadline has passed. `svc` must
+/// already be ready, it is used for the first attempt.
+pub(super) async fn hedge<S>(
+    mut svc: S,
+    config: Arc<RetryConfig>,
+    policy: HedgingPolicy,
+    req: Request<BoxBody>,
+) -> Result<Response<BoxBody>, crate::Error>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::Error> + Send,
+    S::Future: Send + 'static,
+{
+    let has_timeout_header = req.headers().contains_key(GRPC_TIMEOUT_HEADER);
+    let deadline =
+        call_timeout(req.headers(), config.timeout).map(|timeout| Instant::now() + timeout);
+
+    let (mut parts, body) = req.into_parts();
+    let body = body.collect().await?.to_bytes();
+
+    let first = svc.call(Request::from_parts(
+        clone_parts(&parts),
+        boxed(Full::new(body.clone())),
+    ));
+    let mut in_flight: Vec<Attempt> = vec![Box::pin(attempt(first))];
+    let mut sent = 1;
+    let mut last = None;
+    let mut delay = Box::pin(tokio::time::sleep(policy.hedging_delay));
+
+    loop {
+        let event = poll_fn(|cx| {
+            if sent < policy.max_attempts && delay.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Event::Hedge);
+            }
+
+            for (i, attempt) in in_flight.iter_mut().enumerate() {
+                if let Poll::Ready(result) = attempt.as_mut().poll(cx) {
+                    return Poll::Ready(Event::Done(i, result));
+                }
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        match event {
+            Event::Hedge => {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    tracing::debug!("call deadline passed, not hedging");
+                    sent = policy.max_attempts;
+
+                    match last.take() {
+                        Some(result) if in_flight.is_empty() => return result,
+                        _ => continue,
+                    }
+                }
+
+                if let Some(throttle) = &config.throttle {
+                    if !throttle.allows_retry() {
+                        tracing::debug!("hedging throttled");
+                        sent = policy.max_attempts;
+
+                        match last.take() {
+                            Some(result) if in_flight.is_empty() => return result,
+                            _ => continue,
+                        }
+                    }
+                }
+
+                if let (true, Some(deadline)) = (has_timeout_header, deadline) {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let value = duration_to_grpc_timeout(remaining);
+                    parts
+                        .headers
+                        .insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_str(&value).unwrap());
+                }
+
+                tracing::debug!("sending hedged attempt {}", sent + 1);
+                in_flight.push(send(svc.clone(), &parts, body.clone()));
+                sent += 1;
+                delay.as_mut().reset(Instant::now() + policy.hedging_delay);
+            }
+            Event::Done(i, result) => {
+                drop(in_flight.swap_remove(i));
+
+                match attempt_code(&result) {
+                    Some(code) if code != Code::Ok && policy.is_non_fatal(code) => {}
+                    // Responses that were committed to, whose status only
+                    // comes with the trailers, count as successes too.
+                    _ => {
+                        if let (Some(throttle), true) = (&config.throttle, result.is_ok()) {
+                            throttle.on_success();
+                        }
+                        return result;
+                    }
+                }
+
+                if let Some(throttle) = &config.throttle {
+                    throttle.on_failure();
+                }
+
+                match pushback(&result) {
+                    Some(Some(pushback)) => delay.as_mut().reset(Instant::now() + pushback),
+                    // The server asked not to send any more attempts.
+                    Some(None) => sent = policy.max_attempts,
+                    None => delay.as_mut().reset(Instant::now()),
+                }
+
+                if in_flight.is_empty() && sent >= policy.max_attempts {
+                    return result;
+                }
+
+                last = Some(result);
+            }
+        }
+    }
+}
+
+fn send<S>(svc: S, parts: &request::Parts, body: bytes::Bytes) -> Attempt
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Send + 'static,
+    S::Error: Into<crate::Error> + Send,
+    S::Future: Send + 'static,
+{
+    let req = Request::from_parts(clone_parts(parts), boxed(Full::new(body)));
+
+    Box::pin(attempt(svc.oneshot(req)))
+}
+
+async fn attempt<F, E>(fut: F) -> Result<Response<BoxBody>, crate::Error>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+    E: Into<crate::Error>,
+{
+    fut.await.map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::empty_body;
+    use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+    /// Answers every attempt with the next scripted status code after the
+    /// scripted delay, sending `Ok` in the trailers as servers do and failures
+    /// in trailers-only responses.
+    #[derive(Clone, Default)]
+    struct Svc {
+        script: Arc<Mutex<VecDeque<(Duration, Code)>>>,
+        calls: Arc<Mutex<usize>>,
+    }
+
+    impl Svc {
+        fn script(&self, attempts: impl IntoIterator<Item = (u64, Code)>) {
+            let attempts = attempts
+                .into_iter()
+                .map(|(delay, code)| (Duration::from_millis(delay), code));
+            self.script.lock().unwrap().extend(attempts);
+        }
+
+        fn calls(&self) -> usize {
+            std::mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    impl Service<Request<BoxBody>> for Svc {
+        type Response = Response<BoxBody>;
+        type Error = crate::Error;
+        type Future = BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>;
+
+        fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<BoxBody>) -> Self::Future {
+            *self.calls.lock().unwrap() += 1;
+            let (delay, code) = self.script.lock().unwrap().pop_front().unwrap();
+
+            let mut res = Response::new(empty_body());
+            if code != Code::Ok {
+                res.headers_mut()
+                    .insert("grpc-status", HeaderValue::from(code as i32));
+            }
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                Ok(res)
+            })
+        }
+    }
+
+    fn policy() -> HedgingPolicy {
+        HedgingPolicy::new()
+            .max_attempts(2)
+            .hedging_delay(Duration::from_millis(100))
+            .non_fatal_codes([Code::Unavailable])
+    }
+
+    async fn call(svc: &Svc, config: &Arc<RetryConfig>, policy: HedgingPolicy) -> Code {
+        let res = hedge(
+            svc.clone(),
+            config.clone(),
+            policy,
+            Request::new(empty_body()),
+        )
+        .await
+        .unwrap();
+
+        res.headers()
+            .get("grpc-status")
+            .map_or(Code::Ok, |code| Code::from_bytes(code.as_bytes()))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successes_restore_throttled_hedging() {
+        let svc = Svc::default();
+        // Four tokens: hedging stops once two or fewer are left.
+        let config = Arc::new(RetryConfig::new(None, Some((4, 1.0)), None));
+
+        // Both attempts fail, leaving two tokens.
+        svc.script([(0, Code::Unavailable), (0, Code::Unavailable)]);
+        assert_eq!(call(&svc, &config, policy()).await, Code::Unavailable);
+        assert_eq!(svc.calls(), 2);
+
+        // Throttled, the failure is not hedged.
+        svc.script([(0, Code::Unavailable)]);
+        assert_eq!(call(&svc, &config, policy()).await, Code::Unavailable);
+        assert_eq!(svc.calls(), 1);
+
+        // Successes refill the bucket back to four tokens.
+        for _ in 0..3 {
+            svc.script([(0, Code::Ok)]);
+            assert_eq!(call(&svc, &config, policy()).await, Code::Ok);
+        }
+        assert_eq!(svc.calls(), 3);
+
+        svc.script([(0, Code::Unavailable), (0, Code::Ok)]);
+        assert_eq!(call(&svc, &config, policy()).await, Code::Ok);
+        assert_eq!(svc.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_hedges_after_deadline() {
+        let svc = Svc::default();
+        let timeout = Duration::from_millis(150);
+        let config = Arc::new(RetryConfig::new(None, None, Some(timeout)));
+
+        svc.script([(1000, Code::Ok), (1000, Code::Ok), (1000, Code::Ok)]);
+        let start = Instant::now();
+        assert_eq!(
+            call(&svc, &config, policy().max_attempts(3)).await,
+            Code::Ok
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(svc.calls(), 2);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    body::{boxed, BoxBody},
+    extensions::MaxDecodingMessageSize,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::duration_to_grpc_timeout,
+    transport::{channel::MethodConfig, service::grpc_timeout::try_parse_grpc_timeout},
+    Status,
+};
+use bytes::Bytes;
+use http::{HeaderValue, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+/// Length of the prefix of every gRPC message: a compression flag followed by
+/// the message length as a big endian `u32`.
+const HEADER_SIZE: usize = 5;
+
+/// The parts of a [`MethodConfig`] that are enforced on the response of a
+/// call.
+pub(super) struct MethodLimits {
+    request_error: Option<Arc<Mutex<Option<Status>>>>,
+    max_response_message_bytes: Option<usize>,
+}
+
+/// Applies the timeout and request message limit of `method` to `req`.
+///
+/// Returns the limits that still need to be applied to the response, if any.
+pub(super) fn apply(
+    method: &MethodConfig,
+    mut req: Request<BoxBody>,
+) -> (Request<BoxBody>, Option<MethodLimits>) {
+    if let Some(timeout) = method.timeout() {
+        let timeout = match try_parse_grpc_timeout(req.headers()).ok().flatten() {
+            Some(header) => header.min(timeout),
+            None => timeout,
+        };
+        let value = duration_to_grpc_timeout(timeout);
+        req.headers_mut()
+            .insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_str(&value).unwrap());
+    }
+
+    let mut request_error = None;
+    if let Some(limit) = method.max_request_message_bytes() {
+        let error = Arc::new(Mutex::new(None));
+        request_error = Some(error.clone());
+        req = req.map(|body| {
+            boxed(LimitBody {
+                inner: body,
+                limit,
+                header: [0; HEADER_SIZE],
+                header_len: 0,
+                remaining: 0,
+                error,
+            })
+        });
+    }
+
+    let max_response_message_bytes = method.max_response_message_bytes();
+    if request_error.is_none() && max_response_message_bytes.is_none() {
+        return (req, None);
+    }
+
+    let limits = MethodLimits {
+        request_error,
+        max_response_message_bytes,
+    };
+
+    (req, Some(limits))
+}
+
+impl MethodLimits {
+    pub(super) async fn wrap<F, E>(self, fut: F) -> Result<Response<BoxBody>, crate::Error>
+    where
+        F: Future<Output = Result<Response<BoxBody>, E>>,
+        E: Into<crate::Error>,
+    {
+        let result = fut.await.map_err(Into::into);
+
+        // Sending an oversized message fails the call, whatever the outcome
+        // of the request reported by the connection.
+        if let Some(error) = &self.request_error {
+            if let Some(status) = error.lock().unwrap().take() {
+                return Err(status.into());
+            }
+        }
+
+        let mut res = result?;
+        if let Some(limit) = self.max_response_message_bytes {
+            res.extensions_mut().insert(MaxDecodingMessageSize(limit));
+        }
+
+        Ok(res)
+    }
+}
+
+/// A request body failing as soon as it carries a gRPC message larger than
+/// `limit`.
+#[pin_project]
+struct LimitBody {
+    #[pin]
+    inner: BoxBody,
+    limit: usize,
+    header: [u8; HEADER_SIZE],
+    header_len: usize,
+    remaining: usize,
+    error: Arc<Mutex<Option<Status>>>,
+}
+
+impl Body for LimitBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = match ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            other => return Poll::Ready(other),
+        };
+
+        let mut data = frame.data_ref().map(|data| &data[..]).unwrap_or_default();
+        while !data.is_empty() {
+            if *this.remaining > 0 {
+                let n = data.len().min(*this.remaining);
+                *this.remaining -= n;
+                data = &data[n..];
+                continue;
+            }
+
+            let n = data.len().min(HEADER_SIZE - *this.header_len);
+            this.header[*this.header_len..*this.header_len + n].copy_from_slice(&data[..n]);
+            *this.header_len += n;
+            data = &data[n..];
+
+            if *this.header_len == HEADER_SIZE {
+                let len = u32::from_be_bytes([
+                    this.header[1],
+                    this.header[2],
+                    this.header[3],
+                    this.header[4],
+                ]) as usize;
+
+                if len > *this.limit {
+                    let status = Status::resource_exhausted(format!(
+                        "request message larger than max ({} vs. {})",
+                        len, this.limit
+                    ));
+                    *this.error.lock().unwrap() = Some(status.clone());
+                    return Poll::Ready(Some(Err(status)));
+                }
+
+                *this.header_len = 0;
+                *this.remaining = len;
+            }
+        }
+
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, StreamBody};
+    use std::convert::Infallible;
+
+    fn message(len: usize) -> Bytes {
+        let mut buf = vec![0];
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+        buf.resize(HEADER_SIZE + len, 1);
+        buf.into()
+    }
+
+    fn limited(chunks: Vec<Bytes>, limit: usize) -> LimitBody {
+        let stream = tokio_stream::iter(
+            chunks
+                .into_iter()
+                .map(|chunk| Ok::<_, Infallible>(Frame::data(chunk))),
+        );
+        LimitBody {
+            inner: boxed(StreamBody::new(stream)),
+            limit,
+            header: [0; HEADER_SIZE],
+            header_len: 0,
+            remaining: 0,
+            error: Arc::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn allows_messages_within_limit() {
+        let mut chunks = vec![message(4), message(8)];
+        // A message header split across two chunks.
+        let split = message(8);
+        chunks.push(split.slice(..3));
+        chunks.push(split.slice(3..));
+
+        let body = limited(chunks, 8);
+        assert!(body.collect().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_message() {
+        let oversized = message(9);
+        let chunks = vec![message(1), oversized.slice(..2), oversized.slice(2..)];
+        let body = limited(chunks, 8);
+        let error = body.error.clone();
+
+        let status = body.collect().await.unwrap_err();
+        assert_eq!(status.code(), crate::Code::ResourceExhausted);
+        assert!(error.lock().unwrap().is_some());
+    }
+}
@@ -5,7 +5,10 @@ mod user_agent;
 use self::user_agent::UserAgent;
 
 mod reconnect;
-use self::reconnect::Reconnect;
+use self::reconnect::{BackingOff, Reconnect};
+
+mod balance;
+pub(super) use self::balance::Balance;
 
 mod connection;
 pub(super) use self::connection::Connection;
@@ -22,7 +25,21 @@ pub(crate) use self::connector::{ConnectError, Connector};
 mod executor;
 pub(super) use self::executor::{Executor, SharedExec};
 
-#[cfg(feature = "tls")]
+mod health;
+pub(super) use self::health::HealthCheck;
+
+mod hedge;
+
+#[cfg(feature = "service-config")]
+mod method_config;
+
+mod retry;
+pub(super) use self::retry::{ResponseFuture as RetryResponseFuture, Retry, RetryConfig};
+
+mod wait_for_ready;
+pub(super) use self::wait_for_ready::Readiness;
+
+#[cfg(feature = "_tls-any")]
 mod tls;
-#[cfg(feature = "tls")]
-pub(super) use self::tls::TlsConnector;
+#[cfg(feature = "_tls-any")]
+pub(crate) use self::tls::{SharedConfig, TlsConnector, Verification};
@@ -1,11 +1,19 @@
+use super::ConnectError;
+use crate::transport::{
+    channel::{ConnectivityReporter, ConnectivityState, ReconnectBackoff},
+    service::random,
+};
 use crate::Error;
 use pin_project::pin_project;
 use std::fmt;
 use std::{
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
+use tokio::time::{Instant, Sleep};
 use tower::make::MakeService;
 use tower_service::Service;
 use tracing::trace;
@@ -21,21 +29,111 @@ where
     error: Option<crate::Error>,
     has_been_connected: bool,
     is_lazy: bool,
+    connectivity: Arc<ConnectivityReporter>,
+    backoff: Option<Backoff>,
 }
 
 #[derive(Debug)]
 enum State<F, S> {
     Idle,
-    Connecting(F),
+    /// Connecting, until the optional connect timeout expires.
+    Connecting(F, Option<Pin<Box<Sleep>>>),
     Connected(S),
 }
 
+/// Where backoffs too long to be represented end, as tokio does for timers.
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// The state of the connection backoff protocol.
+#[derive(Debug)]
+struct Backoff {
+    config: ReconnectBackoff,
+    current: Duration,
+    /// When the next attempt to connect may start.
+    retry_at: Option<Instant>,
+    /// The error of the last attempt, if it failed.
+    last_error: Option<String>,
+}
+
+impl Backoff {
+    fn new(config: ReconnectBackoff) -> Self {
+        Self {
+            current: config.initial_backoff,
+            config,
+            retry_at: None,
+            last_error: None,
+        }
+    }
+
+    /// The error calls fail with while backing off, if the next attempt
+    /// cannot start yet.
+    fn check(&self) -> Option<Error> {
+        let (retry_at, last_error) = (self.retry_at?, self.last_error.as_deref()?);
+        let now = Instant::now();
+        if now >= retry_at {
+            return None;
+        }
+        let remaining = retry_at - now;
+
+        let message = format!(
+            "reconnecting in {:?}, last attempt failed: {}",
+            remaining, last_error
+        );
+        Some(ConnectError(Box::new(BackingOff { retry_at, message })).into())
+    }
+
+    /// Starts an attempt to connect and returns how long it may take.
+    fn start_attempt(&mut self) -> Duration {
+        let backoff = match self.last_error {
+            Some(_) => self.config.jittered(self.current, random()),
+            None => self.current,
+        };
+        let now = Instant::now();
+        self.retry_at = Some(now.checked_add(backoff).unwrap_or(now + FAR_FUTURE));
+
+        backoff.max(self.config.min_connect_timeout)
+    }
+
+    fn on_failure(&mut self, error: &Error) {
+        self.current = self.config.next(self.current);
+        self.last_error = Some(error.to_string());
+    }
+
+    fn on_success(&mut self) {
+        self.current = self.config.initial_backoff;
+        self.retry_at = None;
+        self.last_error = None;
+    }
+}
+
+/// The error calls fail with while the connection is backing off.
+#[derive(Debug)]
+pub(crate) struct BackingOff {
+    /// When the next attempt to connect may start.
+    pub(super) retry_at: Instant,
+    pub(super) message: String,
+}
+
+impl fmt::Display for BackingOff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BackingOff {}
+
 impl<M, Target> Reconnect<M, Target>
 where
     M: Service<Target>,
     M::Error: Into<Error>,
 {
-    pub(crate) fn new(mk_service: M, target: Target, is_lazy: bool) -> Self {
+    pub(crate) fn new(
+        mk_service: M,
+        target: Target,
+        is_lazy: bool,
+        connectivity: Arc<ConnectivityReporter>,
+        backoff: Option<ReconnectBackoff>,
+    ) -> Self {
         Reconnect {
             mk_service,
             state: State::Idle,
@@ -43,6 +141,8 @@ where
             error: None,
             has_been_connected: false,
             is_lazy,
+            connectivity,
+            backoff: backoff.map(Backoff::new),
         }
     }
 }
@@ -71,6 +171,12 @@ where
             match self.state {
                 State::Idle => {
                     trace!("poll_ready; idle");
+                    if let Some(error) = self.backoff.as_ref().and_then(Backoff::check) {
+                        trace!("poll_ready; backing off");
+                        self.error = Some(error);
+                        return Poll::Ready(Ok(()));
+                    }
+
                     match self.mk_service.poll_ready(cx) {
                         Poll::Ready(r) => r?,
                         Poll::Pending => {
@@ -80,28 +186,51 @@ where
                     }
 
                     let fut = self.mk_service.make_service(self.target.clone());
-                    self.state = State::Connecting(fut);
+                    let timeout = self
+                        .backoff
+                        .as_mut()
+                        .map(|backoff| Box::pin(tokio::time::sleep(backoff.start_attempt())));
+                    self.state = State::Connecting(fut, timeout);
+                    self.connectivity.set(ConnectivityState::Connecting);
                     continue;
                 }
-                State::Connecting(ref mut f) => {
+                State::Connecting(ref mut f, ref mut timeout) => {
                     trace!("poll_ready; connecting");
-                    match Pin::new(f).poll(cx) {
-                        Poll::Ready(Ok(service)) => {
-                            state = State::Connected(service);
-                        }
+                    let result = match Pin::new(f).poll(cx) {
+                        Poll::Ready(result) => result.map_err(Error::from),
                         Poll::Pending => {
-                            trace!("poll_ready; not ready");
-                            return Poll::Pending;
+                            let timed_out = match timeout {
+                                Some(timeout) => timeout.as_mut().poll(cx).is_ready(),
+                                None => false,
+                            };
+                            if !timed_out {
+                                trace!("poll_ready; not ready");
+                                return Poll::Pending;
+                            }
+                            Err(ConnectError("connect timed out".into()).into())
                         }
-                        Poll::Ready(Err(e)) => {
+                    };
+
+                    match result {
+                        Ok(service) => {
+                            state = State::Connected(service);
+                            self.connectivity.set(ConnectivityState::Ready);
+                            if let Some(backoff) = &mut self.backoff {
+                                backoff.on_success();
+                            }
+                        }
+                        Err(error) => {
                             trace!("poll_ready; error");
 
                             state = State::Idle;
+                            self.connectivity.set(ConnectivityState::TransientFailure);
+                            if let Some(backoff) = &mut self.backoff {
+                                backoff.on_failure(&error);
+                            }
 
                             if !(self.has_been_connected || self.is_lazy) {
-                                return Poll::Ready(Err(e.into()));
+                                return Poll::Ready(Err(error));
                             } else {
-                                let error = e.into();
                                 tracing::debug!("reconnect::poll_ready: {:?}", error);
                                 self.error = Some(error);
                                 break;
@@ -126,6 +255,7 @@ where
                         Poll::Ready(Err(_)) => {
                             trace!("poll_ready; error");
                             state = State::Idle;
+                            self.connectivity.set(ConnectivityState::Idle);
                         }
                     }
                 }
@@ -220,3 +350,124 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::channel::{BoxFuture, ConnectivityTracker};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    struct Svc;
+
+    impl Service<()> for Svc {
+        type Response = ();
+        type Error = Error;
+        type Future = std::future::Ready<Result<(), Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            std::future::ready(Ok(()))
+        }
+    }
+
+    /// Records the time of every attempt to connect, which either fails
+    /// right away or never completes.
+    #[derive(Clone, Default)]
+    struct MakeSvc {
+        attempts: Arc<Mutex<Vec<Instant>>>,
+        hang: bool,
+    }
+
+    impl Service<()> for MakeSvc {
+        type Response = Svc;
+        type Error = Error;
+        type Future = BoxFuture<'static, Result<Svc, Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            self.attempts.lock().unwrap().push(Instant::now());
+            if self.hang {
+                Box::pin(std::future::pending())
+            } else {
+                Box::pin(async { Err(ConnectError("refused".into()).into()) })
+            }
+        }
+    }
+
+    fn reconnect(make: MakeSvc, backoff: ReconnectBackoff) -> Reconnect<MakeSvc, ()> {
+        let (connectivity, _) = ConnectivityTracker::new();
+        Reconnect::new(
+            make,
+            (),
+            true,
+            Arc::new(connectivity.reporter()),
+            Some(backoff),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_between_attempts() {
+        let make = MakeSvc::default();
+        let backoff = ReconnectBackoff::new()
+            .initial_backoff(Duration::from_secs(1))
+            .multiplier(2.0)
+            .jitter(0.0)
+            .max_backoff(Duration::from_secs(3));
+        let mut svc = reconnect(make.clone(), backoff);
+
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            let err = svc.ready().await.unwrap().call(()).await.unwrap_err();
+            assert!(err.is::<ConnectError>());
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        let attempts = make
+            .attempts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|attempt| attempt.duration_since(start).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(attempts, [0, 1, 3, 6, 9]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn long_backoffs_saturate() {
+        let make = MakeSvc::default();
+        let backoff = ReconnectBackoff::new()
+            .initial_backoff(Duration::MAX)
+            .max_backoff(Duration::MAX);
+        let mut svc = reconnect(make.clone(), backoff);
+
+        for _ in 0..2 {
+            let err = svc.ready().await.unwrap().call(()).await.unwrap_err();
+            assert!(err.is::<ConnectError>());
+        }
+        assert_eq!(make.attempts.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connect_times_out() {
+        let make = MakeSvc {
+            hang: true,
+            ..Default::default()
+        };
+        let backoff = ReconnectBackoff::new()
+            .initial_backoff(Duration::from_secs(1))
+            .min_connect_timeout(Duration::from_secs(5));
+        let mut svc = reconnect(make, backoff);
+
+        let start = Instant::now();
+        let err = svc.ready().await.unwrap().call(()).await.unwrap_err();
+        assert_eq!(err.to_string(), "connect timed out");
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
+}
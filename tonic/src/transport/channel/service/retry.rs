@@ -0,0 +1,752 @@
+#[cfg(feature = "service-config")]
+use super::method_config;
+use super::{
+    hedge::hedge,
+    wait_for_ready::{AwaitReady, Readiness},
+    ConnectError,
+};
+#[cfg(feature = "service-config")]
+use crate::transport::channel::{MethodConfig, ServiceConfig};
+use crate::{
+    body::{boxed, BoxBody},
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::duration_to_grpc_timeout,
+    transport::{
+        channel::{self, BoxFuture, HedgingPolicy, RetryPolicy},
+        service::{grpc_timeout::try_parse_grpc_timeout, random},
+    },
+    Code, Status,
+};
+use bytes::{Bytes, BytesMut};
+use http::{request, HeaderMap, HeaderValue, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::Full;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower::ServiceExt;
+use tower_service::Service;
+
+const GRPC_RETRY_PUSHBACK_HEADER: &str = "grpc-retry-pushback-ms";
+
+/// The largest request body kept around to be replayed on a retry.
+const MAX_REPLAY_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Per channel retry and hedging settings shared by every call.
+#[derive(Clone, Default)]
+pub(crate) struct RetryConfig {
+    policy: Option<RetryPolicy>,
+    hedging: HashMap<String, HedgingPolicy>,
+    pub(super) throttle: Option<Arc<Throttle>>,
+    pub(super) timeout: Option<Duration>,
+    wait_for_ready: bool,
+    #[cfg(feature = "service-config")]
+    service_config: Option<Arc<ServiceConfig>>,
+}
+
+impl RetryConfig {
+    pub(crate) fn new(
+        policy: Option<RetryPolicy>,
+        throttling: Option<(u32, f64)>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            policy,
+            hedging: HashMap::new(),
+            throttle: throttling
+                .map(|(max_tokens, token_ratio)| Arc::new(Throttle::new(max_tokens, token_ratio))),
+            timeout,
+            wait_for_ready: false,
+            #[cfg(feature = "service-config")]
+            service_config: None,
+        }
+    }
+
+    /// Apply the per method settings of a service config.
+    ///
+    /// The config's retry throttling is only used if the channel has none.
+    #[cfg(feature = "service-config")]
+    pub(crate) fn set_service_config(&mut self, config: Arc<ServiceConfig>) {
+        if self.throttle.is_none() {
+            self.throttle = config
+                .retry_throttling
+                .map(|(max_tokens, token_ratio)| Arc::new(Throttle::new(max_tokens, token_ratio)));
+        }
+        self.service_config = Some(config);
+    }
+
+    #[cfg(feature = "service-config")]
+    fn method_config(&self, path: &str) -> Option<&MethodConfig> {
+        self.service_config.as_ref()?.method_config_for_path(path)
+    }
+
+    pub(crate) fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.policy = Some(policy);
+    }
+
+    pub(crate) fn set_retry_throttling(&mut self, max_tokens: u32, token_ratio: f64) {
+        self.throttle = Some(Arc::new(Throttle::new(max_tokens, token_ratio)));
+    }
+
+    /// Whether calls wait for the channel to connect by default.
+    pub(crate) fn set_wait_for_ready(&mut self, wait_for_ready: bool) {
+        self.wait_for_ready = wait_for_ready;
+    }
+
+    /// Hedge calls to `method`, either `package.Service/Method` or
+    /// `package.Service` for every method of the service.
+    pub(crate) fn set_hedging_policy(&mut self, method: String, policy: HedgingPolicy) {
+        self.hedging.insert(method, policy);
+    }
+
+    fn hedging_policy(&self, path: &str) -> Option<&HedgingPolicy> {
+        if self.hedging.is_empty() {
+            return None;
+        }
+
+        let method = path.trim_start_matches('/');
+        let service = method.split_once('/').map(|(service, _)| service)?;
+
+        self.hedging
+            .get(method)
+            .or_else(|| self.hedging.get(service))
+    }
+}
+
+/// Token bucket limiting the rate of retries, as described in the gRPC retry
+/// design. Retries are only attempted while more than half the tokens remain.
+pub(super) struct Throttle {
+    max_tokens: f64,
+    token_ratio: f64,
+    tokens: Mutex<f64>,
+}
+
+impl Throttle {
+    fn new(max_tokens: u32, token_ratio: f64) -> Self {
+        let max_tokens = f64::from(max_tokens);
+        Self {
+            max_tokens,
+            token_ratio,
+            tokens: Mutex::new(max_tokens),
+        }
+    }
+
+    pub(super) fn on_success(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.token_ratio).min(self.max_tokens);
+    }
+
+    /// Records a failed attempt and returns whether a retry is still allowed.
+    pub(super) fn on_failure(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens - 1.0).max(0.0);
+        *tokens > self.max_tokens / 2.0
+    }
+
+    pub(super) fn allows_retry(&self) -> bool {
+        *self.tokens.lock().unwrap() > self.max_tokens / 2.0
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Retry<S> {
+    inner: S,
+    config: Option<Arc<RetryConfig>>,
+    readiness: Readiness,
+}
+
+impl<S> Retry<S> {
+    pub(crate) fn new(inner: S, config: Option<RetryConfig>, readiness: Readiness) -> Self {
+        Self {
+            inner,
+            config: config.map(Arc::new),
+            readiness,
+        }
+    }
+
+    pub(crate) fn config_mut(&mut self) -> &mut RetryConfig {
+        Arc::make_mut(self.config.get_or_insert_with(Default::default))
+    }
+}
+
+impl<S> Service<Request<BoxBody>> for Retry<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
+        let config = match &self.config {
+            Some(config) => config.clone(),
+            None => return ResponseFuture::new(self.inner.call(req)),
+        };
+
+        let hedging = config.hedging_policy(req.uri().path()).cloned();
+        let retry_policy = config.policy.clone();
+        let wait_for_ready = req
+            .extensions()
+            .get::<channel::WaitForReady>()
+            .map(|wait_for_ready| wait_for_ready.0);
+        // Policies from the service config replace the channel's retry policy
+        // but not its hedging policies.
+        #[cfg(feature = "service-config")]
+        let (hedging, retry_policy, wait_for_ready, req, limits) =
+            match config.method_config(req.uri().path()) {
+                Some(method) => {
+                    let (req, limits) = method_config::apply(method, req);
+                    (
+                        hedging.or_else(|| method.hedging_policy().cloned()),
+                        method.retry_policy().cloned().or(retry_policy),
+                        wait_for_ready.or(method.wait_for_ready()),
+                        req,
+                        limits,
+                    )
+                }
+                None => (hedging, retry_policy, wait_for_ready, req, None),
+            };
+        let wait_for_ready = wait_for_ready.unwrap_or(config.wait_for_ready);
+
+        if hedging.is_none() && retry_policy.is_none() && !wait_for_ready {
+            let fut = self.inner.call(req);
+            #[cfg(feature = "service-config")]
+            if let Some(limits) = limits {
+                return ResponseFuture::boxed(Box::pin(limits.wrap(fut)));
+            }
+            return ResponseFuture::new(fut);
+        }
+
+        // The ready service is moved into the future, which needs to own it
+        // in order to make further attempts.
+        let clone = self.inner.clone();
+        let inner = mem::replace(&mut self.inner, clone);
+
+        let fut = if wait_for_ready {
+            let inner = AwaitReady::new(inner, config.timeout, self.readiness.clone());
+            attempts(inner, config, hedging, retry_policy, req)
+        } else {
+            attempts(inner, config, hedging, retry_policy, req)
+        };
+
+        #[cfg(feature = "service-config")]
+        if let Some(limits) = limits {
+            return ResponseFuture::boxed(Box::pin(limits.wrap(fut)));
+        }
+        ResponseFuture::boxed(fut)
+    }
+}
+
+/// Send `req` on the ready `svc` according to the call's policies.
+fn attempts<S>(
+    mut svc: S,
+    config: Arc<RetryConfig>,
+    hedging: Option<HedgingPolicy>,
+    retry_policy: Option<RetryPolicy>,
+    req: Request<BoxBody>,
+) -> BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::Error> + Send,
+    S::Future: Send + 'static,
+{
+    match (hedging, retry_policy) {
+        (Some(policy), _) => Box::pin(hedge(svc, config, policy, req)),
+        (None, Some(policy)) => Box::pin(retry(svc, config, policy, req)),
+        (None, None) => {
+            let fut = svc.call(req);
+            Box::pin(async move { fut.await.map_err(Into::into) })
+        }
+    }
+}
+
+async fn retry<S>(
+    mut svc: S,
+    config: Arc<RetryConfig>,
+    policy: RetryPolicy,
+    req: Request<BoxBody>,
+) -> Result<Response<BoxBody>, crate::Error>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>>,
+    S::Error: Into<crate::Error> + Send,
+{
+    let has_timeout_header = req.headers().contains_key(GRPC_TIMEOUT_HEADER);
+    let deadline =
+        call_timeout(req.headers(), config.timeout).map(|timeout| Instant::now() + timeout);
+
+    let (parts, body) = req.into_parts();
+    let slot = Arc::new(Mutex::new(Some(body)));
+    let replay = Arc::new(Mutex::new(Replay::default()));
+
+    let mut attempt = 1;
+    let mut result: Result<_, crate::Error> = svc
+        .call(Request::from_parts(
+            clone_parts(&parts),
+            replay_body(&slot, &replay),
+        ))
+        .await
+        .map_err(Into::into);
+
+    loop {
+        match attempt_code(&result) {
+            Some(code) if code != Code::Ok && policy.is_retryable(code) => {}
+            // Responses that were committed to, whose status only comes with
+            // the trailers, count as successes too.
+            _ => {
+                if let (Some(throttle), true) = (&config.throttle, result.is_ok()) {
+                    throttle.on_success();
+                }
+                return result;
+            }
+        }
+
+        if let Some(throttle) = &config.throttle {
+            if !throttle.on_failure() {
+                tracing::debug!("retry throttled");
+                return result;
+            }
+        }
+
+        if attempt >= policy.max_attempts {
+            return result;
+        }
+
+        let delay = match pushback(&result) {
+            Some(Some(delay)) => delay,
+            // The server asked not to retry this call.
+            Some(None) => return result,
+            None => jitter(policy.backoff(attempt)),
+        };
+
+        if let Some(deadline) = deadline {
+            if Instant::now() + delay >= deadline {
+                return result;
+            }
+        }
+
+        // Attempts that failed to connect never sent the body, which can then
+        // be sent as is.
+        let body = if slot.lock().unwrap().is_some() {
+            replay_body(&slot, &replay)
+        } else {
+            match replay.lock().unwrap().body() {
+                Some(body) => boxed(Full::new(body)),
+                None => return result,
+            }
+        };
+
+        tracing::debug!("retrying call after {:?}; attempt {}", delay, attempt);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+
+        let mut req = Request::from_parts(clone_parts(&parts), body);
+        if let (true, Some(deadline)) = (has_timeout_header, deadline) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let value = duration_to_grpc_timeout(remaining);
+            req.headers_mut()
+                .insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_str(&value).unwrap());
+        }
+
+        result = match svc.ready().await {
+            Ok(svc) => svc.call(req).await.map_err(Into::into),
+            Err(e) => Err(e.into()),
+        };
+    }
+}
+
+/// The time left for a call, from its `grpc-timeout` header and the channel's
+/// timeout.
+pub(super) fn call_timeout(headers: &HeaderMap, channel: Option<Duration>) -> Option<Duration> {
+    match (try_parse_grpc_timeout(headers).ok().flatten(), channel) {
+        (Some(header), Some(channel)) => Some(header.min(channel)),
+        (header, channel) => header.or(channel),
+    }
+}
+
+/// The status code of an attempt that has not yet been committed to, that is
+/// one which ended before any response message was received.
+pub(super) fn attempt_code(result: &Result<Response<BoxBody>, crate::Error>) -> Option<Code> {
+    match result {
+        Ok(res) => res
+            .headers()
+            .get("grpc-status")
+            .map(|code| Code::from_bytes(code.as_bytes())),
+        Err(err) => is_connect_error(&**err).then_some(Code::Unavailable),
+    }
+}
+
+pub(super) fn is_connect_error(err: &(dyn StdError + 'static)) -> bool {
+    let mut source = Some(err);
+
+    while let Some(err) = source {
+        if err.is::<ConnectError>() {
+            return true;
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Parses the `grpc-retry-pushback-ms` trailer.
+///
+/// Returns `Some(None)` if the server asked for the call not to be retried.
+pub(super) fn pushback(
+    result: &Result<Response<BoxBody>, crate::Error>,
+) -> Option<Option<Duration>> {
+    let headers: &HeaderMap = result.as_ref().ok()?.headers();
+    let value = headers.get(GRPC_RETRY_PUSHBACK_HEADER)?;
+
+    Some(
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_millis),
+    )
+}
+
+/// A uniformly distributed duration between zero and `max`.
+pub(crate) fn jitter(max: Duration) -> Duration {
+    max.mul_f64(random())
+}
+
+pub(super) fn clone_parts(parts: &request::Parts) -> request::Parts {
+    let (mut clone, ()) = Request::new(()).into_parts();
+    clone.method = parts.method.clone();
+    clone.uri = parts.uri.clone();
+    clone.version = parts.version;
+    clone.headers = parts.headers.clone();
+    clone.extensions = parts.extensions.clone();
+    clone
+}
+
+#[derive(Default)]
+struct Replay {
+    chunks: Vec<Bytes>,
+    size: usize,
+    complete: bool,
+    discarded: bool,
+}
+
+impl Replay {
+    fn push(&mut self, data: &Bytes) {
+        self.size += data.len();
+        if self.size > MAX_REPLAY_BUFFER_SIZE {
+            self.discarded = true;
+            self.chunks.clear();
+        } else if !self.discarded {
+            self.chunks.push(data.clone());
+        }
+    }
+
+    /// The complete request body, if it can be sent again.
+    fn body(&self) -> Option<Bytes> {
+        if !self.complete || self.discarded {
+            return None;
+        }
+
+        let mut body = BytesMut::with_capacity(self.size);
+        for chunk in &self.chunks {
+            body.extend_from_slice(chunk);
+        }
+        Some(body.freeze())
+    }
+}
+
+fn replay_body(slot: &Arc<Mutex<Option<BoxBody>>>, replay: &Arc<Mutex<Replay>>) -> BoxBody {
+    boxed(ReplayBody {
+        slot: slot.clone(),
+        inner: None,
+        replay: replay.clone(),
+    })
+}
+
+/// Request body that records the data it yields so that it can be replayed.
+///
+/// The call's body is only taken out of the shared slot once it is first
+/// polled, so that it can be sent again as is if it never was.
+struct ReplayBody {
+    slot: Arc<Mutex<Option<BoxBody>>>,
+    inner: Option<BoxBody>,
+    replay: Arc<Mutex<Replay>>,
+}
+
+impl Body for ReplayBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        if this.inner.is_none() {
+            this.inner = this.slot.lock().unwrap().take();
+        }
+        let frame = match &mut this.inner {
+            Some(inner) => ready!(Pin::new(inner).poll_frame(cx)),
+            None => return Poll::Ready(None),
+        };
+
+        let mut replay = this.replay.lock().unwrap();
+        match &frame {
+            Some(Ok(frame)) => match frame.data_ref() {
+                Some(data) => replay.push(data),
+                // Trailers are never sent by tonic clients, do not try to
+                // reproduce them.
+                None => replay.discarded = true,
+            },
+            Some(Err(_)) => replay.discarded = true,
+            None => replay.complete = true,
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        let end = self.inner.as_ref().is_some_and(Body::is_end_stream);
+        if end {
+            self.replay.lock().unwrap().complete = true;
+        }
+        end
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            Some(inner) => inner.size_hint(),
+            None => self
+                .slot
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(Body::size_hint)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Response future for [`Retry`].
+#[pin_project]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: Inner<F>,
+}
+
+#[pin_project(project = InnerProj)]
+enum Inner<F> {
+    Future(#[pin] F),
+    Boxed(BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>),
+}
+
+impl<F> ResponseFuture<F> {
+    fn new(inner: F) -> Self {
+        ResponseFuture {
+            inner: Inner::Future(inner),
+        }
+    }
+
+    fn boxed(inner: BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>) -> Self {
+        ResponseFuture {
+            inner: Inner::Boxed(inner),
+        }
+    }
+}
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+    E: Into<crate::Error>,
+{
+    type Output = Result<Response<BoxBody>, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            InnerProj::Future(fut) => fut.poll(cx).map_err(Into::into),
+            InnerProj::Boxed(fut) => fut.as_mut().poll(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::empty_body,
+        transport::channel::{service::Reconnect, ConnectivityTracker},
+    };
+    use http_body_util::BodyExt;
+    use std::collections::VecDeque;
+
+    /// Answers calls with the scripted status codes, sending `Ok` in the
+    /// trailers as servers do and failures in trailers-only responses.
+    #[derive(Clone)]
+    struct Svc {
+        codes: Arc<Mutex<VecDeque<Code>>>,
+        calls: Arc<Mutex<usize>>,
+        bodies: Arc<Mutex<Vec<Bytes>>>,
+    }
+
+    impl Svc {
+        fn new() -> Self {
+            Self {
+                codes: Arc::default(),
+                calls: Arc::default(),
+                bodies: Arc::default(),
+            }
+        }
+
+        fn script(&self, codes: impl IntoIterator<Item = Code>) {
+            self.codes.lock().unwrap().extend(codes);
+        }
+
+        fn calls(&self) -> usize {
+            mem::take(&mut *self.calls.lock().unwrap())
+        }
+    }
+
+    impl Service<Request<BoxBody>> for Svc {
+        type Response = Response<BoxBody>;
+        type Error = crate::Error;
+        type Future = BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
+            *self.calls.lock().unwrap() += 1;
+            let code = self.codes.lock().unwrap().pop_front().unwrap();
+
+            let mut res = Response::new(empty_body());
+            if code != Code::Ok {
+                res.headers_mut()
+                    .insert("grpc-status", HeaderValue::from(code as i32));
+            }
+            let bodies = self.bodies.clone();
+            Box::pin(async move {
+                let body = req.into_body().collect().await?.to_bytes();
+                bodies.lock().unwrap().push(body);
+                Ok(res)
+            })
+        }
+    }
+
+    /// Fails to connect `failures` times, then connects to `svc`.
+    #[derive(Clone)]
+    struct MakeSvc {
+        svc: Svc,
+        failures: Arc<Mutex<usize>>,
+    }
+
+    impl Service<()> for MakeSvc {
+        type Response = Svc;
+        type Error = crate::Error;
+        type Future = BoxFuture<'static, Result<Svc, crate::Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            let mut failures = self.failures.lock().unwrap();
+            let result = match *failures {
+                0 => Ok(self.svc.clone()),
+                _ => {
+                    *failures -= 1;
+                    Err(ConnectError("refused".into()).into())
+                }
+            };
+            Box::pin(std::future::ready(result))
+        }
+    }
+
+    fn call_code(res: Response<BoxBody>) -> Code {
+        res.headers()
+            .get("grpc-status")
+            .map_or(Code::Ok, |code| Code::from_bytes(code.as_bytes()))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successes_restore_throttled_retries() {
+        let svc = Svc::new();
+        let policy = RetryPolicy::new()
+            .max_attempts(2)
+            .initial_backoff(Duration::from_millis(10));
+        // Four tokens: retries stop once two or fewer are left.
+        let config = RetryConfig::new(Some(policy), Some((4, 1.0)), None);
+        let (_tracker, state) = ConnectivityTracker::new();
+        let mut retry = Retry::new(svc.clone(), Some(config), Readiness::new(state, None));
+
+        // Both attempts fail, leaving two tokens.
+        svc.script([Code::Unavailable, Code::Unavailable]);
+        let res = retry.ready().await.unwrap().call(empty_request()).await;
+        assert_eq!(call_code(res.unwrap()), Code::Unavailable);
+        assert_eq!(svc.calls(), 2);
+
+        // Throttled, the failure is not retried.
+        svc.script([Code::Unavailable]);
+        let res = retry.ready().await.unwrap().call(empty_request()).await;
+        assert_eq!(call_code(res.unwrap()), Code::Unavailable);
+        assert_eq!(svc.calls(), 1);
+
+        // Successes refill the bucket back to four tokens.
+        for _ in 0..3 {
+            svc.script([Code::Ok]);
+            let res = retry.ready().await.unwrap().call(empty_request()).await;
+            assert_eq!(call_code(res.unwrap()), Code::Ok);
+        }
+        assert_eq!(svc.calls(), 3);
+
+        svc.script([Code::Unavailable, Code::Ok]);
+        let res = retry.ready().await.unwrap().call(empty_request()).await;
+        assert_eq!(call_code(res.unwrap()), Code::Ok);
+        assert_eq!(svc.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_connections_are_retried() {
+        let svc = Svc::new();
+        svc.script([Code::Ok]);
+        let make = MakeSvc {
+            svc: svc.clone(),
+            failures: Arc::new(Mutex::new(1)),
+        };
+        let (tracker, state) = ConnectivityTracker::new();
+        let reconnect = Reconnect::new(make, (), true, Arc::new(tracker.reporter()), None);
+        let (buffer, worker) = tower::buffer::Buffer::pair(reconnect, 1);
+        tokio::spawn(worker);
+
+        let policy = RetryPolicy::new()
+            .max_attempts(2)
+            .initial_backoff(Duration::from_millis(10));
+        let config = RetryConfig::new(Some(policy), None, None);
+        let mut retry = Retry::new(buffer, Some(config), Readiness::new(state, None));
+
+        // The first attempt fails to connect before sending the body.
+        let req = Request::new(boxed(Full::new(Bytes::from_static(b"hello"))));
+        let res = retry.ready().await.unwrap().call(req).await;
+        assert_eq!(call_code(res.unwrap()), Code::Ok);
+        assert_eq!(svc.calls(), 1);
+        assert_eq!(*svc.bodies.lock().unwrap(), [Bytes::from_static(b"hello")]);
+    }
+
+    fn empty_request() -> Request<BoxBody> {
+        Request::new(empty_body())
+    }
+}
@@ -1,35 +1,75 @@
 use std::fmt;
 use std::io::Cursor;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use hyper_util::rt::TokioIo;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_rustls::{
-    rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+    rustls::{
+        self,
+        client::{
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+            Resumption, WebPkiServerVerifier,
+        },
+        pki_types::{CertificateDer, ServerName, TrustAnchor, UnixTime},
+        CertificateError, ClientConfig, DigitallySignedStruct, HandshakeKind, RootCertStore,
+        SignatureScheme,
+    },
     TlsConnector as RustlsConnector,
 };
+use tracing::debug;
 
 use super::io::BoxedIo;
-use crate::transport::service::tls::{add_certs_from_pem, load_identity, TlsError, ALPN_H2};
-use crate::transport::tls::{Certificate, Identity};
+use crate::transport::channel::ClientSessionCache;
+use crate::transport::credentials::{ResolvedConfig, SharedResolver};
+use crate::transport::service::tls::{
+    add_certs_from_pem, alpn_protocols, crypto_provider, load_crls, load_identity,
+    protocol_versions, verify_chain, TlsError, ALPN_H2,
+};
+use crate::transport::tls::{
+    Certificate, Identity, ProtocolPolicy, RevocationConfig, SharedVerifier,
+};
 
 #[derive(Clone)]
 pub(crate) struct TlsConnector {
-    config: Arc<ClientConfig>,
+    config: Config,
     domain: Arc<ServerName<'static>>,
     assume_http2: bool,
 }
 
+#[derive(Clone)]
+enum Config {
+    Static(Arc<ClientConfig>),
+    Resolved {
+        config: Arc<ResolvedConfig<Arc<ClientConfig>>>,
+        roots: RootCertStore,
+        verification: Verification,
+        session_cache: ClientSessionCache,
+        policy: ProtocolPolicy,
+    },
+}
+
+/// How the server certificate is verified, beyond the CA certificates.
+#[derive(Clone)]
+pub(crate) struct Verification {
+    pub(crate) revocation: Option<RevocationConfig>,
+    pub(crate) verifier: Option<SharedVerifier>,
+}
+
 impl TlsConnector {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         ca_certs: Vec<Certificate>,
         identity: Option<Identity>,
+        resolver: Option<SharedResolver>,
+        verification: Verification,
+        session_cache: ClientSessionCache,
+        policy: ProtocolPolicy,
         domain: &str,
         assume_http2: bool,
         #[cfg(feature = "tls-roots")] with_native_roots: bool,
         #[cfg(feature = "tls-webpki-roots")] with_webpki_roots: bool,
     ) -> Result<Self, crate::Error> {
-        let builder = ClientConfig::builder();
         let mut roots = RootCertStore::empty();
 
         #[cfg(feature = "tls-roots")]
@@ -46,34 +86,75 @@ impl TlsConnector {
             add_certs_from_pem(&mut Cursor::new(cert), &mut roots)?;
         }
 
-        let builder = builder.with_root_certificates(roots);
-        let mut config = match identity {
-            Some(identity) => {
-                let (client_cert, client_key) = load_identity(identity)?;
-                builder.with_client_auth_cert(client_cert, client_key)?
-            }
-            None => builder.with_no_client_auth(),
+        let config = match resolver {
+            Some(resolver) => Config::Resolved {
+                config: Arc::new(ResolvedConfig::new(resolver)),
+                roots,
+                verification,
+                session_cache,
+                policy,
+            },
+            None => Config::Static(client_config(
+                roots,
+                identity,
+                &verification,
+                &session_cache,
+                &policy,
+            )?),
         };
 
-        config.alpn_protocols.push(ALPN_H2.into());
-        Ok(Self {
-            config: Arc::new(config),
+        let connector = Self {
+            config,
             domain: Arc::new(ServerName::try_from(domain)?.to_owned()),
             assume_http2,
-        })
+        };
+        // Fail early if the first credentials are invalid.
+        connector.client_config()?;
+        Ok(connector)
+    }
+
+    fn client_config(&self) -> Result<Arc<ClientConfig>, crate::Error> {
+        match &self.config {
+            Config::Static(config) => Ok(config.clone()),
+            Config::Resolved {
+                config,
+                roots,
+                verification,
+                session_cache,
+                policy,
+            } => config
+                .get(|credentials| {
+                    let mut roots = roots.clone();
+                    if let Some(cert) = &credentials.ca_certificate {
+                        add_certs_from_pem(&mut Cursor::new(cert), &mut roots)?;
+                    }
+                    client_config(
+                        roots,
+                        Some(credentials.identity.clone()),
+                        verification,
+                        session_cache,
+                        policy,
+                    )
+                })
+                .map(|config| Arc::clone(&*config)),
+        }
     }
 
     pub(crate) async fn connect<I>(&self, io: I) -> Result<BoxedIo, crate::Error>
     where
         I: AsyncRead + AsyncWrite + Send + Unpin + 'static,
     {
-        let io = RustlsConnector::from(self.config.clone())
+        let io = RustlsConnector::from(self.client_config()?)
             .connect(self.domain.as_ref().to_owned(), io)
             .await?;
 
         // Generally we require ALPN to be negotiated, but if the user has
         // explicitly set `assume_http2` to true, we'll allow it to be missing.
         let (_, session) = io.get_ref();
+        debug!(
+            resumed = session.handshake_kind() == Some(HandshakeKind::Resumed),
+            "TLS handshake complete"
+        );
         let alpn_protocol = session.alpn_protocol();
         if !(alpn_protocol == Some(ALPN_H2) || self.assume_http2) {
             return Err(TlsError::H2NotNegotiated.into());
@@ -82,6 +163,180 @@ impl TlsConnector {
     }
 }
 
+/// The last configuration built with a [`ClientSessionCache`].
+///
+/// Sessions are only resumed with the certificate verifier and client
+/// certificate that established them, so connectors sharing a cache also
+/// share their configuration when they are built from the same settings.
+#[derive(Default)]
+pub(crate) struct SharedConfig(Mutex<Option<(ConfigKey, Arc<ClientConfig>)>>);
+
+/// The settings a [`ClientConfig`] is built from.
+#[derive(PartialEq)]
+struct ConfigKey {
+    roots: Vec<TrustAnchor<'static>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    revocation: Option<(Vec<Vec<u8>>, bool, bool)>,
+    verifier: Option<SharedVerifier>,
+    policy: ProtocolPolicy,
+}
+
+fn client_config(
+    roots: RootCertStore,
+    identity: Option<Identity>,
+    verification: &Verification,
+    session_cache: &ClientSessionCache,
+    policy: &ProtocolPolicy,
+) -> Result<Arc<ClientConfig>, crate::Error> {
+    let key = ConfigKey {
+        roots: roots.roots.clone(),
+        identity: identity
+            .as_ref()
+            .map(|identity| (identity.cert.pem.clone(), identity.key.clone())),
+        revocation: verification.revocation.as_ref().map(|revocation| {
+            (
+                revocation.crls.iter().map(|crl| crl.data.clone()).collect(),
+                revocation.end_entity_only,
+                revocation.allow_unknown_status,
+            )
+        }),
+        verifier: verification.verifier.clone(),
+        policy: policy.clone(),
+    };
+
+    let mut shared = session_cache.config.0.lock().unwrap();
+    match &*shared {
+        Some((shared_key, config)) if *shared_key == key => Ok(config.clone()),
+        _ => {
+            let config = Arc::new(build_client_config(
+                roots,
+                identity,
+                verification,
+                session_cache,
+                policy,
+            )?);
+            *shared = Some((key, config.clone()));
+            Ok(config)
+        }
+    }
+}
+
+fn build_client_config(
+    roots: RootCertStore,
+    identity: Option<Identity>,
+    verification: &Verification,
+    session_cache: &ClientSessionCache,
+    policy: &ProtocolPolicy,
+) -> Result<ClientConfig, crate::Error> {
+    let provider = crypto_provider(policy)?;
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&protocol_versions(policy))?;
+    let builder = match verification {
+        Verification {
+            revocation: None,
+            verifier: None,
+        } => builder.with_root_certificates(roots),
+        Verification {
+            revocation,
+            verifier,
+        } => {
+            let mut webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider);
+            if let Some(revocation) = revocation {
+                webpki = webpki.with_crls(load_crls(revocation)?);
+                if revocation.end_entity_only {
+                    webpki = webpki.only_check_end_entity_revocation();
+                }
+                if revocation.allow_unknown_status {
+                    webpki = webpki.allow_unknown_revocation_status();
+                }
+            }
+            let webpki = webpki.build()?;
+
+            match verifier {
+                Some(custom) => builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(CustomServerVerifier {
+                        inner: webpki,
+                        custom: custom.clone(),
+                    })),
+                None => builder.with_webpki_verifier(webpki),
+            }
+        }
+    };
+    let mut config = match identity {
+        Some(identity) => {
+            let (client_cert, client_key) = load_identity(identity)?;
+            builder.with_client_auth_cert(client_cert, client_key)?
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    config.alpn_protocols = alpn_protocols(policy);
+    config.resumption = Resumption::store(session_cache.inner.clone());
+    Ok(config)
+}
+
+/// Runs a [`CertificateVerifier`] on server certificates once they are
+/// trusted, in place of the check of the domain name.
+///
+/// [`CertificateVerifier`]: crate::transport::CertificateVerifier
+#[derive(Debug)]
+struct CustomServerVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    custom: SharedVerifier,
+}
+
+impl ServerCertVerifier for CustomServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        match self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        ) {
+            // The name is only checked once the chain is trusted.
+            Ok(_)
+            | Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => {}
+            Err(err) => return Err(err),
+        }
+
+        verify_chain(&self.custom, end_entity, intermediates)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
 impl fmt::Debug for TlsConnector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TlsConnector").finish()
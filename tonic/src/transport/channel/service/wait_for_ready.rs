@@ -0,0 +1,392 @@
+use super::retry::{call_timeout, clone_parts, is_connect_error};
+use super::BackingOff;
+use crate::{
+    body::{boxed, BoxBody},
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::duration_to_grpc_timeout,
+    transport::{
+        channel::{BoxFuture, ConnectivityState, ReconnectBackoff},
+        service::random,
+    },
+    Status,
+};
+use bytes::Bytes;
+use http::{HeaderValue, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use std::{
+    error::Error as StdError,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::watch, time::Instant};
+use tower::ServiceExt;
+use tower_service::Service;
+
+/// What calls waiting for a channel to be ready follow: the connectivity
+/// state of the channel and the backoff between its attempts to connect.
+#[derive(Clone)]
+pub(crate) struct Readiness {
+    connectivity: watch::Receiver<ConnectivityState>,
+    backoff: ReconnectBackoff,
+}
+
+impl Readiness {
+    /// Channels without a [`ReconnectBackoff`] connect again as soon as a
+    /// call is made, so waiting calls back off with the default one.
+    pub(crate) fn new(
+        connectivity: watch::Receiver<ConnectivityState>,
+        backoff: Option<ReconnectBackoff>,
+    ) -> Self {
+        Self {
+            connectivity,
+            backoff: backoff.unwrap_or_default(),
+        }
+    }
+}
+
+/// Sends calls again when they fail because the channel could not connect,
+/// until the channel connects or the call's deadline expires.
+///
+/// Calls are sent again once the channel may attempt to connect again, or as
+/// soon as it becomes ready. They are only sent again if their body has not
+/// been read yet, which is always the case when connecting failed.
+#[derive(Clone)]
+pub(super) struct AwaitReady<S> {
+    inner: S,
+    timeout: Option<Duration>,
+    readiness: Readiness,
+}
+
+impl<S> AwaitReady<S> {
+    pub(super) fn new(inner: S, timeout: Option<Duration>, readiness: Readiness) -> Self {
+        Self {
+            inner,
+            timeout,
+            readiness,
+        }
+    }
+}
+
+impl<S> Service<Request<BoxBody>> for AwaitReady<S>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<BoxBody>) -> Self::Future {
+        let clone = self.inner.clone();
+        let inner = mem::replace(&mut self.inner, clone);
+
+        Box::pin(wait_for_ready(
+            inner,
+            self.timeout,
+            self.readiness.clone(),
+            req,
+        ))
+    }
+}
+
+async fn wait_for_ready<S>(
+    mut svc: S,
+    timeout: Option<Duration>,
+    mut readiness: Readiness,
+    req: Request<BoxBody>,
+) -> Result<Response<BoxBody>, crate::Error>
+where
+    S: Service<Request<BoxBody>, Response = Response<BoxBody>>,
+    S::Error: Into<crate::Error> + Send,
+{
+    let has_timeout_header = req.headers().contains_key(GRPC_TIMEOUT_HEADER);
+    let deadline = call_timeout(req.headers(), timeout).map(|timeout| Instant::now() + timeout);
+
+    let (mut parts, body) = req.into_parts();
+    let slot = Arc::new(Mutex::new(Some(body)));
+    let mut backoff = readiness.backoff.initial_backoff;
+
+    let mut result: Result<_, crate::Error> = svc
+        .call(Request::from_parts(clone_parts(&parts), slot_body(&slot)))
+        .await
+        .map_err(Into::into);
+
+    loop {
+        let retry_at = match &result {
+            Err(err) if is_connect_error(&**err) => match backing_off_until(&**err) {
+                Some(retry_at) => retry_at,
+                None => {
+                    let delay = readiness.backoff.jittered(backoff, random());
+                    backoff = readiness.backoff.next(backoff);
+                    Instant::now() + delay
+                }
+            },
+            _ => return result,
+        };
+
+        // The body is gone once the call started sending it.
+        if slot.lock().unwrap().is_none() {
+            return result;
+        }
+
+        if let Some(deadline) = deadline {
+            if retry_at >= deadline {
+                return result;
+            }
+        }
+
+        tracing::debug!(
+            "channel not ready, sending call again in {:?}",
+            retry_at.saturating_duration_since(Instant::now())
+        );
+        readiness.connectivity.borrow_and_update();
+        let state = tokio::select! {
+            _ = tokio::time::sleep_until(retry_at) => None,
+            state = ready_or_shutdown(&mut readiness.connectivity) => Some(state),
+        };
+        if state == Some(ConnectivityState::Shutdown) {
+            return result;
+        }
+
+        if let (true, Some(deadline)) = (has_timeout_header, deadline) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let value = duration_to_grpc_timeout(remaining);
+            parts
+                .headers
+                .insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_str(&value).unwrap());
+        }
+
+        let req = Request::from_parts(clone_parts(&parts), slot_body(&slot));
+        result = match svc.ready().await {
+            Ok(svc) => svc.call(req).await.map_err(Into::into),
+            Err(e) => Err(e.into()),
+        };
+    }
+}
+
+/// When the channel attempts to connect again, if it failed the call because
+/// it is backing off.
+fn backing_off_until(err: &(dyn StdError + 'static)) -> Option<Instant> {
+    let mut source = Some(err);
+
+    while let Some(err) = source {
+        if let Some(backing_off) = err.downcast_ref::<BackingOff>() {
+            return Some(backing_off.retry_at);
+        }
+        source = err.source();
+    }
+
+    None
+}
+
+/// Waits for the channel to become ready or shut down, and returns which.
+async fn ready_or_shutdown(
+    connectivity: &mut watch::Receiver<ConnectivityState>,
+) -> ConnectivityState {
+    loop {
+        if connectivity.changed().await.is_err() {
+            return ConnectivityState::Shutdown;
+        }
+
+        let state = *connectivity.borrow_and_update();
+        if matches!(
+            state,
+            ConnectivityState::Ready | ConnectivityState::Shutdown
+        ) {
+            return state;
+        }
+    }
+}
+
+fn slot_body(slot: &Arc<Mutex<Option<BoxBody>>>) -> BoxBody {
+    boxed(SlotBody {
+        slot: slot.clone(),
+        inner: None,
+    })
+}
+
+/// Request body that only takes the call's body out of the shared slot once
+/// it is first polled, so that it can be sent again if it never was.
+struct SlotBody {
+    slot: Arc<Mutex<Option<BoxBody>>>,
+    inner: Option<BoxBody>,
+}
+
+impl SlotBody {
+    fn inner(&mut self) -> Option<&mut BoxBody> {
+        if self.inner.is_none() {
+            self.inner = self.slot.lock().unwrap().take();
+        }
+        self.inner.as_mut()
+    }
+}
+
+impl Body for SlotBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match self.inner() {
+            Some(inner) => Pin::new(inner).poll_frame(cx),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            Some(inner) => inner.is_end_stream(),
+            None => false,
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            Some(inner) => inner.size_hint(),
+            None => self
+                .slot
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(Body::size_hint)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::channel::{service::ConnectError, ConnectivityTracker};
+
+    /// Fails calls to connect until `connected` attempts were made, and
+    /// records the time of every attempt.
+    #[derive(Clone)]
+    struct Svc {
+        attempts: Arc<Mutex<Vec<Instant>>>,
+        connected: usize,
+        retry_in: Option<Duration>,
+    }
+
+    impl Svc {
+        fn new(connected: usize, retry_in: Option<Duration>) -> Self {
+            Self {
+                attempts: Arc::default(),
+                connected,
+                retry_in,
+            }
+        }
+
+        fn attempts(&self, start: Instant) -> Vec<u64> {
+            self.attempts
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|attempt| attempt.duration_since(start).as_secs())
+                .collect()
+        }
+    }
+
+    impl Service<Request<BoxBody>> for Svc {
+        type Response = Response<BoxBody>;
+        type Error = crate::Error;
+        type Future = BoxFuture<'static, Result<Response<BoxBody>, crate::Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<BoxBody>) -> Self::Future {
+            let mut attempts = self.attempts.lock().unwrap();
+            attempts.push(Instant::now());
+
+            let result = if attempts.len() > self.connected {
+                Ok(Response::new(crate::body::empty_body()))
+            } else if let Some(retry_in) = self.retry_in {
+                let backing_off = BackingOff {
+                    retry_at: Instant::now() + retry_in,
+                    message: "backing off".into(),
+                };
+                Err(ConnectError(Box::new(backing_off)).into())
+            } else {
+                Err(ConnectError("refused".into()).into())
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    fn backoff() -> ReconnectBackoff {
+        ReconnectBackoff::new()
+            .initial_backoff(Duration::from_secs(1))
+            .multiplier(2.0)
+            .jitter(0.0)
+    }
+
+    fn request() -> Request<BoxBody> {
+        Request::new(crate::body::empty_body())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_channel_backoff() {
+        let (_tracker, state) = ConnectivityTracker::new();
+        let svc = Svc::new(1, Some(Duration::from_secs(5)));
+        let mut await_ready = AwaitReady::new(svc.clone(), None, Readiness::new(state, None));
+
+        let start = Instant::now();
+        await_ready.call(request()).await.unwrap();
+        assert_eq!(svc.attempts(start), [0, 5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_with_endpoint_backoff() {
+        let (_tracker, state) = ConnectivityTracker::new();
+        let svc = Svc::new(3, None);
+        let readiness = Readiness::new(state, Some(backoff()));
+        let mut await_ready = AwaitReady::new(svc.clone(), None, readiness);
+
+        let start = Instant::now();
+        await_ready.call(request()).await.unwrap();
+        assert_eq!(svc.attempts(start), [0, 1, 3, 7]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sends_again_once_ready() {
+        let (tracker, state) = ConnectivityTracker::new();
+        let svc = Svc::new(1, Some(Duration::from_secs(60)));
+        let mut await_ready = AwaitReady::new(svc.clone(), None, Readiness::new(state, None));
+
+        let reporter = tracker.reporter();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            reporter.set(ConnectivityState::Ready);
+            std::future::pending::<()>().await;
+        });
+
+        let start = Instant::now();
+        await_ready.call(request()).await.unwrap();
+        assert_eq!(svc.attempts(start), [0, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_at_deadline() {
+        let (_tracker, state) = ConnectivityTracker::new();
+        let svc = Svc::new(usize::MAX, None);
+        let readiness = Readiness::new(state, Some(backoff()));
+        let mut await_ready = AwaitReady::new(svc.clone(), Some(Duration::from_secs(5)), readiness);
+
+        let start = Instant::now();
+        let err = await_ready.call(request()).await.unwrap_err();
+        assert!(is_connect_error(&*err));
+        assert_eq!(svc.attempts(start), [0, 1, 3]);
+    }
+}
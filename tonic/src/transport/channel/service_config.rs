@@ -0,0 +1,463 @@
+use super::{HedgingPolicy, RetryPolicy};
+use crate::{transport::Error, Code};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// A parsed [gRPC service config][spec].
+///
+/// The service config carries per-method defaults for timeouts, message size
+/// limits, wait-for-ready and retry or hedging policies, along with the load
+/// balancing configuration and retry throttling for the whole channel. It is
+/// applied to a channel with [`Endpoint::service_config`] or
+/// [`Channel::service_config`].
+///
+/// ```
+/// # use tonic::transport::{channel::ServiceConfig, Endpoint};
+/// let config = ServiceConfig::from_json(r#"{
+///     "methodConfig": [{
+///         "name": [{ "service": "helloworld.Greeter" }],
+///         "timeout": "1.5s",
+///         "retryPolicy": {
+///             "maxAttempts": 3,
+///             "initialBackoff": "0.1s",
+///             "maxBackoff": "1s",
+///             "backoffMultiplier": 2,
+///             "retryableStatusCodes": ["UNAVAILABLE"]
+///         }
+///     }]
+/// }"#).unwrap();
+///
+/// let endpoint = Endpoint::from_static("https://example.com").service_config(config);
+/// ```
+///
+/// [spec]: https://github.com/grpc/grpc/blob/master/doc/service_config.md
+/// [`Endpoint::service_config`]: super::Endpoint::service_config
+/// [`Channel::service_config`]: super::Channel::service_config
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    methods: HashMap<String, Arc<MethodConfig>>,
+    services: HashMap<String, Arc<MethodConfig>>,
+    default: Option<Arc<MethodConfig>>,
+    load_balancing_policies: Vec<String>,
+    pub(crate) retry_throttling: Option<(u32, f64)>,
+}
+
+/// Settings a [`ServiceConfig`] applies to the calls of a method.
+#[derive(Debug, Clone, Default)]
+pub struct MethodConfig {
+    timeout: Option<Duration>,
+    max_request_message_bytes: Option<usize>,
+    max_response_message_bytes: Option<usize>,
+    wait_for_ready: Option<bool>,
+    retry_policy: Option<RetryPolicy>,
+    hedging_policy: Option<HedgingPolicy>,
+}
+
+impl ServiceConfig {
+    /// Parse a service config from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(json).map_err(invalid)?;
+        let config = value
+            .as_object()
+            .ok_or_else(|| invalid("service config must be an object"))?;
+
+        let mut service_config = ServiceConfig::default();
+
+        for method_config in array(config, "methodConfig")? {
+            let method_config = method_config
+                .as_object()
+                .ok_or_else(|| invalid("methodConfig entries must be objects"))?;
+            service_config.add_method_config(method_config)?;
+        }
+
+        if let Some(policies) = config.get("loadBalancingConfig") {
+            for policy in policies
+                .as_array()
+                .ok_or_else(|| invalid("loadBalancingConfig must be an array"))?
+            {
+                let name = policy
+                    .as_object()
+                    .filter(|policy| policy.len() == 1)
+                    .and_then(|policy| policy.keys().next())
+                    .ok_or_else(|| {
+                        invalid("loadBalancingConfig entries must have exactly one field")
+                    })?;
+                service_config.load_balancing_policies.push(name.clone());
+            }
+        } else if let Some(policy) = config.get("loadBalancingPolicy") {
+            let policy = policy
+                .as_str()
+                .ok_or_else(|| invalid("loadBalancingPolicy must be a string"))?;
+            service_config
+                .load_balancing_policies
+                .push(policy.to_ascii_lowercase());
+        }
+
+        if let Some(throttling) = config.get("retryThrottling") {
+            let throttling = throttling
+                .as_object()
+                .ok_or_else(|| invalid("retryThrottling must be an object"))?;
+            let max_tokens = uint(throttling, "maxTokens")?
+                .filter(|max_tokens| (1..=1000).contains(max_tokens))
+                .ok_or_else(|| invalid("retryThrottling.maxTokens must be in 1..=1000"))?;
+            let token_ratio = number(throttling, "tokenRatio")?
+                .filter(|ratio| *ratio > 0.0)
+                .ok_or_else(|| invalid("retryThrottling.tokenRatio must be positive"))?;
+
+            service_config.retry_throttling = Some((max_tokens as u32, token_ratio));
+        }
+
+        Ok(service_config)
+    }
+
+    fn add_method_config(&mut self, config: &Map<String, Value>) -> Result<(), Error> {
+        let method_config = Arc::new(MethodConfig {
+            timeout: duration(config, "timeout")?,
+            max_request_message_bytes: uint(config, "maxRequestMessageBytes")?
+                .map(|bytes| bytes as usize),
+            max_response_message_bytes: uint(config, "maxResponseMessageBytes")?
+                .map(|bytes| bytes as usize),
+            wait_for_ready: match config.get("waitForReady") {
+                Some(value) => Some(
+                    value
+                        .as_bool()
+                        .ok_or_else(|| invalid("waitForReady must be a boolean"))?,
+                ),
+                None => None,
+            },
+            retry_policy: match config.get("retryPolicy") {
+                Some(policy) => Some(retry_policy(policy)?),
+                None => None,
+            },
+            hedging_policy: match config.get("hedgingPolicy") {
+                Some(policy) => Some(hedging_policy(policy)?),
+                None => None,
+            },
+        });
+
+        if method_config.retry_policy.is_some() && method_config.hedging_policy.is_some() {
+            return Err(invalid(
+                "retryPolicy and hedgingPolicy cannot both be set on a method",
+            ));
+        }
+
+        for name in array(config, "name")? {
+            let name = name
+                .as_object()
+                .ok_or_else(|| invalid("methodConfig names must be objects"))?;
+            let service = string(name, "service")?.unwrap_or_default();
+            let method = string(name, "method")?.unwrap_or_default();
+
+            match (service.is_empty(), method.is_empty()) {
+                (true, true) => self.default = Some(method_config.clone()),
+                (false, true) => {
+                    self.services
+                        .insert(service.to_string(), method_config.clone());
+                }
+                (false, false) => {
+                    self.methods
+                        .insert(format!("{}/{}", service, method), method_config.clone());
+                }
+                (true, false) => {
+                    return Err(invalid(
+                        "methodConfig names with a method must have a service",
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The configuration for calls to `method` of `service`.
+    ///
+    /// A configuration naming the method takes precedence over one naming the
+    /// whole service, which takes precedence over the default configuration.
+    pub fn method_config(&self, service: &str, method: &str) -> Option<&MethodConfig> {
+        self.methods
+            .get(&format!("{}/{}", service, method))
+            .or_else(|| self.services.get(service))
+            .or(self.default.as_ref())
+            .map(|config| &**config)
+    }
+
+    /// Looks up the configuration for a request path such as
+    /// `/helloworld.Greeter/SayHello`.
+    pub(crate) fn method_config_for_path(&self, path: &str) -> Option<&MethodConfig> {
+        let (service, method) = path
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or_default();
+
+        self.method_config(service, method)
+    }
+
+    /// The load balancing policies listed in the config, in order of
+    /// preference.
+    pub fn load_balancing_policies(&self) -> &[String] {
+        &self.load_balancing_policies
+    }
+}
+
+impl MethodConfig {
+    /// The default timeout of a call.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The largest request message that may be sent.
+    pub fn max_request_message_bytes(&self) -> Option<usize> {
+        self.max_request_message_bytes
+    }
+
+    /// The largest response message that may be received.
+    pub fn max_response_message_bytes(&self) -> Option<usize> {
+        self.max_response_message_bytes
+    }
+
+    /// Whether calls wait for the channel to become ready instead of failing
+    /// fast.
+    pub fn wait_for_ready(&self) -> Option<bool> {
+        self.wait_for_ready
+    }
+
+    /// The policy used to retry calls.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// The policy used to hedge calls.
+    pub fn hedging_policy(&self) -> Option<&HedgingPolicy> {
+        self.hedging_policy.as_ref()
+    }
+}
+
+fn retry_policy(value: &Value) -> Result<RetryPolicy, Error> {
+    let policy = value
+        .as_object()
+        .ok_or_else(|| invalid("retryPolicy must be an object"))?;
+
+    let max_attempts = uint(policy, "maxAttempts")?
+        .filter(|attempts| *attempts > 1)
+        .ok_or_else(|| invalid("retryPolicy.maxAttempts must be greater than 1"))?;
+    let initial_backoff = duration(policy, "initialBackoff")?
+        .filter(|backoff| !backoff.is_zero())
+        .ok_or_else(|| invalid("retryPolicy.initialBackoff must be positive"))?;
+    let max_backoff = duration(policy, "maxBackoff")?
+        .filter(|backoff| !backoff.is_zero())
+        .ok_or_else(|| invalid("retryPolicy.maxBackoff must be positive"))?;
+    let backoff_multiplier = number(policy, "backoffMultiplier")?
+        .filter(|multiplier| *multiplier > 0.0)
+        .ok_or_else(|| invalid("retryPolicy.backoffMultiplier must be positive"))?;
+    let codes = codes(policy, "retryableStatusCodes")?;
+    if codes.is_empty() {
+        return Err(invalid(
+            "retryPolicy.retryableStatusCodes must not be empty",
+        ));
+    }
+
+    Ok(RetryPolicy::new()
+        .max_attempts(max_attempts as usize)
+        .initial_backoff(initial_backoff)
+        .max_backoff(max_backoff)
+        .backoff_multiplier(backoff_multiplier)
+        .retryable_codes(codes))
+}
+
+fn hedging_policy(value: &Value) -> Result<HedgingPolicy, Error> {
+    let policy = value
+        .as_object()
+        .ok_or_else(|| invalid("hedgingPolicy must be an object"))?;
+
+    let max_attempts = uint(policy, "maxAttempts")?
+        .filter(|attempts| *attempts > 1)
+        .ok_or_else(|| invalid("hedgingPolicy.maxAttempts must be greater than 1"))?;
+
+    Ok(HedgingPolicy::new()
+        .max_attempts(max_attempts as usize)
+        .hedging_delay(duration(policy, "hedgingDelay")?.unwrap_or_default())
+        .non_fatal_codes(codes(policy, "nonFatalStatusCodes")?))
+}
+
+fn invalid(msg: impl Into<crate::Error>) -> Error {
+    Error::new_invalid_service_config().with(msg)
+}
+
+fn array<'a>(object: &'a Map<String, Value>, key: &str) -> Result<&'a [Value], Error> {
+    match object.get(key) {
+        Some(Value::Array(values)) => Ok(values),
+        Some(_) => Err(invalid(format!("{} must be an array", key))),
+        None => Ok(&[]),
+    }
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &str) -> Result<Option<&'a str>, Error> {
+    match object.get(key) {
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(invalid(format!("{} must be a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn number(object: &Map<String, Value>, key: &str) -> Result<Option<f64>, Error> {
+    match object.get(key) {
+        Some(Value::Number(value)) => Ok(value.as_f64()),
+        Some(Value::String(value)) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid(format!("{} must be a number", key))),
+        Some(_) => Err(invalid(format!("{} must be a number", key))),
+        None => Ok(None),
+    }
+}
+
+/// Parses an unsigned integer, which the protobuf JSON mapping allows to be
+/// either a number or a string.
+fn uint(object: &Map<String, Value>, key: &str) -> Result<Option<u64>, Error> {
+    let value = match object.get(key) {
+        Some(Value::Number(value)) => value.as_u64(),
+        Some(Value::String(value)) => value.parse().ok(),
+        Some(_) => None,
+        None => return Ok(None),
+    };
+
+    value
+        .map(Some)
+        .ok_or_else(|| invalid(format!("{} must be an unsigned integer", key)))
+}
+
+/// Parses a `google.protobuf.Duration` in its JSON form, such as `"1.5s"`.
+fn duration(object: &Map<String, Value>, key: &str) -> Result<Option<Duration>, Error> {
+    let value = match string(object, key)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    value
+        .strip_suffix('s')
+        .and_then(|secs| secs.parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .map(Some)
+        .ok_or_else(|| invalid(format!("{} must be a duration such as \"1.5s\"", key)))
+}
+
+fn codes(object: &Map<String, Value>, key: &str) -> Result<Vec<Code>, Error> {
+    array(object, key)?
+        .iter()
+        .map(|code| match code {
+            Value::String(name) => code_from_name(name),
+            Value::Number(code) => code
+                .as_u64()
+                .filter(|code| *code <= Code::Unauthenticated as u64)
+                .map(|code| Code::from_i32(code as i32)),
+            _ => None,
+        })
+        .map(|code| code.ok_or_else(|| invalid(format!("{} contains an invalid code", key))))
+        .collect()
+}
+
+fn code_from_name(name: &str) -> Option<Code> {
+    let code = match name {
+        "OK" => Code::Ok,
+        "CANCELLED" => Code::Cancelled,
+        "UNKNOWN" => Code::Unknown,
+        "INVALID_ARGUMENT" => Code::InvalidArgument,
+        "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+        "NOT_FOUND" => Code::NotFound,
+        "ALREADY_EXISTS" => Code::AlreadyExists,
+        "PERMISSION_DENIED" => Code::PermissionDenied,
+        "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+        "FAILED_PRECONDITION" => Code::FailedPrecondition,
+        "ABORTED" => Code::Aborted,
+        "OUT_OF_RANGE" => Code::OutOfRange,
+        "UNIMPLEMENTED" => Code::Unimplemented,
+        "INTERNAL" => Code::Internal,
+        "UNAVAILABLE" => Code::Unavailable,
+        "DATA_LOSS" => Code::DataLoss,
+        "UNAUTHENTICATED" => Code::Unauthenticated,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_precedence() {
+        let config = ServiceConfig::from_json(
+            r#"{
+                "methodConfig": [
+                    { "name": [{}], "timeout": "10s" },
+                    { "name": [{ "service": "a.A" }], "timeout": "5s" },
+                    { "name": [{ "service": "a.A", "method": "M" }], "timeout": "0.5s" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let timeout = |service, method| {
+            config
+                .method_config(service, method)
+                .and_then(MethodConfig::timeout)
+        };
+
+        assert_eq!(timeout("a.A", "M"), Some(Duration::from_millis(500)));
+        assert_eq!(timeout("a.A", "N"), Some(Duration::from_secs(5)));
+        assert_eq!(timeout("b.B", "M"), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn parses_policies() {
+        let config = ServiceConfig::from_json(
+            r#"{
+                "loadBalancingConfig": [{ "round_robin": {} }],
+                "retryThrottling": { "maxTokens": 10, "tokenRatio": 0.1 },
+                "methodConfig": [{
+                    "name": [{ "service": "a.A" }],
+                    "maxRequestMessageBytes": "1024",
+                    "maxResponseMessageBytes": 2048,
+                    "waitForReady": true,
+                    "retryPolicy": {
+                        "maxAttempts": 4,
+                        "initialBackoff": "0.1s",
+                        "maxBackoff": "1s",
+                        "backoffMultiplier": 2,
+                        "retryableStatusCodes": ["UNAVAILABLE", 8]
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.load_balancing_policies(), ["round_robin"]);
+        assert_eq!(config.retry_throttling, Some((10, 0.1)));
+
+        let method = config.method_config("a.A", "M").unwrap();
+        assert_eq!(method.max_request_message_bytes(), Some(1024));
+        assert_eq!(method.max_response_message_bytes(), Some(2048));
+        assert_eq!(method.wait_for_ready(), Some(true));
+
+        let retry = method.retry_policy().unwrap();
+        assert_eq!(retry.max_attempts, 4);
+        assert_eq!(
+            retry.retryable_codes,
+            [Code::Unavailable, Code::ResourceExhausted]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        for json in [
+            "[]",
+            r#"{ "methodConfig": [{ "timeout": "soon" }] }"#,
+            r#"{ "methodConfig": [{ "name": [{ "method": "M" }] }] }"#,
+            r#"{ "methodConfig": [{ "retryPolicy": { "maxAttempts": 1 } }] }"#,
+            r#"{ "retryThrottling": { "maxTokens": 0, "tokenRatio": 1 } }"#,
+        ] {
+            assert!(ServiceConfig::from_json(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -1,9 +1,13 @@
-use super::service::TlsConnector;
+use super::service::{SharedConfig, TlsConnector, Verification};
 use crate::transport::{
-    tls::{Certificate, Identity},
-    Error,
+    credentials::SharedResolver,
+    tls::{Certificate, Identity, ProtocolPolicy, RevocationConfig, SharedVerifier},
+    CertificateVerifier, CipherSuite, CredentialsResolver, Error, TlsVersion,
 };
 use http::Uri;
+use std::{fmt, sync::Arc};
+use tokio_rustls::rustls::client::ClientSessionMemoryCache;
+use tokio_rustls::rustls::crypto::CryptoProvider;
 
 /// Configures TLS settings for endpoints.
 #[derive(Debug, Clone, Default)]
@@ -11,6 +15,11 @@ pub struct ClientTlsConfig {
     domain: Option<String>,
     certs: Vec<Certificate>,
     identity: Option<Identity>,
+    resolver: Option<SharedResolver>,
+    revocation: Option<RevocationConfig>,
+    verifier: Option<SharedVerifier>,
+    session_cache: Option<ClientSessionCache>,
+    policy: ProtocolPolicy,
     assume_http2: bool,
     #[cfg(feature = "tls-roots")]
     with_native_roots: bool,
@@ -54,6 +63,102 @@ impl ClientTlsConfig {
         }
     }
 
+    /// Sets a [`CredentialsResolver`] providing the client identity, and
+    /// optionally more CA certificates against which to verify the server's
+    /// TLS certificate, for each new connection.
+    ///
+    /// This lets certificates be rotated without recreating the channel, for
+    /// example with [`FileCredentials`]. It takes precedence over
+    /// [`ClientTlsConfig::identity`].
+    ///
+    /// [`FileCredentials`]: crate::transport::FileCredentials
+    pub fn credentials_resolver(self, resolver: impl CredentialsResolver) -> Self {
+        ClientTlsConfig {
+            resolver: Some(SharedResolver(Arc::new(resolver))),
+            ..self
+        }
+    }
+
+    /// Sets how the server's TLS certificate is checked for revocation.
+    ///
+    /// By default, revocation is not checked.
+    pub fn revocation(self, revocation: RevocationConfig) -> Self {
+        ClientTlsConfig {
+            revocation: Some(revocation),
+            ..self
+        }
+    }
+
+    /// Sets a [`CertificateVerifier`] deciding whether to accept the server's
+    /// TLS certificate, once it has been verified against the CA
+    /// certificates.
+    ///
+    /// The certificate is then no longer checked against the domain name,
+    /// which is still sent to the server through SNI.
+    pub fn certificate_verifier(self, verifier: impl CertificateVerifier) -> Self {
+        ClientTlsConfig {
+            verifier: Some(SharedVerifier(Arc::new(verifier))),
+            ..self
+        }
+    }
+
+    /// Sets the cache of TLS sessions used to resume sessions with the
+    /// server, rather than perform a full handshake.
+    ///
+    /// By default, each channel has its own cache. Sharing one between the
+    /// channels to a server lets them resume each other's sessions, when
+    /// they are configured with the same certificates, verification and
+    /// protocols.
+    pub fn session_cache(self, cache: ClientSessionCache) -> Self {
+        ClientTlsConfig {
+            session_cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Sets the TLS protocol versions that may be negotiated.
+    ///
+    /// # Default
+    /// By default, TLS 1.2 and TLS 1.3 are allowed.
+    pub fn tls_versions(self, versions: impl IntoIterator<Item = TlsVersion>) -> Self {
+        let mut policy = self.policy;
+        policy.versions = Some(versions.into_iter().collect());
+        ClientTlsConfig { policy, ..self }
+    }
+
+    /// Sets the cipher suites that may be negotiated, among those supported.
+    ///
+    /// # Default
+    /// By default, every supported cipher suite is allowed.
+    pub fn cipher_suites(self, suites: impl IntoIterator<Item = CipherSuite>) -> Self {
+        let mut policy = self.policy;
+        policy.cipher_suites = Some(suites.into_iter().collect());
+        ClientTlsConfig { policy, ..self }
+    }
+
+    /// Sets the protocols offered to the server through ALPN, in order of
+    /// preference.
+    ///
+    /// # Default
+    /// By default, only `h2` is offered.
+    pub fn alpn_protocols(self, protocols: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        let mut policy = self.policy;
+        policy.alpn_protocols = Some(protocols.into_iter().map(Into::into).collect());
+        ClientTlsConfig { policy, ..self }
+    }
+
+    /// Sets the [`CryptoProvider`] the TLS client is built with.
+    ///
+    /// # Default
+    /// By default, the process-wide default provider is used if one is
+    /// installed, or else the one enabled by the `tls-ring` or `tls-aws-lc`
+    /// feature, `ring` first.
+    pub fn crypto_provider(self, provider: Arc<CryptoProvider>) -> Self {
+        let mut policy = self.policy;
+        policy.provider = Some(provider);
+        ClientTlsConfig { policy, ..self }
+    }
+
     /// If true, the connector should assume that the server supports HTTP/2,
     /// even if it doesn't provide protocol negotiation via ALPN.
     pub fn assume_http2(self, assume_http2: bool) -> Self {
@@ -89,6 +194,13 @@ impl ClientTlsConfig {
         TlsConnector::new(
             self.certs,
             self.identity,
+            self.resolver,
+            Verification {
+                revocation: self.revocation,
+                verifier: self.verifier,
+            },
+            self.session_cache.unwrap_or_default(),
+            self.policy,
             domain,
             self.assume_http2,
             #[cfg(feature = "tls-roots")]
@@ -98,3 +210,98 @@ impl ClientTlsConfig {
         )
     }
 }
+
+/// A cache of TLS sessions, used to resume sessions with servers connected to
+/// before. See [`ClientTlsConfig::session_cache`].
+#[derive(Clone)]
+pub struct ClientSessionCache {
+    pub(crate) inner: Arc<ClientSessionMemoryCache>,
+    pub(crate) config: Arc<SharedConfig>,
+}
+
+impl ClientSessionCache {
+    /// Creates a cache holding up to `capacity` sessions.
+    ///
+    /// Room is made for several TLS 1.3 sessions per server, in blocks of
+    /// eight, so caches for fewer than nine sessions may not keep any.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(ClientSessionMemoryCache::new(capacity)),
+            config: Arc::default(),
+        }
+    }
+}
+
+impl Default for ClientSessionCache {
+    /// Creates a cache holding up to 256 sessions.
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl fmt::Debug for ClientSessionCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientSessionCache").finish()
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::transport::{
+        san_uris,
+        service::tls::test_util::{ca, handshake, identity, read},
+        CertificateDer, ServerTlsConfig,
+    };
+
+    fn connector(client: ClientTlsConfig) -> TlsConnector {
+        client
+            .into_tls_connector(&Uri::from_static("https://backend.example.org"))
+            .unwrap()
+    }
+
+    fn spiffe_id(expected: &'static str) -> impl CertificateVerifier {
+        move |chain: &[CertificateDer<'_>]| -> Result<(), crate::Error> {
+            if san_uris(&chain[0]).iter().any(|uri| uri == expected) {
+                Ok(())
+            } else {
+                Err("unexpected SPIFFE ID".into())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn certificate_verifier_replaces_name_check() {
+        // The server certificate only has a URI subject alternative name.
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("spiffe"))
+            .tls_acceptor()
+            .unwrap();
+        let client = ClientTlsConfig::new().ca_certificate(ca());
+
+        assert!(handshake(&acceptor, &connector(client.clone()))
+            .await
+            .is_err());
+
+        let verified = client
+            .clone()
+            .certificate_verifier(spiffe_id("spiffe://example.org/backend"));
+        handshake(&acceptor, &connector(verified)).await.unwrap();
+
+        let rejected = client.certificate_verifier(spiffe_id("spiffe://example.org/other"));
+        assert!(handshake(&acceptor, &connector(rejected)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn certificate_verifier_requires_trusted_chain() {
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("spiffe"))
+            .tls_acceptor()
+            .unwrap();
+        let untrusted = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(read("other-ca.pem")))
+            .certificate_verifier(spiffe_id("spiffe://example.org/backend"));
+
+        assert!(handshake(&acceptor, &connector(untrusted)).await.is_err());
+    }
+}
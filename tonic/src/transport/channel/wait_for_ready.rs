@@ -0,0 +1,23 @@
+/// Whether a call waits for the [`Channel`](super::Channel) to connect.
+///
+/// Calls normally fail with [`Unavailable`](crate::Code::Unavailable) as soon
+/// as the channel fails to connect. A call that waits for ready instead keeps
+/// trying to connect until it succeeds or the call's `grpc-timeout` deadline
+/// or [`Endpoint::timeout`](super::Endpoint::timeout) expires. It is sent
+/// again as soon as the channel becomes [`Ready`](super::ConnectivityState::Ready),
+/// or when the [`ReconnectBackoff`](super::ReconnectBackoff) of the endpoint
+/// allows another attempt to connect.
+///
+/// Inserting this into the extensions of a request overrides the default of
+/// the channel, set with [`Endpoint::wait_for_ready`](super::Endpoint::wait_for_ready)
+/// or [`Channel::wait_for_ready`](super::Channel::wait_for_ready).
+///
+/// ```
+/// # use tonic::{transport::channel::WaitForReady, Request};
+/// # use std::time::Duration;
+/// let mut request = Request::new(());
+/// request.set_timeout(Duration::from_secs(30));
+/// request.extensions_mut().insert(WaitForReady(true));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitForReady(pub bool);
@@ -0,0 +1,360 @@
+use super::{Certificate, Identity};
+use std::{
+    fmt, io,
+    path::PathBuf,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+/// The certificates a TLS endpoint presents and trusts at a point in time.
+#[derive(Debug, Clone)]
+pub struct TlsCredentials {
+    pub(crate) identity: Identity,
+    pub(crate) ca_certificate: Option<Certificate>,
+}
+
+impl TlsCredentials {
+    /// Creates credentials presenting `identity` to the peer.
+    pub fn new(identity: Identity) -> Self {
+        Self {
+            identity,
+            ca_certificate: None,
+        }
+    }
+
+    /// Sets the CA certificates against which to verify the peer.
+    ///
+    /// When set, these replace the CA certificates configured on
+    /// [`ServerTlsConfig`] and are added to those configured on
+    /// [`ClientTlsConfig`].
+    ///
+    /// [`ServerTlsConfig`]: crate::transport::ServerTlsConfig
+    /// [`ClientTlsConfig`]: crate::transport::ClientTlsConfig
+    pub fn ca_certificate(self, ca_certificate: Certificate) -> Self {
+        Self {
+            ca_certificate: Some(ca_certificate),
+            ..self
+        }
+    }
+}
+
+/// Provides the [`TlsCredentials`] to use for each new TLS connection, so
+/// that they can be rotated while connections are open.
+///
+/// Connections keep the credentials they were established with. Credentials
+/// that a TLS configuration cannot be built from, such as a certificate that
+/// does not match its private key, are ignored and new connections keep
+/// using the previous credentials until they change again.
+pub trait CredentialsResolver: Send + Sync + 'static {
+    /// Returns the credentials for a new connection.
+    ///
+    /// This is called for every connection, so it should be cheap. The TLS
+    /// configuration built from the credentials is reused for as long as the
+    /// same `Arc` is returned.
+    fn resolve(&self) -> Arc<TlsCredentials>;
+}
+
+impl CredentialsResolver for Arc<TlsCredentials> {
+    fn resolve(&self) -> Arc<TlsCredentials> {
+        self.clone()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedResolver(pub(crate) Arc<dyn CredentialsResolver>);
+
+impl fmt::Debug for SharedResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialsResolver").finish()
+    }
+}
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The PEM files to load [`TlsCredentials`] from, and reload them from when
+/// they change.
+///
+/// # Example
+///
+/// ```no_run
+/// # use tonic::transport::{FileCredentials, ServerTlsConfig};
+/// # use std::time::Duration;
+/// # async fn run() -> std::io::Result<()> {
+/// let credentials = FileCredentials::new("/etc/tls/tls.crt", "/etc/tls/tls.key")
+///     .ca_certificate("/etc/tls/ca.crt")
+///     .poll_interval(Duration::from_secs(30))
+///     .watch()?;
+/// let tls = ServerTlsConfig::new().credentials_resolver(credentials);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    cert: PathBuf,
+    key: PathBuf,
+    ca_certificate: Option<PathBuf>,
+    poll_interval: Duration,
+}
+
+impl FileCredentials {
+    /// Loads the identity from the given certificate chain and private key.
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+            ca_certificate: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Loads the CA certificates against which to verify the peer.
+    pub fn ca_certificate(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_certificate: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Sets how often the files are checked for changes.
+    ///
+    /// Default is 60 seconds.
+    pub fn poll_interval(self, interval: Duration) -> Self {
+        Self {
+            poll_interval: interval,
+            ..self
+        }
+    }
+
+    /// Loads the files, and spawns a task reloading them whenever their
+    /// modification time changes.
+    ///
+    /// Files that fail to load are retried at the next check, and the last
+    /// credentials loaded are used meanwhile. The same goes for files that
+    /// load but do not make valid credentials, see [`CredentialsResolver`]. The task stops once the
+    /// returned [`WatchedCredentials`] and all its clones are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn watch(self) -> io::Result<WatchedCredentials> {
+        let (credentials, modified) = self.load()?;
+        let current = Arc::new(RwLock::new(Arc::new(credentials)));
+
+        tokio::spawn(poll(self, Arc::downgrade(&current), modified));
+
+        Ok(WatchedCredentials { current })
+    }
+
+    fn modified(&self) -> io::Result<Vec<SystemTime>> {
+        self.paths()
+            .map(|path| std::fs::metadata(path)?.modified())
+            .collect()
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.cert, &self.key]
+            .into_iter()
+            .chain(self.ca_certificate.as_ref())
+    }
+
+    fn load(&self) -> io::Result<(TlsCredentials, Vec<SystemTime>)> {
+        let modified = self.modified()?;
+
+        let identity = Identity::from_pem(std::fs::read(&self.cert)?, std::fs::read(&self.key)?);
+        let mut credentials = TlsCredentials::new(identity);
+        if let Some(path) = &self.ca_certificate {
+            credentials = credentials.ca_certificate(Certificate::from_pem(std::fs::read(path)?));
+        }
+
+        Ok((credentials, modified))
+    }
+}
+
+async fn poll(
+    files: FileCredentials,
+    current: Weak<RwLock<Arc<TlsCredentials>>>,
+    mut modified: Vec<SystemTime>,
+) {
+    loop {
+        tokio::time::sleep(files.poll_interval).await;
+        if current.strong_count() == 0 {
+            return;
+        }
+
+        let reload = files.clone();
+        let last_modified = modified.clone();
+        let loaded = tokio::task::spawn_blocking(move || match reload.modified() {
+            Ok(now) if now == last_modified => Ok(None),
+            _ => reload.load().map(Some),
+        })
+        .await;
+
+        match loaded {
+            Ok(Ok(Some((credentials, now)))) => {
+                let Some(current) = current.upgrade() else {
+                    return;
+                };
+                debug!("reloaded TLS credentials");
+                *current.write().unwrap() = Arc::new(credentials);
+                modified = now;
+            }
+            Ok(Ok(None)) => {}
+            Ok(Err(err)) => warn!("failed to reload TLS credentials: {}", err),
+            Err(err) => warn!("failed to reload TLS credentials: {}", err),
+        }
+    }
+}
+
+/// [`TlsCredentials`] reloaded from files as they change, created with
+/// [`FileCredentials::watch`].
+#[derive(Debug, Clone)]
+pub struct WatchedCredentials {
+    current: Arc<RwLock<Arc<TlsCredentials>>>,
+}
+
+impl CredentialsResolver for WatchedCredentials {
+    fn resolve(&self) -> Arc<TlsCredentials> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+/// TLS configurations built from the credentials of a [`CredentialsResolver`],
+/// rebuilt when they change.
+pub(crate) struct ResolvedConfig<C> {
+    resolver: SharedResolver,
+    cache: std::sync::Mutex<Option<(Arc<TlsCredentials>, Arc<C>)>>,
+}
+
+impl<C> ResolvedConfig<C> {
+    pub(crate) fn new(resolver: SharedResolver) -> Self {
+        Self {
+            resolver,
+            cache: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Returns the configuration for the current credentials, building it
+    /// with `build` if they changed.
+    ///
+    /// The previous configuration is kept if it cannot be built, so that
+    /// bad credentials only fail the first configuration.
+    pub(crate) fn get(
+        &self,
+        build: impl FnOnce(&TlsCredentials) -> Result<C, crate::Error>,
+    ) -> Result<Arc<C>, crate::Error> {
+        let credentials = self.resolver.0.resolve();
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((cached, config)) = &*cache {
+            if Arc::ptr_eq(cached, &credentials) {
+                return Ok(config.clone());
+            }
+        }
+
+        match (build(&credentials), &mut *cache) {
+            (Ok(config), cache) => {
+                let config = Arc::new(config);
+                *cache = Some((credentials, config.clone()));
+                Ok(config)
+            }
+            (Err(err), Some((cached, config))) => {
+                warn!(
+                    "invalid TLS credentials, keeping the previous ones: {}",
+                    err
+                );
+                // Do not try building them again for every connection.
+                *cached = credentials;
+                Ok(config.clone())
+            }
+            (Err(err), None) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_rebuilt_when_credentials_change() {
+        let identity = Identity::from_pem("cert", "key");
+        let current = Arc::new(RwLock::new(Arc::new(TlsCredentials::new(identity.clone()))));
+        let resolved = ResolvedConfig::new(SharedResolver(Arc::new(WatchedCredentials {
+            current: current.clone(),
+        })));
+
+        let mut builds = 0;
+        let mut build = |_: &TlsCredentials| {
+            builds += 1;
+            Ok(builds)
+        };
+
+        assert_eq!(*resolved.get(&mut build).unwrap(), 1);
+        assert_eq!(*resolved.get(&mut build).unwrap(), 1);
+
+        *current.write().unwrap() = Arc::new(TlsCredentials::new(identity));
+        assert_eq!(*resolved.get(&mut build).unwrap(), 2);
+    }
+
+    #[test]
+    fn config_kept_when_credentials_are_invalid() {
+        let identity = Identity::from_pem("cert", "key");
+        let current = Arc::new(RwLock::new(Arc::new(TlsCredentials::new(identity.clone()))));
+        let resolved = ResolvedConfig::new(SharedResolver(Arc::new(WatchedCredentials {
+            current: current.clone(),
+        })));
+
+        let mut builds = 0;
+        let mut build = |credentials: &TlsCredentials| {
+            builds += 1;
+            match credentials.identity.key.as_slice() {
+                b"bad key" => Err("bad key".into()),
+                _ => Ok(builds),
+            }
+        };
+
+        assert_eq!(*resolved.get(&mut build).unwrap(), 1);
+
+        *current.write().unwrap() =
+            Arc::new(TlsCredentials::new(Identity::from_pem("cert", "bad key")));
+        assert_eq!(*resolved.get(&mut build).unwrap(), 1);
+        assert_eq!(*resolved.get(&mut build).unwrap(), 1);
+
+        *current.write().unwrap() = Arc::new(TlsCredentials::new(identity));
+        assert_eq!(*resolved.get(&mut build).unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn reloads_changed_files() {
+        let dir = std::env::temp_dir().join(format!("tonic-credentials-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("tls.crt"), dir.join("tls.key"));
+        std::fs::write(&cert, "cert 1").unwrap();
+        std::fs::write(&key, "key").unwrap();
+
+        let watched = FileCredentials::new(&cert, &key)
+            .poll_interval(Duration::from_millis(10))
+            .watch()
+            .unwrap();
+        assert_eq!(watched.resolve().identity.cert.get_ref(), b"cert 1");
+
+        // Rewrite the file until its modification time changes, whatever
+        // its resolution.
+        let modified = std::fs::metadata(&cert).unwrap().modified().unwrap();
+        while std::fs::metadata(&cert).unwrap().modified().unwrap() == modified {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            std::fs::write(&cert, "cert 2").unwrap();
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while watched.resolve().identity.cert.get_ref() != b"cert 2" {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("credentials were not reloaded");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -94,9 +94,11 @@ pub mod channel;
 #[cfg(feature = "server")]
 pub mod server;
 
+#[cfg(feature = "_tls-any")]
+mod credentials;
 mod error;
 mod service;
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 mod tls;
 
 #[doc(inline)]
@@ -109,15 +111,22 @@ pub use self::server::Server;
 #[doc(inline)]
 pub use self::service::grpc_timeout::TimeoutExpired;
 
-#[cfg(feature = "tls")]
-pub use self::tls::Certificate;
+#[cfg(feature = "_tls-any")]
+pub use self::credentials::{
+    CredentialsResolver, FileCredentials, TlsCredentials, WatchedCredentials,
+};
+#[cfg(feature = "_tls-any")]
+pub use self::tls::{
+    san_uris, Certificate, CertificateRevocationList, CertificateVerifier, RevocationConfig,
+    TlsVersion,
+};
 pub use hyper::{body::Body, Uri};
-#[cfg(feature = "tls")]
-pub use tokio_rustls::rustls::pki_types::CertificateDer;
+#[cfg(feature = "_tls-any")]
+pub use tokio_rustls::rustls::{crypto::CryptoProvider, pki_types::CertificateDer, CipherSuite};
 
-#[cfg(all(feature = "channel", feature = "tls"))]
-pub use self::channel::ClientTlsConfig;
-#[cfg(all(feature = "server", feature = "tls"))]
+#[cfg(all(feature = "channel", feature = "_tls-any"))]
+pub use self::channel::{ClientSessionCache, ClientTlsConfig};
+#[cfg(all(feature = "server", feature = "_tls-any"))]
 pub use self::server::ServerTlsConfig;
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 pub use self::tls::Identity;
@@ -1,11 +1,13 @@
 use std::net::SocketAddr;
 use tokio::net::TcpStream;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
+use crate::transport::{CipherSuite, TlsVersion};
+#[cfg(feature = "_tls-any")]
 use std::sync::Arc;
-#[cfg(feature = "tls")]
-use tokio_rustls::rustls::pki_types::CertificateDer;
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
+use tokio_rustls::rustls::{pki_types::CertificateDer, HandshakeKind};
+#[cfg(feature = "_tls-any")]
 use tokio_rustls::server::TlsStream;
 
 /// Trait that connected IO resources implement and use to produce info about the connection.
@@ -102,7 +104,7 @@ impl Connected for tokio::io::DuplexStream {
     fn connect_info(&self) -> Self::ConnectInfo {}
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 impl<T> Connected for TlsStream<T>
 where
     T: Connected,
@@ -117,7 +119,19 @@ where
             .peer_certificates()
             .map(|certs| certs.to_owned().into());
 
-        TlsConnectInfo { inner, certs }
+        let server_name = session.server_name().map(Arc::from);
+        let resumed = session.handshake_kind() == Some(HandshakeKind::Resumed);
+        let version = session.protocol_version().and_then(TlsVersion::from_rustls);
+        let cipher_suite = session.negotiated_cipher_suite().map(|suite| suite.suite());
+
+        TlsConnectInfo {
+            inner,
+            certs,
+            server_name,
+            resumed,
+            version,
+            cipher_suite,
+        }
     }
 }
 
@@ -128,14 +142,18 @@ where
 /// See [`Connected`] for more details.
 ///
 /// [ext]: crate::Request::extensions
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 #[derive(Debug, Clone)]
 pub struct TlsConnectInfo<T> {
     inner: T,
     certs: Option<Arc<Vec<CertificateDer<'static>>>>,
+    server_name: Option<Arc<str>>,
+    resumed: bool,
+    version: Option<TlsVersion>,
+    cipher_suite: Option<CipherSuite>,
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 impl<T> TlsConnectInfo<T> {
     /// Get a reference to the underlying connection info.
     pub fn get_ref(&self) -> &T {
@@ -151,4 +169,25 @@ impl<T> TlsConnectInfo<T> {
     pub fn peer_certs(&self) -> Option<Arc<Vec<CertificateDer<'static>>>> {
         self.certs.clone()
     }
+
+    /// Return the server name the client asked for through SNI, if any.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// Return whether the TLS session was resumed, rather than established
+    /// with a full handshake.
+    pub fn session_resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// Return the negotiated TLS protocol version.
+    pub fn protocol_version(&self) -> Option<TlsVersion> {
+        self.version
+    }
+
+    /// Return the negotiated cipher suite.
+    pub fn cipher_suite(&self) -> Option<CipherSuite> {
+        self.cipher_suite
+    }
 }
@@ -0,0 +1,225 @@
+use crate::{
+    body::{boxed, BoxBody},
+    Status,
+};
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use std::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::watch;
+use tower_service::Service;
+
+/// How many calls were still in flight when a [`Server`](super::Server) was
+/// shut down, and what became of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    drained: usize,
+    aborted: usize,
+}
+
+impl ShutdownReport {
+    /// The number of calls that completed before the grace period ended.
+    pub fn drained(&self) -> usize {
+        self.drained
+    }
+
+    /// The number of calls cancelled with [`Code::Unavailable`] once the
+    /// grace period ended.
+    ///
+    /// [`Code::Unavailable`]: crate::Code::Unavailable
+    pub fn aborted(&self) -> usize {
+        self.aborted
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Serving,
+    Draining,
+    Aborting,
+}
+
+/// Tracks the calls in flight on a server while it shuts down, and aborts
+/// them once the grace period ends.
+#[derive(Clone)]
+pub(crate) struct Drain {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    phase: watch::Sender<Phase>,
+    drained: AtomicUsize,
+    aborted: AtomicUsize,
+}
+
+impl Drain {
+    pub(crate) fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                phase: watch::channel(Phase::Serving).0,
+                drained: AtomicUsize::new(0),
+                aborted: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Count calls that end from now on as drained.
+    pub(crate) fn start(&self) {
+        self.shared.phase.send_replace(Phase::Draining);
+    }
+
+    /// Abort every call still in flight.
+    pub(crate) fn abort(&self) {
+        self.shared.phase.send_replace(Phase::Aborting);
+    }
+
+    pub(crate) fn report(&self) -> ShutdownReport {
+        ShutdownReport {
+            drained: self.shared.drained.load(Ordering::Acquire),
+            aborted: self.shared.aborted.load(Ordering::Acquire),
+        }
+    }
+
+    /// Resolves once calls are aborted.
+    fn aborted(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut phase = self.shared.phase.subscribe();
+
+        async move {
+            // The sender lives as long as any call, so this never fails.
+            let _ = phase.wait_for(|phase| *phase == Phase::Aborting).await;
+        }
+    }
+
+    fn record_end(&self) {
+        if *self.shared.phase.borrow() != Phase::Serving {
+            self.shared.drained.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    fn record_abort(&self) {
+        self.shared.aborted.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+fn shutdown_status() -> Status {
+    Status::unavailable("server is shutting down")
+}
+
+/// Lets the calls made to `S` be aborted by a [`Drain`].
+#[derive(Clone)]
+pub(crate) struct DrainService<S> {
+    inner: S,
+    drain: Drain,
+}
+
+impl<S> DrainService<S> {
+    pub(crate) fn new(inner: S, drain: Drain) -> Self {
+        Self { inner, drain }
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for DrainService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+    S::Future: Send + 'static,
+    S::Error: 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<BoxBody>, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let drain = self.drain.clone();
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut fut = pin!(fut);
+            let mut aborted = pin!(drain.aborted());
+
+            let res = poll_fn(|cx| {
+                if aborted.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(None);
+                }
+                fut.as_mut().poll(cx).map(Some)
+            })
+            .await;
+
+            match res {
+                Some(res) => Ok(res?.map(|body| {
+                    boxed(DrainBody {
+                        inner: body,
+                        aborted: Box::pin(drain.aborted()),
+                        drain,
+                        aborted_call: false,
+                    })
+                })),
+                None => {
+                    drain.record_abort();
+                    Ok(shutdown_status().into_http())
+                }
+            }
+        })
+    }
+}
+
+/// Response body that ends with [`shutdown_status`] once calls are aborted.
+struct DrainBody {
+    inner: BoxBody,
+    aborted: Pin<Box<dyn Future<Output = ()> + Send>>,
+    drain: Drain,
+    aborted_call: bool,
+}
+
+impl Body for DrainBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.aborted_call {
+            return Poll::Ready(None);
+        }
+
+        if self.aborted.as_mut().poll(cx).is_ready() {
+            self.aborted_call = true;
+            self.drain.record_abort();
+
+            let trailers = shutdown_status()
+                .to_header_map()
+                .unwrap_or_else(|_| Default::default());
+            return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+        }
+
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.aborted_call || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for DrainBody {
+    fn drop(&mut self) {
+        if !self.aborted_call {
+            self.drain.record_end();
+        }
+    }
+}
@@ -1,4 +1,4 @@
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 use super::service::TlsAcceptor;
 use super::{service::ServerIo, Connected};
 use std::{
@@ -15,7 +15,7 @@ use tokio_stream::wrappers::TcpListenerStream;
 use tokio_stream::{Stream, StreamExt};
 use tracing::warn;
 
-#[cfg(not(feature = "tls"))]
+#[cfg(not(feature = "_tls-any"))]
 pub(crate) fn tcp_incoming<IO, IE>(
     incoming: impl Stream<Item = Result<IO, IE>>,
 ) -> impl Stream<Item = Result<ServerIo<IO>, crate::Error>>
@@ -32,7 +32,7 @@ where
     }
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 pub(crate) fn tcp_incoming<IO, IE>(
     incoming: impl Stream<Item = Result<IO, IE>>,
     tls: Option<TlsAcceptor>,
@@ -76,7 +76,7 @@ where
     }
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 async fn select<IO: 'static, IE>(
     incoming: &mut (impl Stream<Item = Result<IO, IE>> + Unpin),
     tasks: &mut tokio::task::JoinSet<Result<ServerIo<IO>, crate::Error>>,
@@ -111,7 +111,7 @@ where
     }
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 enum SelectOutput<A> {
     Incoming(A),
     Io(ServerIo<A>),
@@ -0,0 +1,435 @@
+use crate::transport::service::IdleTracker;
+use std::{
+    io, mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Instant,
+};
+use tracing::debug;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_HEADER_LEN: usize = 9;
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const ACK: u8 = 0x1;
+
+const ENHANCE_YOUR_CALM: u32 = 0xb;
+const GOAWAY_DEBUG_DATA: &[u8] = b"too_many_pings";
+
+/// How many pings may violate the policy before the connection is closed.
+const MAX_PING_STRIKES: u32 = 2;
+
+/// How often a client may ping while it has no call in flight, when that is
+/// not permitted. Clients only do so until their keepalive shuts down.
+const PING_WITHOUT_STREAM_INTERVAL: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// What a client may do with HTTP/2 pings, as enforced by grpc-go servers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KeepalivePolicy {
+    pub(crate) min_interval: Duration,
+    pub(crate) permit_without_stream: bool,
+}
+
+/// Finds the headers of HTTP/2 frames in a stream of bytes.
+#[derive(Default)]
+struct FrameParser {
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    payload_left: usize,
+}
+
+impl FrameParser {
+    /// Calls `on_frame` with the type, flags and stream id of each frame
+    /// whose header ends in `data`.
+    fn feed(&mut self, mut data: &[u8], mut on_frame: impl FnMut(u8, u8, u32)) {
+        while !data.is_empty() {
+            if self.payload_left > 0 {
+                let n = self.payload_left.min(data.len());
+                self.payload_left -= n;
+                data = &data[n..];
+                continue;
+            }
+
+            let n = (FRAME_HEADER_LEN - self.header_len).min(data.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&data[..n]);
+            self.header_len += n;
+            data = &data[n..];
+
+            if self.header_len == FRAME_HEADER_LEN {
+                let h = &self.header;
+                let len = u32::from_be_bytes([0, h[0], h[1], h[2]]) as usize;
+                let stream_id = u32::from_be_bytes([h[5], h[6], h[7], h[8]]) & 0x7fff_ffff;
+                on_frame(h[3], h[4], stream_id);
+
+                self.header_len = 0;
+                self.payload_left = len;
+            }
+        }
+    }
+
+    /// Whether the stream is between two frames.
+    fn at_boundary(&self) -> bool {
+        self.header_len == 0 && self.payload_left == 0
+    }
+}
+
+/// Closes HTTP/2 connections whose client pings more often than a
+/// [`KeepalivePolicy`] allows, with a GOAWAY frame carrying
+/// `ENHANCE_YOUR_CALM`.
+///
+/// hyper answers pings on its own, so they are found by reading the frames
+/// going through the connection. Connections that are not HTTP/2 with prior
+/// knowledge are left alone.
+pub(crate) struct PingEnforcer<IO> {
+    inner: IO,
+    policy: Option<(KeepalivePolicy, IdleTracker)>,
+    preface_read: usize,
+    inbound: FrameParser,
+    outbound: FrameParser,
+    last_ping: Option<Instant>,
+    strikes: u32,
+    reset_strikes: bool,
+    last_stream_id: u32,
+    goaway: Option<(Vec<u8>, usize)>,
+    closed: bool,
+}
+
+impl<IO> PingEnforcer<IO> {
+    /// Enforce `policy` on `inner`, counting the calls in flight with the
+    /// given tracker.
+    pub(crate) fn new(inner: IO, policy: Option<(KeepalivePolicy, IdleTracker)>) -> Self {
+        Self {
+            inner,
+            policy,
+            preface_read: 0,
+            inbound: FrameParser::default(),
+            outbound: FrameParser::default(),
+            last_ping: None,
+            strikes: 0,
+            reset_strikes: false,
+            last_stream_id: 0,
+            goaway: None,
+            closed: false,
+        }
+    }
+
+    fn inspect_inbound(&mut self, mut data: &[u8]) {
+        if self.policy.is_none() {
+            return;
+        }
+
+        if self.preface_read < PREFACE.len() {
+            let n = (PREFACE.len() - self.preface_read).min(data.len());
+            if data[..n] != PREFACE[self.preface_read..self.preface_read + n] {
+                self.policy = None;
+                return;
+            }
+            self.preface_read += n;
+            data = &data[n..];
+        }
+
+        let mut pings = 0;
+        let mut last_stream_id = self.last_stream_id;
+        self.inbound.feed(data, |ty, flags, stream_id| match ty {
+            PING if flags & ACK == 0 => pings += 1,
+            HEADERS => last_stream_id = last_stream_id.max(stream_id),
+            _ => {}
+        });
+        self.last_stream_id = last_stream_id;
+
+        for _ in 0..pings {
+            self.on_ping();
+        }
+    }
+
+    fn inspect_outbound(&mut self, data: &[u8]) {
+        if self.policy.is_none() {
+            return;
+        }
+
+        let mut reset_strikes = false;
+        self.outbound.feed(data, |ty, _, _| {
+            if ty == DATA || ty == HEADERS {
+                reset_strikes = true;
+            }
+        });
+        self.reset_strikes |= reset_strikes;
+    }
+
+    fn on_ping(&mut self) {
+        let (policy, calls) = match &self.policy {
+            Some(policy) => policy,
+            None => return,
+        };
+
+        let now = Instant::now();
+        let last_ping = self.last_ping.replace(now);
+
+        // Sending a response makes up for the pings received so far.
+        if mem::take(&mut self.reset_strikes) {
+            self.strikes = 0;
+            return;
+        }
+
+        let min_interval = if calls.in_flight() == 0 && !policy.permit_without_stream {
+            PING_WITHOUT_STREAM_INTERVAL
+        } else {
+            policy.min_interval
+        };
+        let too_soon = last_ping.is_some_and(|last| now.duration_since(last) < min_interval);
+        if too_soon {
+            self.strikes += 1;
+        }
+
+        if self.strikes > MAX_PING_STRIKES && self.goaway.is_none() {
+            debug!("client sent too many pings, closing connection");
+            self.goaway = Some((goaway_frame(self.last_stream_id), 0));
+        }
+    }
+
+    /// Writes the pending GOAWAY frame, once the frame being written by
+    /// hyper is complete.
+    fn poll_goaway(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    where
+        IO: AsyncWrite + Unpin,
+    {
+        if !self.outbound.at_boundary() {
+            // hyper finishes writing its frame first, and wakes the task
+            // when the connection is writable again.
+            return Poll::Pending;
+        }
+
+        if let Some((frame, written)) = &mut self.goaway {
+            while *written < frame.len() {
+                let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &frame[*written..]))?;
+                if n == 0 {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                *written += n;
+            }
+            ready!(Pin::new(&mut self.inner).poll_flush(cx))?;
+        }
+
+        self.closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn goaway_frame(last_stream_id: u32) -> Vec<u8> {
+    let len = 8 + GOAWAY_DEBUG_DATA.len() as u32;
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + len as usize);
+    frame.extend_from_slice(&len.to_be_bytes()[1..]);
+    frame.push(GOAWAY);
+    frame.push(0);
+    frame.extend_from_slice(&0u32.to_be_bytes());
+    frame.extend_from_slice(&last_stream_id.to_be_bytes());
+    frame.extend_from_slice(&ENHANCE_YOUR_CALM.to_be_bytes());
+    frame.extend_from_slice(GOAWAY_DEBUG_DATA);
+    frame
+}
+
+fn closed_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "connection closed for sending too many pings",
+    )
+}
+
+impl<IO: AsyncRead + AsyncWrite + Unpin> AsyncRead for PingEnforcer<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        if this.goaway.is_some() {
+            return this.poll_goaway(cx);
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.inspect_inbound(&buf.filled()[filled..]);
+
+        if this.goaway.is_some() {
+            // The connection is closing, the rest is not served.
+            buf.set_filled(filled);
+            return this.poll_goaway(cx);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for PingEnforcer<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        if this.closed {
+            return Poll::Ready(Err(closed_error()));
+        }
+        if this.goaway.is_some() && this.outbound.at_boundary() {
+            ready!(this.poll_goaway(cx))?;
+            return Poll::Ready(Err(closed_error()));
+        }
+
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.inspect_outbound(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    fn frame(ty: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+        frame.push(ty);
+        frame.push(flags);
+        frame.extend_from_slice(&stream_id.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn ping() -> Vec<u8> {
+        frame(PING, 0, 0, &[0; 8])
+    }
+
+    fn enforcer<IO>(io: IO, permit_without_stream: bool) -> PingEnforcer<IO> {
+        let policy = KeepalivePolicy {
+            min_interval: Duration::from_secs(60),
+            permit_without_stream,
+        };
+        PingEnforcer::new(io, Some((policy, IdleTracker::new())))
+    }
+
+    #[test]
+    fn parser_splits_frames_across_reads() {
+        let mut bytes = frame(HEADERS, 0x4, 3, b"abc");
+        bytes.extend(frame(PING, ACK, 0, &[0; 8]));
+
+        let mut parser = FrameParser::default();
+        let mut frames = Vec::new();
+        for chunk in bytes.chunks(4) {
+            parser.feed(chunk, |ty, flags, id| frames.push((ty, flags, id)));
+        }
+
+        assert_eq!(frames, vec![(HEADERS, 0x4, 3), (PING, ACK, 0)]);
+        assert!(parser.at_boundary());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn goaway_after_too_many_pings() {
+        let (client, server) = duplex(1024);
+        let (mut client_read, mut client_write) = tokio::io::split(client);
+        let mut server = enforcer(server, true);
+
+        client_write.write_all(PREFACE).await.unwrap();
+        client_write
+            .write_all(&frame(HEADERS, 0x4, 1, b""))
+            .await
+            .unwrap();
+        for _ in 0..=MAX_PING_STRIKES + 1 {
+            client_write.write_all(&ping()).await.unwrap();
+        }
+
+        // Everything up to the violation is read, then the connection ends.
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert!(server.write_all(b"more").await.is_err());
+
+        let mut goaway = vec![0; FRAME_HEADER_LEN + 8 + GOAWAY_DEBUG_DATA.len()];
+        client_read.read_exact(&mut goaway).await.unwrap();
+        assert_eq!(goaway, goaway_frame(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pings_spaced_out_are_allowed() {
+        let (mut client, server) = duplex(1024);
+        let mut server = enforcer(server, true);
+
+        client.write_all(PREFACE).await.unwrap();
+        for _ in 0..10 {
+            client.write_all(&ping()).await.unwrap();
+            let mut buf = [0; 64];
+            let _ = server.read(&mut buf).await.unwrap();
+            tokio::time::advance(Duration::from_secs(60)).await;
+        }
+
+        assert_eq!(server.strikes, 0);
+        assert!(server.goaway.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pings_without_stream() {
+        let (mut client, server) = duplex(1024);
+        let mut server = enforcer(server, false);
+
+        client.write_all(PREFACE).await.unwrap();
+        for _ in 0..2 {
+            client.write_all(&ping()).await.unwrap();
+            let mut buf = [0; 64];
+            let _ = server.read(&mut buf).await.unwrap();
+            tokio::time::advance(Duration::from_secs(60)).await;
+        }
+
+        assert_eq!(server.strikes, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn responses_reset_strikes() {
+        let (mut client, server) = duplex(1024);
+        let mut server = enforcer(server, true);
+
+        client.write_all(PREFACE).await.unwrap();
+        for _ in 0..2 {
+            client.write_all(&ping()).await.unwrap();
+            let mut buf = [0; 64];
+            let _ = server.read(&mut buf).await.unwrap();
+        }
+        assert_eq!(server.strikes, 1);
+
+        server.write_all(&frame(DATA, 0, 1, b"data")).await.unwrap();
+        client.write_all(&ping()).await.unwrap();
+        let mut buf = [0; 64];
+        let _ = server.read(&mut buf).await.unwrap();
+        assert_eq!(server.strikes, 0);
+    }
+
+    #[tokio::test]
+    async fn http1_is_left_alone() {
+        let (mut client, server) = duplex(1024);
+        let mut server = enforcer(server, true);
+
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut buf = [0; 64];
+        let _ = server.read(&mut buf).await.unwrap();
+        assert!(server.policy.is_none());
+    }
+}
@@ -1,9 +1,11 @@
 //! Server implementation and builder.
 
 mod conn;
+mod drain;
 mod incoming;
-mod service;
-#[cfg(feature = "tls")]
+mod keepalive;
+pub(crate) mod service;
+#[cfg(feature = "_tls-any")]
 mod tls;
 #[cfg(unix)]
 mod unix;
@@ -15,17 +17,18 @@ use tracing::{debug, trace};
 pub use crate::service::{Routes, RoutesBuilder};
 
 pub use conn::{Connected, TcpConnectInfo};
+pub use drain::ShutdownReport;
 use hyper_util::{
     rt::{TokioExecutor, TokioIo, TokioTimer},
     service::TowerToHyperService,
 };
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 pub use tls::ServerTlsConfig;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 pub use conn::TlsConnectInfo;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 use self::service::TlsAcceptor;
 
 #[cfg(unix)]
@@ -33,11 +36,13 @@ pub use unix::UdsConnectInfo;
 
 pub use incoming::TcpIncoming;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 use crate::transport::Error;
 
+use self::drain::{Drain, DrainService};
+use self::keepalive::{KeepalivePolicy, PingEnforcer};
 use self::service::{RecoverError, ServerIo};
-use super::service::GrpcTimeout;
+use super::service::{random, GrpcTimeout, IdleTracker, TrackIdle};
 use crate::body::{boxed, BoxBody};
 use crate::server::NamedService;
 use bytes::Bytes;
@@ -73,6 +78,11 @@ type TraceInterceptor = Arc<dyn Fn(&http::Request<()>) -> tracing::Span + Send +
 
 const DEFAULT_HTTP2_KEEPALIVE_TIMEOUT_SECS: u64 = 20;
 
+/// The max connection age of every connection is randomized by up to 10% so
+/// that connections accepted together are not closed together. Documented on
+/// [`Server::max_connection_age`].
+const MAX_CONNECTION_AGE_JITTER: f64 = 0.1;
+
 /// A default batteries included `transport` server.
 ///
 /// This provides an easy builder pattern style builder [`Server`] on top of
@@ -86,7 +96,7 @@ pub struct Server<L = Identity> {
     trace_interceptor: Option<TraceInterceptor>,
     concurrency_limit: Option<usize>,
     timeout: Option<Duration>,
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     tls: Option<TlsAcceptor>,
     init_stream_window_size: Option<u32>,
     init_connection_window_size: Option<u32>,
@@ -95,10 +105,16 @@ pub struct Server<L = Identity> {
     tcp_nodelay: bool,
     http2_keepalive_interval: Option<Duration>,
     http2_keepalive_timeout: Option<Duration>,
+    http2_keepalive_min_interval: Option<Duration>,
+    http2_keepalive_permit_without_stream: bool,
     http2_adaptive_window: Option<bool>,
     http2_max_pending_accept_reset_streams: Option<usize>,
     max_frame_size: Option<u32>,
     accept_http1: bool,
+    idle_timeout: Option<Duration>,
+    shutdown_grace_period: Option<Duration>,
+    max_connection_age: Option<Duration>,
+    max_connection_age_grace: Option<Duration>,
     service_builder: ServiceBuilder<L>,
 }
 
@@ -108,7 +124,7 @@ impl Default for Server<Identity> {
             trace_interceptor: None,
             concurrency_limit: None,
             timeout: None,
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             tls: None,
             init_stream_window_size: None,
             init_connection_window_size: None,
@@ -117,10 +133,16 @@ impl Default for Server<Identity> {
             tcp_nodelay: false,
             http2_keepalive_interval: None,
             http2_keepalive_timeout: None,
+            http2_keepalive_min_interval: None,
+            http2_keepalive_permit_without_stream: false,
             http2_adaptive_window: None,
             http2_max_pending_accept_reset_streams: None,
             max_frame_size: None,
             accept_http1: false,
+            idle_timeout: None,
+            shutdown_grace_period: None,
+            max_connection_age: None,
+            max_connection_age_grace: None,
             service_builder: Default::default(),
         }
     }
@@ -150,7 +172,7 @@ impl Server {
 
 impl<L> Server<L> {
     /// Configure TLS for this server.
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     pub fn tls_config(self, tls_config: ServerTlsConfig) -> Result<Self, Error> {
         Ok(Server {
             tls: Some(tls_config.tls_acceptor().map_err(Error::from_source)?),
@@ -266,6 +288,53 @@ impl<L> Server<L> {
         }
     }
 
+    /// Sets the minimum time clients must wait between two HTTP2 Ping frames.
+    ///
+    /// A client that sends a ping sooner gets a strike, and after three
+    /// strikes its connection is closed with a GOAWAY frame carrying
+    /// `ENHANCE_YOUR_CALM` and the debug data `too_many_pings`, as grpc-go
+    /// servers do. Strikes are forgiven once the server sends a response.
+    /// Clients should set their keepalive interval to at least this value.
+    ///
+    /// Only connections made with HTTP2 prior knowledge, as gRPC clients do,
+    /// are policed.
+    ///
+    /// Default is to accept pings at any rate (`None`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder
+    ///     .http2_keepalive_min_interval(Some(Duration::from_secs(5 * 60)))
+    ///     .http2_keepalive_permit_without_stream(true);
+    /// ```
+    #[must_use]
+    pub fn http2_keepalive_min_interval(self, min_interval: Option<Duration>) -> Self {
+        Server {
+            http2_keepalive_min_interval: min_interval,
+            ..self
+        }
+    }
+
+    /// Sets whether clients may send HTTP2 Ping frames while they have no
+    /// call in flight.
+    ///
+    /// If not, such pings are allowed only once every two hours, and get the
+    /// client a strike otherwise. Does nothing if
+    /// [`Server::http2_keepalive_min_interval`] is not set.
+    ///
+    /// Default is false.
+    #[must_use]
+    pub fn http2_keepalive_permit_without_stream(self, permit: bool) -> Self {
+        Server {
+            http2_keepalive_permit_without_stream: permit,
+            ..self
+        }
+    }
+
     /// Sets whether to use an adaptive flow control. Defaults to false.
     /// Enabling this will override the limits set in http2_initial_stream_window_size and
     /// http2_initial_connection_window_size.
@@ -344,6 +413,103 @@ impl<L> Server<L> {
         }
     }
 
+    /// Gracefully close connections that have had no request in flight for
+    /// `timeout`, by sending them a GOAWAY frame.
+    ///
+    /// A request is in flight until its response has been sent completely.
+    ///
+    /// Default is to keep connections open.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.idle_timeout(Duration::from_secs(300));
+    /// ```
+    #[must_use]
+    pub fn idle_timeout(self, timeout: Duration) -> Self {
+        Server {
+            idle_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Gracefully close connections once they have been open for `age`, by
+    /// sending them a GOAWAY frame.
+    ///
+    /// Clients then open a new connection, which lets them spread over
+    /// server instances started after they first connected, for example
+    /// behind an L4 load balancer. See also
+    /// [`Server::max_connection_age_grace`].
+    ///
+    /// The age of each connection is randomized, so that connections accepted
+    /// at the same time do not all close at the same time. The jitter amount
+    /// is fixed at ±10%, drawn per connection: with an age of 30 minutes,
+    /// each connection is closed between 27 and 33 minutes after it was
+    /// accepted.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder
+    ///     .max_connection_age(Duration::from_secs(30 * 60))
+    ///     .max_connection_age_grace(Duration::from_secs(60));
+    /// ```
+    #[must_use]
+    pub fn max_connection_age(self, age: Duration) -> Self {
+        Server {
+            max_connection_age: Some(age),
+            ..self
+        }
+    }
+
+    /// Forcibly close connections that still have calls in flight `grace`
+    /// after reaching their [`Server::max_connection_age`].
+    ///
+    /// Default is to wait for every call to complete.
+    #[must_use]
+    pub fn max_connection_age_grace(self, grace: Duration) -> Self {
+        Server {
+            max_connection_age_grace: Some(grace),
+            ..self
+        }
+    }
+
+    /// Limit how long a graceful shutdown waits for calls in flight.
+    ///
+    /// When the shutdown signal passed to [`Router::serve_with_shutdown`] or
+    /// [`Router::serve_with_incoming_shutdown`] completes, the server stops
+    /// accepting connections and sends a GOAWAY frame on every connection.
+    /// Calls still in flight once the grace period ends are cancelled with
+    /// [`Code::Unavailable`](crate::Code::Unavailable), which lets the
+    /// shutdown complete. Use [`Router::serve_with_shutdown_report`] to find
+    /// out how many calls were drained and aborted.
+    ///
+    /// Default is to wait for every call to complete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.shutdown_grace_period(Duration::from_secs(10));
+    /// ```
+    #[must_use]
+    pub fn shutdown_grace_period(self, grace_period: Duration) -> Self {
+        Server {
+            shutdown_grace_period: Some(grace_period),
+            ..self
+        }
+    }
+
     /// Intercept inbound headers and add a [`tracing::Span`] to each response future.
     #[must_use]
     pub fn trace_fn<F>(self, f: F) -> Self
@@ -473,7 +639,7 @@ impl<L> Server<L> {
             trace_interceptor: self.trace_interceptor,
             concurrency_limit: self.concurrency_limit,
             timeout: self.timeout,
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             tls: self.tls,
             init_stream_window_size: self.init_stream_window_size,
             init_connection_window_size: self.init_connection_window_size,
@@ -482,10 +648,16 @@ impl<L> Server<L> {
             tcp_nodelay: self.tcp_nodelay,
             http2_keepalive_interval: self.http2_keepalive_interval,
             http2_keepalive_timeout: self.http2_keepalive_timeout,
+            http2_keepalive_min_interval: self.http2_keepalive_min_interval,
+            http2_keepalive_permit_without_stream: self.http2_keepalive_permit_without_stream,
             http2_adaptive_window: self.http2_adaptive_window,
             http2_max_pending_accept_reset_streams: self.http2_max_pending_accept_reset_streams,
             max_frame_size: self.max_frame_size,
             accept_http1: self.accept_http1,
+            idle_timeout: self.idle_timeout,
+            shutdown_grace_period: self.shutdown_grace_period,
+            max_connection_age: self.max_connection_age,
+            max_connection_age_grace: self.max_connection_age_grace,
         }
     }
 
@@ -494,7 +666,7 @@ impl<L> Server<L> {
         svc: S,
         incoming: I,
         signal: Option<F>,
-    ) -> Result<(), super::Error>
+    ) -> Result<ShutdownReport, super::Error>
     where
         L: Layer<S>,
         L::Service:
@@ -518,11 +690,21 @@ impl<L> Server<L> {
         let timeout = self.timeout;
         let max_frame_size = self.max_frame_size;
         let http2_only = !self.accept_http1;
+        let idle_timeout = self.idle_timeout;
+        let shutdown_grace_period = self.shutdown_grace_period;
+        let max_connection_age = self.max_connection_age;
+        let max_connection_age_grace = self.max_connection_age_grace;
 
         let http2_keepalive_interval = self.http2_keepalive_interval;
         let http2_keepalive_timeout = self
             .http2_keepalive_timeout
             .unwrap_or_else(|| Duration::new(DEFAULT_HTTP2_KEEPALIVE_TIMEOUT_SECS, 0));
+        let keepalive_policy =
+            self.http2_keepalive_min_interval
+                .map(|min_interval| KeepalivePolicy {
+                    min_interval,
+                    permit_without_stream: self.http2_keepalive_permit_without_stream,
+                });
         let http2_adaptive_window = self.http2_adaptive_window;
         let http2_max_pending_accept_reset_streams = self.http2_max_pending_accept_reset_streams;
 
@@ -530,7 +712,7 @@ impl<L> Server<L> {
 
         let incoming = incoming::tcp_incoming(
             incoming,
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             self.tls,
         );
         let mut svc = MakeSvc {
@@ -567,6 +749,7 @@ impl<L> Server<L> {
         let signal_tx = Arc::new(signal_tx);
 
         let graceful = signal.is_some();
+        let drain = Drain::new();
         let mut sig = pin!(Fuse { inner: signal });
         let mut incoming = pin!(incoming);
 
@@ -600,14 +783,42 @@ impl<L> Server<L> {
                         .map_err(super::Error::from_source)?
                         .map_request(|req: Request<Incoming>| req.map(boxed));
 
+                    let req_svc = if graceful {
+                        Either::A(DrainService::new(req_svc, drain.clone()))
+                    } else {
+                        Either::B(req_svc)
+                    };
+
+                    let tracker = (idle_timeout.is_some() || keepalive_policy.is_some())
+                        .then(IdleTracker::new);
+                    let req_svc = match &tracker {
+                        Some(tracker) => Either::A(TrackIdle::new(req_svc, tracker.clone())),
+                        None => Either::B(req_svc),
+                    };
+                    let idle = tracker.clone().zip(idle_timeout);
+                    let io = PingEnforcer::new(io, keepalive_policy.zip(tracker));
+
                     let hyper_svc = TowerToHyperService::new(req_svc);
 
-                    serve_connection(io, hyper_svc, server.clone(), graceful.then(|| signal_rx.clone()));
+                    let max_age = max_connection_age.map(|age| MaxAge {
+                        age: jittered_age(age, random()),
+                        grace: max_connection_age_grace,
+                    });
+
+                    serve_connection(
+                        io,
+                        hyper_svc,
+                        server.clone(),
+                        graceful.then(|| signal_rx.clone()),
+                        idle,
+                        max_age,
+                    );
                 }
             }
         }
 
         if graceful {
+            drain.start();
             let _ = signal_tx.send(());
             drop(signal_rx);
             trace!(
@@ -616,20 +827,50 @@ impl<L> Server<L> {
             );
 
             // Wait for all connections to close
-            signal_tx.closed().await;
+            match shutdown_grace_period {
+                Some(grace_period) => {
+                    if tokio::time::timeout(grace_period, signal_tx.closed())
+                        .await
+                        .is_err()
+                    {
+                        debug!("shutdown grace period ended, aborting calls in flight");
+                        drain.abort();
+                        signal_tx.closed().await;
+                    }
+                }
+                None => signal_tx.closed().await,
+            }
         }
 
-        Ok(())
+        Ok(drain.report())
     }
 }
 
+/// The lifetime of a connection, once jittered.
+#[derive(Clone, Copy)]
+struct MaxAge {
+    age: Duration,
+    grace: Option<Duration>,
+}
+
+/// Randomizes a max connection age by [`MAX_CONNECTION_AGE_JITTER`], given a
+/// uniformly distributed `random` number in `[0, 1]`.
+///
+/// Ages too long to be represented saturate to `Duration::MAX`.
+fn jittered_age(age: Duration, random: f64) -> Duration {
+    let factor = 1.0 + MAX_CONNECTION_AGE_JITTER * (2.0 * random - 1.0);
+    Duration::try_from_secs_f64(age.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+}
+
 // This is moved to its own function as a way to get around
 // https://github.com/rust-lang/rust/issues/102211
 fn serve_connection<IO, S>(
-    io: ServerIo<IO>,
+    io: PingEnforcer<ServerIo<IO>>,
     hyper_svc: TowerToHyperService<S>,
     builder: ConnectionBuilder,
     mut watcher: Option<tokio::sync::watch::Receiver<()>>,
+    idle: Option<(IdleTracker, Duration)>,
+    max_age: Option<MaxAge>,
 ) where
     S: Service<Request<Incoming>, Response = Response<BoxBody>> + Clone + Send + 'static,
     S::Future: Send + 'static,
@@ -645,6 +886,19 @@ fn serve_connection<IO, S>(
 
             let mut conn = pin!(builder.serve_connection(TokioIo::new(io), hyper_svc));
 
+            let mut idle = pin!(Fuse {
+                inner: idle
+                    .as_ref()
+                    .map(|(tracker, timeout)| tracker.idle(*timeout)),
+            });
+
+            let mut age = pin!(Fuse {
+                inner: max_age.map(|max_age| tokio::time::sleep(max_age.age)),
+            });
+            let mut grace = pin!(Fuse {
+                inner: None::<tokio::time::Sleep>,
+            });
+
             loop {
                 tokio::select! {
                     rv = &mut conn => {
@@ -656,6 +910,23 @@ fn serve_connection<IO, S>(
                     _ = &mut sig => {
                         conn.as_mut().graceful_shutdown();
                     }
+                    _ = &mut idle => {
+                        debug!("closing idle connection");
+                        conn.as_mut().graceful_shutdown();
+                    }
+                    _ = &mut age => {
+                        debug!("connection reached its max age, closing");
+                        conn.as_mut().graceful_shutdown();
+                        if let Some(grace_period) = max_age.and_then(|max_age| max_age.grace) {
+                            grace.set(Fuse {
+                                inner: Some(tokio::time::sleep(grace_period)),
+                            });
+                        }
+                    }
+                    _ = &mut grace => {
+                        debug!("max connection age grace period ended, aborting connection");
+                        break;
+                    }
                 }
             }
         }
@@ -739,6 +1010,7 @@ impl<L> Router<L> {
                 None,
             )
             .await
+            .map(|_| ())
     }
 
     /// Consume this [`Server`] creating a future that will execute the server
@@ -752,6 +1024,32 @@ impl<L> Router<L> {
         addr: SocketAddr,
         signal: F,
     ) -> Result<(), super::Error>
+    where
+        L: Layer<Routes>,
+        L::Service:
+            Service<Request<BoxBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<BoxBody>>>::Future: Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<BoxBody>>>::Error:
+            Into<crate::Error> + Send,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::Error>,
+    {
+        let incoming = TcpIncoming::new(addr, self.server.tcp_nodelay, self.server.tcp_keepalive)
+            .map_err(super::Error::from_source)?;
+        self.server
+            .serve_with_shutdown(self.routes.prepare(), incoming, Some(signal))
+            .await
+            .map(|_| ())
+    }
+
+    /// Like [`Router::serve_with_shutdown`], but returns how many calls in
+    /// flight were drained and aborted during the shutdown, see
+    /// [`Server::shutdown_grace_period`].
+    pub async fn serve_with_shutdown_report<F: Future<Output = ()>, ResBody>(
+        self,
+        addr: SocketAddr,
+        signal: F,
+    ) -> Result<ShutdownReport, super::Error>
     where
         L: Layer<Routes>,
         L::Service:
@@ -800,6 +1098,7 @@ impl<L> Router<L> {
                 None,
             )
             .await
+            .map(|_| ())
     }
 
     /// Consume this [`Server`] creating a future that will execute the server
@@ -815,6 +1114,35 @@ impl<L> Router<L> {
         incoming: I,
         signal: F,
     ) -> Result<(), super::Error>
+    where
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IO::ConnectInfo: Clone + Send + Sync + 'static,
+        IE: Into<crate::Error>,
+        F: Future<Output = ()>,
+        L: Layer<Routes>,
+        L::Service:
+            Service<Request<BoxBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<BoxBody>>>::Future: Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<BoxBody>>>::Error:
+            Into<crate::Error> + Send,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::Error>,
+    {
+        self.server
+            .serve_with_shutdown(self.routes.prepare(), incoming, Some(signal))
+            .await
+            .map(|_| ())
+    }
+
+    /// Like [`Router::serve_with_incoming_shutdown`], but returns how many
+    /// calls in flight were drained and aborted during the shutdown, see
+    /// [`Server::shutdown_grace_period`].
+    pub async fn serve_with_incoming_shutdown_report<I, IO, IE, F, ResBody>(
+        self,
+        incoming: I,
+        signal: F,
+    ) -> Result<ShutdownReport, super::Error>
     where
         I: Stream<Item = Result<IO, IE>>,
         IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
@@ -980,13 +1308,13 @@ where
                         request.extensions_mut().insert(inner.clone());
                     }
                     tower::util::Either::B(inner) => {
-                        #[cfg(feature = "tls")]
+                        #[cfg(feature = "_tls-any")]
                         {
                             request.extensions_mut().insert(inner.clone());
                             request.extensions_mut().insert(inner.get_ref().clone());
                         }
 
-                        #[cfg(not(feature = "tls"))]
+                        #[cfg(not(feature = "_tls-any"))]
                         {
                             // just a type check to make sure we didn't forget to
                             // insert this into the extensions
@@ -1031,3 +1359,19 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_connection_age_jitter() {
+        let age = Duration::from_secs(30 * 60);
+        assert_eq!(jittered_age(age, 0.0), Duration::from_secs(27 * 60));
+        assert_eq!(jittered_age(age, 0.5), age);
+        assert_eq!(jittered_age(age, 1.0), Duration::from_secs(33 * 60));
+
+        assert_eq!(jittered_age(Duration::MAX, 1.0), Duration::MAX);
+        assert!(jittered_age(Duration::MAX, 0.0) > Duration::from_secs(u64::MAX / 2));
+    }
+}
@@ -4,22 +4,22 @@ use std::io::IoSlice;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 use tokio_rustls::server::TlsStream;
 
 pub(crate) enum ServerIo<IO> {
     Io(IO),
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     TlsIo(Box<TlsStream<IO>>),
 }
 
 use tower::util::Either;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 type ServerIoConnectInfo<IO> =
     Either<<IO as Connected>::ConnectInfo, <TlsStream<IO> as Connected>::ConnectInfo>;
 
-#[cfg(not(feature = "tls"))]
+#[cfg(not(feature = "_tls-any"))]
 type ServerIoConnectInfo<IO> = Either<<IO as Connected>::ConnectInfo, ()>;
 
 impl<IO> ServerIo<IO> {
@@ -27,12 +27,12 @@ impl<IO> ServerIo<IO> {
         Self::Io(io)
     }
 
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     pub(in crate::transport) fn new_tls_io(io: TlsStream<IO>) -> Self {
         Self::TlsIo(Box::new(io))
     }
 
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "_tls-any")]
     pub(in crate::transport) fn connect_info(&self) -> ServerIoConnectInfo<IO>
     where
         IO: Connected,
@@ -44,7 +44,7 @@ impl<IO> ServerIo<IO> {
         }
     }
 
-    #[cfg(not(feature = "tls"))]
+    #[cfg(not(feature = "_tls-any"))]
     pub(in crate::transport) fn connect_info(&self) -> ServerIoConnectInfo<IO>
     where
         IO: Connected,
@@ -66,7 +66,7 @@ where
     ) -> Poll<io::Result<()>> {
         match &mut *self {
             Self::Io(io) => Pin::new(io).poll_read(cx, buf),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => Pin::new(io).poll_read(cx, buf),
         }
     }
@@ -83,7 +83,7 @@ where
     ) -> Poll<io::Result<usize>> {
         match &mut *self {
             Self::Io(io) => Pin::new(io).poll_write(cx, buf),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => Pin::new(io).poll_write(cx, buf),
         }
     }
@@ -91,7 +91,7 @@ where
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             Self::Io(io) => Pin::new(io).poll_flush(cx),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => Pin::new(io).poll_flush(cx),
         }
     }
@@ -99,7 +99,7 @@ where
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         match &mut *self {
             Self::Io(io) => Pin::new(io).poll_shutdown(cx),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => Pin::new(io).poll_shutdown(cx),
         }
     }
@@ -111,7 +111,7 @@ where
     ) -> Poll<Result<usize, io::Error>> {
         match &mut *self {
             Self::Io(io) => Pin::new(io).poll_write_vectored(cx, bufs),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => Pin::new(io).poll_write_vectored(cx, bufs),
         }
     }
@@ -119,7 +119,7 @@ where
     fn is_write_vectored(&self) -> bool {
         match self {
             Self::Io(io) => io.is_write_vectored(),
-            #[cfg(feature = "tls")]
+            #[cfg(feature = "_tls-any")]
             Self::TlsIo(io) => io.is_write_vectored(),
         }
     }
@@ -4,7 +4,7 @@ pub(crate) use self::io::ServerIo;
 mod recover_error;
 pub(crate) use self::recover_error::RecoverError;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "_tls-any")]
 mod tls;
-#[cfg(feature = "tls")]
-pub(crate) use self::tls::TlsAcceptor;
+#[cfg(feature = "_tls-any")]
+pub(crate) use self::tls::{ClientAuth, Sessions, TlsAcceptor};
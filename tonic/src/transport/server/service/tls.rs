@@ -1,64 +1,294 @@
-use std::{fmt, io::Cursor, sync::Arc};
+use std::{collections::HashMap, fmt, io::Cursor, sync::Arc};
 
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_rustls::{
-    rustls::{server::WebPkiClientVerifier, RootCertStore, ServerConfig},
+    rustls::{
+        self,
+        client::danger::HandshakeSignatureValid,
+        pki_types::{CertificateDer, UnixTime},
+        server::{
+            danger::{ClientCertVerified, ClientCertVerifier},
+            ClientHello, ProducesTickets, ResolvesServerCert, StoresServerSessions,
+            WebPkiClientVerifier,
+        },
+        sign::CertifiedKey,
+        DigitallySignedStruct, DistinguishedName, RootCertStore, ServerConfig, SignatureScheme,
+    },
     server::TlsStream,
     TlsAcceptor as RustlsAcceptor,
 };
 
 use crate::transport::{
+    credentials::{ResolvedConfig, SharedResolver},
     server::Connected,
-    service::tls::{add_certs_from_pem, load_identity, ALPN_H2},
-    Certificate, Identity,
+    service::tls::{
+        add_certs_from_pem, alpn_protocols, crypto_provider, load_crls, load_identity,
+        protocol_versions, verify_chain, TlsError,
+    },
+    tls::{ProtocolPolicy, SharedVerifier},
+    Certificate, Identity, RevocationConfig,
 };
 
 #[derive(Clone)]
 pub(crate) struct TlsAcceptor {
-    inner: Arc<ServerConfig>,
+    inner: Arc<Inner>,
+}
+
+enum Inner {
+    Static(Arc<ServerConfig>),
+    Resolved(Box<Resolved>),
+}
+
+/// What a configuration is built from whenever the credentials change.
+struct Resolved {
+    config: ResolvedConfig<ServerConfig>,
+    sni_identities: HashMap<String, Identity>,
+    client_auth: ClientAuth,
+    sessions: Sessions,
+    policy: ProtocolPolicy,
+}
+
+/// Where sessions are kept for clients to resume them, shared by the
+/// configurations built from resolved credentials.
+#[derive(Clone)]
+pub(crate) struct Sessions {
+    pub(crate) storage: Arc<dyn StoresServerSessions>,
+    pub(crate) ticketer: Option<Arc<dyn ProducesTickets>>,
+}
+
+/// How client certificates are verified.
+#[derive(Clone)]
+pub(crate) struct ClientAuth {
+    pub(crate) ca_root: Option<Certificate>,
+    pub(crate) optional: bool,
+    pub(crate) revocation: Option<RevocationConfig>,
+    pub(crate) verifier: Option<SharedVerifier>,
 }
 
 impl TlsAcceptor {
     pub(crate) fn new(
-        identity: Identity,
-        client_ca_root: Option<Certificate>,
-        client_auth_optional: bool,
+        identity: Option<Identity>,
+        sni_identities: &HashMap<String, Identity>,
+        client_auth: ClientAuth,
+        sessions: Sessions,
+        policy: &ProtocolPolicy,
     ) -> Result<Self, crate::Error> {
-        let builder = ServerConfig::builder();
-
-        let builder = match client_ca_root {
-            None => builder.with_no_client_auth(),
-            Some(cert) => {
-                let mut roots = RootCertStore::empty();
-                add_certs_from_pem(&mut Cursor::new(cert), &mut roots)?;
-                let verifier = if client_auth_optional {
-                    WebPkiClientVerifier::builder(roots.into()).allow_unauthenticated()
-                } else {
-                    WebPkiClientVerifier::builder(roots.into())
-                }
-                .build()?;
-                builder.with_client_cert_verifier(verifier)
-            }
-        };
-
-        let (cert, key) = load_identity(identity)?;
-        let mut config = builder.with_single_cert(cert, key)?;
-
-        config.alpn_protocols.push(ALPN_H2.into());
+        let config = server_config(identity, sni_identities, client_auth, &sessions, policy)?;
         Ok(Self {
-            inner: Arc::new(config),
+            inner: Arc::new(Inner::Static(Arc::new(config))),
         })
     }
 
+    pub(crate) fn with_resolver(
+        resolver: SharedResolver,
+        sni_identities: HashMap<String, Identity>,
+        client_auth: ClientAuth,
+        sessions: Sessions,
+        policy: ProtocolPolicy,
+    ) -> Result<Self, crate::Error> {
+        let acceptor = Self {
+            inner: Arc::new(Inner::Resolved(Box::new(Resolved {
+                config: ResolvedConfig::new(resolver),
+                sni_identities,
+                client_auth,
+                sessions,
+                policy,
+            }))),
+        };
+        // Fail early if the first credentials are invalid.
+        acceptor.config()?;
+        Ok(acceptor)
+    }
+
+    fn config(&self) -> Result<Arc<ServerConfig>, crate::Error> {
+        match &*self.inner {
+            Inner::Static(config) => Ok(config.clone()),
+            Inner::Resolved(resolved) => {
+                let Resolved {
+                    config,
+                    sni_identities,
+                    client_auth,
+                    sessions,
+                    policy,
+                } = &**resolved;
+                config.get(|credentials| {
+                    let client_auth = ClientAuth {
+                        ca_root: credentials
+                            .ca_certificate
+                            .clone()
+                            .or_else(|| client_auth.ca_root.clone()),
+                        ..client_auth.clone()
+                    };
+                    server_config(
+                        Some(credentials.identity.clone()),
+                        sni_identities,
+                        client_auth,
+                        sessions,
+                        policy,
+                    )
+                })
+            }
+        }
+    }
+
     pub(crate) async fn accept<IO>(&self, io: IO) -> Result<TlsStream<IO>, crate::Error>
     where
         IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
     {
-        let acceptor = RustlsAcceptor::from(self.inner.clone());
+        let acceptor = RustlsAcceptor::from(self.config()?);
         acceptor.accept(io).await.map_err(Into::into)
     }
 }
 
+/// Builds the configuration presenting the identity registered for the
+/// server name the client asked for, or `identity` otherwise.
+fn server_config(
+    identity: Option<Identity>,
+    sni_identities: &HashMap<String, Identity>,
+    client_auth: ClientAuth,
+    sessions: &Sessions,
+    policy: &ProtocolPolicy,
+) -> Result<ServerConfig, crate::Error> {
+    let provider = crypto_provider(policy)?;
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&protocol_versions(policy))?;
+
+    let builder = match client_auth.ca_root {
+        None => builder.with_no_client_auth(),
+        Some(cert) => {
+            let mut roots = RootCertStore::empty();
+            add_certs_from_pem(&mut Cursor::new(cert), &mut roots)?;
+            let mut verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider);
+            if client_auth.optional {
+                verifier = verifier.allow_unauthenticated();
+            }
+            if let Some(revocation) = &client_auth.revocation {
+                verifier = verifier.with_crls(load_crls(revocation)?);
+                if revocation.end_entity_only {
+                    verifier = verifier.only_check_end_entity_revocation();
+                }
+                if revocation.allow_unknown_status {
+                    verifier = verifier.allow_unknown_revocation_status();
+                }
+            }
+            let verifier = verifier.build()?;
+            match client_auth.verifier {
+                Some(custom) => builder.with_client_cert_verifier(Arc::new(CustomClientVerifier {
+                    inner: verifier,
+                    custom,
+                })),
+                None => builder.with_client_cert_verifier(verifier),
+            }
+        }
+    };
+
+    let mut config = if sni_identities.is_empty() {
+        let (cert, key) = load_identity(identity.ok_or(TlsError::MissingIdentity)?)?;
+        builder.with_single_cert(cert, key)?
+    } else {
+        let provider = builder.crypto_provider().clone();
+        let certified_key = |identity: Identity| -> Result<Arc<CertifiedKey>, crate::Error> {
+            let (cert, key) = load_identity(identity)?;
+            let key = provider.key_provider.load_private_key(key)?;
+            Ok(Arc::new(CertifiedKey::new(cert, key)))
+        };
+
+        let resolver = SniResolver {
+            by_name: sni_identities
+                .iter()
+                .map(|(name, identity)| {
+                    Ok((name.to_ascii_lowercase(), certified_key(identity.clone())?))
+                })
+                .collect::<Result<_, crate::Error>>()?,
+            default: identity.map(certified_key).transpose()?,
+        };
+        builder.with_cert_resolver(Arc::new(resolver))
+    };
+
+    config.alpn_protocols = alpn_protocols(policy);
+    config.session_storage = sessions.storage.clone();
+    if let Some(ticketer) = &sessions.ticketer {
+        config.ticketer = ticketer.clone();
+    }
+    Ok(config)
+}
+
+/// Picks the certificate to present by the server name the client asked for.
+#[derive(Debug)]
+struct SniResolver {
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        client_hello
+            .server_name()
+            .and_then(|name| self.by_name.get(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+/// Runs a [`CertificateVerifier`] on client certificates once they are
+/// trusted.
+///
+/// [`CertificateVerifier`]: crate::transport::CertificateVerifier
+#[derive(Debug)]
+struct CustomClientVerifier {
+    inner: Arc<dyn ClientCertVerifier>,
+    custom: SharedVerifier,
+}
+
+impl ClientCertVerifier for CustomClientVerifier {
+    fn offer_client_auth(&self) -> bool {
+        self.inner.offer_client_auth()
+    }
+
+    fn client_auth_mandatory(&self) -> bool {
+        self.inner.client_auth_mandatory()
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        self.inner.root_hint_subjects()
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .verify_client_cert(end_entity, intermediates, now)?;
+        verify_chain(&self.custom, end_entity, intermediates)?;
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
 impl fmt::Debug for TlsAcceptor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TlsAcceptor").finish()
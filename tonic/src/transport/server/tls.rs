@@ -1,14 +1,33 @@
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
-use super::service::TlsAcceptor;
-use crate::transport::tls::{Certificate, Identity};
+use super::service::{ClientAuth, Sessions, TlsAcceptor};
+use crate::transport::{
+    credentials::SharedResolver,
+    service::tls::ticketer,
+    tls::{Certificate, Identity, ProtocolPolicy, RevocationConfig, SharedVerifier},
+    CertificateVerifier, CipherSuite, CredentialsResolver, TlsVersion,
+};
+use std::sync::Arc;
+use tokio_rustls::rustls::{
+    crypto::CryptoProvider,
+    server::{NoServerSessionStorage, ServerSessionMemoryCache, StoresServerSessions},
+};
+
+const DEFAULT_SESSION_CACHE_SIZE: usize = 256;
 
 /// Configures TLS settings for servers.
 #[derive(Clone, Default)]
 pub struct ServerTlsConfig {
     identity: Option<Identity>,
+    sni_identities: HashMap<String, Identity>,
+    resolver: Option<SharedResolver>,
     client_ca_root: Option<Certificate>,
     client_auth_optional: bool,
+    revocation: Option<RevocationConfig>,
+    verifier: Option<SharedVerifier>,
+    session_cache_size: Option<usize>,
+    session_tickets: bool,
+    policy: ProtocolPolicy,
 }
 
 impl fmt::Debug for ServerTlsConfig {
@@ -22,12 +41,22 @@ impl ServerTlsConfig {
     pub fn new() -> Self {
         ServerTlsConfig {
             identity: None,
+            sni_identities: HashMap::new(),
+            resolver: None,
             client_ca_root: None,
             client_auth_optional: false,
+            revocation: None,
+            verifier: None,
+            session_cache_size: None,
+            session_tickets: false,
+            policy: ProtocolPolicy::default(),
         }
     }
 
     /// Sets the [`Identity`] of the server.
+    ///
+    /// When [`ServerTlsConfig::sni_identity`] is used, this is the identity
+    /// presented to clients asking for any other server name, or none.
     pub fn identity(self, identity: Identity) -> Self {
         ServerTlsConfig {
             identity: Some(identity),
@@ -35,6 +64,56 @@ impl ServerTlsConfig {
         }
     }
 
+    /// Sets the [`Identity`] presented to clients asking for `server_name`
+    /// through SNI.
+    ///
+    /// Server names are matched ignoring ASCII case. Clients asking for a
+    /// server name without an identity get the one set with
+    /// [`ServerTlsConfig::identity`], and fail the handshake if there is none.
+    pub fn sni_identity(self, server_name: impl Into<String>, identity: Identity) -> Self {
+        let mut sni_identities = self.sni_identities;
+        sni_identities.insert(server_name.into(), identity);
+        ServerTlsConfig {
+            sni_identities,
+            ..self
+        }
+    }
+
+    /// Sets the [`Identity`] presented to clients asking for each server name
+    /// through SNI. See [`ServerTlsConfig::sni_identity`].
+    pub fn sni_identities(
+        self,
+        identities: impl IntoIterator<Item = (impl Into<String>, Identity)>,
+    ) -> Self {
+        let mut sni_identities = self.sni_identities;
+        sni_identities.extend(
+            identities
+                .into_iter()
+                .map(|(server_name, identity)| (server_name.into(), identity)),
+        );
+        ServerTlsConfig {
+            sni_identities,
+            ..self
+        }
+    }
+
+    /// Sets a [`CredentialsResolver`] providing the identity of the server,
+    /// and optionally the certificate against which to validate client TLS
+    /// certificates, for each new connection.
+    ///
+    /// This lets certificates be rotated without restarting the server, for
+    /// example with [`FileCredentials`]. It takes precedence over
+    /// [`ServerTlsConfig::identity`], and is used as the identity presented to
+    /// clients asking for a server name without an SNI identity.
+    ///
+    /// [`FileCredentials`]: crate::transport::FileCredentials
+    pub fn credentials_resolver(self, resolver: impl CredentialsResolver) -> Self {
+        ServerTlsConfig {
+            resolver: Some(SharedResolver(Arc::new(resolver))),
+            ..self
+        }
+    }
+
     /// Sets a certificate against which to validate client TLS certificates.
     pub fn client_ca_root(self, cert: Certificate) -> Self {
         ServerTlsConfig {
@@ -56,11 +135,474 @@ impl ServerTlsConfig {
         }
     }
 
+    /// Sets how client certificates are checked for revocation.
+    ///
+    /// This option has effect only if CA certificate is set.
+    ///
+    /// # Default
+    /// By default, revocation is not checked.
+    pub fn revocation(self, revocation: RevocationConfig) -> Self {
+        ServerTlsConfig {
+            revocation: Some(revocation),
+            ..self
+        }
+    }
+
+    /// Sets a [`CertificateVerifier`] deciding whether to accept client
+    /// certificates, once they have been verified against the CA certificate.
+    ///
+    /// This option has effect only if CA certificate is set.
+    pub fn client_cert_verifier(self, verifier: impl CertificateVerifier) -> Self {
+        ServerTlsConfig {
+            verifier: Some(SharedVerifier(Arc::new(verifier))),
+            ..self
+        }
+    }
+
+    /// Sets how many sessions are kept for clients to resume them, rather
+    /// than perform a full handshake. Zero disables resumption, unless
+    /// session tickets are enabled.
+    ///
+    /// # Default
+    /// By default, up to 256 sessions are kept.
+    pub fn session_cache_size(self, size: usize) -> Self {
+        ServerTlsConfig {
+            session_cache_size: Some(size),
+            ..self
+        }
+    }
+
+    /// Sets whether session tickets are issued, which let clients resume
+    /// sessions without the server keeping them. Ticket keys are rotated
+    /// every six hours.
+    ///
+    /// Tickets are encrypted with `ring` or `aws-lc-rs`, the same as the
+    /// crypto provider of the server.
+    ///
+    /// # Default
+    /// By default, session tickets are disabled.
+    pub fn session_tickets(self, enabled: bool) -> Self {
+        ServerTlsConfig {
+            session_tickets: enabled,
+            ..self
+        }
+    }
+
+    /// Sets the TLS protocol versions that may be negotiated.
+    ///
+    /// # Default
+    /// By default, TLS 1.2 and TLS 1.3 are allowed.
+    pub fn tls_versions(self, versions: impl IntoIterator<Item = TlsVersion>) -> Self {
+        let mut policy = self.policy;
+        policy.versions = Some(versions.into_iter().collect());
+        ServerTlsConfig { policy, ..self }
+    }
+
+    /// Sets the cipher suites that may be negotiated, among those supported.
+    ///
+    /// # Default
+    /// By default, every supported cipher suite is allowed.
+    pub fn cipher_suites(self, suites: impl IntoIterator<Item = CipherSuite>) -> Self {
+        let mut policy = self.policy;
+        policy.cipher_suites = Some(suites.into_iter().collect());
+        ServerTlsConfig { policy, ..self }
+    }
+
+    /// Sets the protocols offered to clients through ALPN, in order of
+    /// preference.
+    ///
+    /// # Default
+    /// By default, only `h2` is offered.
+    pub fn alpn_protocols(self, protocols: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        let mut policy = self.policy;
+        policy.alpn_protocols = Some(protocols.into_iter().map(Into::into).collect());
+        ServerTlsConfig { policy, ..self }
+    }
+
+    /// Sets the [`CryptoProvider`] the TLS server is built with.
+    ///
+    /// # Default
+    /// By default, the process-wide default provider is used if one is
+    /// installed, or else the one enabled by the `tls-ring` or `tls-aws-lc`
+    /// feature, `ring` first.
+    pub fn crypto_provider(self, provider: Arc<CryptoProvider>) -> Self {
+        let mut policy = self.policy;
+        policy.provider = Some(provider);
+        ServerTlsConfig { policy, ..self }
+    }
+
     pub(crate) fn tls_acceptor(&self) -> Result<TlsAcceptor, crate::Error> {
+        let client_auth = ClientAuth {
+            ca_root: self.client_ca_root.clone(),
+            optional: self.client_auth_optional,
+            revocation: self.revocation.clone(),
+            verifier: self.verifier.clone(),
+        };
+
+        let storage: Arc<dyn StoresServerSessions> = match self
+            .session_cache_size
+            .unwrap_or(DEFAULT_SESSION_CACHE_SIZE)
+        {
+            0 => Arc::new(NoServerSessionStorage {}),
+            size => ServerSessionMemoryCache::new(size),
+        };
+        let sessions = Sessions {
+            storage,
+            ticketer: if self.session_tickets {
+                Some(ticketer(&self.policy)?)
+            } else {
+                None
+            },
+        };
+
+        if let Some(resolver) = &self.resolver {
+            return TlsAcceptor::with_resolver(
+                resolver.clone(),
+                self.sni_identities.clone(),
+                client_auth,
+                sessions,
+                self.policy.clone(),
+            );
+        }
+
         TlsAcceptor::new(
-            self.identity.clone().unwrap(),
-            self.client_ca_root.clone(),
-            self.client_auth_optional,
+            self.identity.clone(),
+            &self.sni_identities,
+            client_auth,
+            sessions,
+            &self.policy,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "channel"))]
+mod tests {
+    use super::*;
+    use crate::transport::server::TlsConnectInfo;
+    use crate::transport::{
+        channel::service::TlsConnector,
+        san_uris,
+        service::tls::test_util::{ca, handshake, identity, read},
+        CertificateDer, CertificateRevocationList, ClientSessionCache, ClientTlsConfig,
+        FileCredentials, RevocationConfig,
+    };
+    use http::Uri;
+    use std::time::Duration;
+
+    fn connector(client: ClientTlsConfig) -> TlsConnector {
+        client
+            .into_tls_connector(&Uri::from_static("https://localhost"))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_rotated_credentials_are_ignored() {
+        let dir = std::env::temp_dir().join(format!("tonic-rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("tls.crt"), dir.join("tls.key"));
+        std::fs::write(&cert, read("server.pem")).unwrap();
+        std::fs::write(&key, read("server.key")).unwrap();
+
+        let watched = FileCredentials::new(&cert, &key)
+            .poll_interval(Duration::from_millis(10))
+            .watch()
+            .unwrap();
+        let acceptor = ServerTlsConfig::new()
+            .credentials_resolver(watched.clone())
+            .tls_acceptor()
+            .unwrap();
+        let connector = connector(ClientTlsConfig::new().ca_certificate(ca()));
+        handshake(&acceptor, &connector).await.unwrap();
+
+        // Rotate to a key that does not match the certificate, rewriting it
+        // until its modification time changes, whatever its resolution.
+        let modified = std::fs::metadata(&key).unwrap().modified().unwrap();
+        while std::fs::metadata(&key).unwrap().modified().unwrap() == modified {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            std::fs::write(&key, read("foo.key")).unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while watched.resolve().identity.key != read("foo.key") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("credentials were not reloaded");
+
+        handshake(&acceptor, &connector).await.unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_cert_verifier_runs_on_trusted_chains() {
+        let server = ServerTlsConfig::new()
+            .identity(identity("server"))
+            .client_ca_root(ca());
+        let client = ClientTlsConfig::new().ca_certificate(ca());
+
+        let spiffe_id = |expected: &'static str| {
+            move |chain: &[CertificateDer<'_>]| -> Result<(), crate::Error> {
+                if san_uris(&chain[0]).iter().any(|uri| uri == expected) {
+                    Ok(())
+                } else {
+                    Err("unexpected SPIFFE ID".into())
+                }
+            }
+        };
+
+        let acceptor = server
+            .clone()
+            .client_cert_verifier(spiffe_id("spiffe://example.org/client"))
+            .tls_acceptor()
+            .unwrap();
+        handshake(
+            &acceptor,
+            &connector(client.clone().identity(identity("client"))),
+        )
+        .await
+        .unwrap();
+        // The chain must still be trusted.
+        assert!(handshake(
+            &acceptor,
+            &connector(client.clone().identity(identity("server")))
+        )
+        .await
+        .is_err());
+
+        let acceptor = server
+            .client_cert_verifier(spiffe_id("spiffe://example.org/other"))
+            .tls_acceptor()
+            .unwrap();
+        assert!(
+            handshake(&acceptor, &connector(client.identity(identity("client"))))
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(any(feature = "tls-ring", feature = "tls-aws-lc"))]
+    #[tokio::test]
+    async fn handshake_with_each_provider() {
+        let providers = [
+            #[cfg(feature = "tls-ring")]
+            tokio_rustls::rustls::crypto::ring::default_provider(),
+            #[cfg(feature = "tls-aws-lc")]
+            tokio_rustls::rustls::crypto::aws_lc_rs::default_provider(),
+        ];
+
+        for provider in providers.map(Arc::new) {
+            // Only tickets let sessions resume.
+            let acceptor = ServerTlsConfig::new()
+                .identity(identity("server"))
+                .session_cache_size(0)
+                .session_tickets(true)
+                .crypto_provider(provider.clone())
+                .tls_acceptor()
+                .unwrap();
+            let connector = connector(
+                ClientTlsConfig::new()
+                    .ca_certificate(ca())
+                    .crypto_provider(provider),
+            );
+
+            assert!(!handshake(&acceptor, &connector)
+                .await
+                .unwrap()
+                .session_resumed());
+            assert!(handshake(&acceptor, &connector)
+                .await
+                .unwrap()
+                .session_resumed());
+        }
+    }
+
+    #[tokio::test]
+    async fn shared_session_cache_resumes_sessions() {
+        // Sessions kept by the server, then tickets kept by the client.
+        let servers = [
+            ServerTlsConfig::new().session_cache_size(16),
+            ServerTlsConfig::new()
+                .session_cache_size(0)
+                .session_tickets(true),
+        ];
+
+        for server in servers {
+            let acceptor = server.identity(identity("server")).tls_acceptor().unwrap();
+            let client = ClientTlsConfig::new().ca_certificate(ca());
+
+            // Channels with their own caches do not share sessions.
+            let first = connector(client.clone());
+            let second = connector(client.clone());
+            assert!(!handshake(&acceptor, &first)
+                .await
+                .unwrap()
+                .session_resumed());
+            assert!(!handshake(&acceptor, &second)
+                .await
+                .unwrap()
+                .session_resumed());
+
+            let cache = ClientSessionCache::default();
+            let first = connector(client.clone().session_cache(cache.clone()));
+            let second = connector(client.session_cache(cache));
+            assert!(!handshake(&acceptor, &first)
+                .await
+                .unwrap()
+                .session_resumed());
+            assert!(handshake(&acceptor, &second)
+                .await
+                .unwrap()
+                .session_resumed());
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_not_resumed_when_disabled() {
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("server"))
+            .session_cache_size(0)
+            .tls_acceptor()
+            .unwrap();
+        let connector = connector(ClientTlsConfig::new().ca_certificate(ca()));
+
+        for _ in 0..2 {
+            assert!(!handshake(&acceptor, &connector)
+                .await
+                .unwrap()
+                .session_resumed());
+        }
+    }
+
+    /// The revocation list issued by the test CA `ca`.
+    fn crl(ca: &str) -> CertificateRevocationList {
+        CertificateRevocationList::from_pem(read(&format!("{ca}.crl.pem")))
+    }
+
+    #[tokio::test]
+    async fn revoked_client_certs_are_rejected() {
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("server"))
+            .client_ca_root(ca())
+            .revocation(RevocationConfig::new().crl(crl("ca")))
+            .tls_acceptor()
+            .unwrap();
+        let client = ClientTlsConfig::new().ca_certificate(ca());
+
+        handshake(
+            &acceptor,
+            &connector(client.clone().identity(identity("client"))),
+        )
+        .await
+        .unwrap();
+        assert!(handshake(
+            &acceptor,
+            &connector(client.identity(identity("client-revoked")))
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn revoked_server_certs_are_rejected() {
+        let client = ClientTlsConfig::new()
+            .ca_certificate(ca())
+            .revocation(RevocationConfig::new().crl(crl("ca")));
+
+        for (server, revoked) in [("server", false), ("server-revoked", true)] {
+            let acceptor = ServerTlsConfig::new()
+                .identity(identity(server))
+                .tls_acceptor()
+                .unwrap();
+            let result = handshake(&acceptor, &connector(client.clone())).await;
+            assert_eq!(result.is_err(), revoked, "{server}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_revocation_status() {
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("server"))
+            .tls_acceptor()
+            .unwrap();
+        // No list was issued by the CA of the server.
+        let revocation = RevocationConfig::new().crl(crl("other-ca"));
+        let client = ClientTlsConfig::new().ca_certificate(ca());
+
+        let rejecting = connector(client.clone().revocation(revocation.clone()));
+        assert!(handshake(&acceptor, &rejecting).await.is_err());
+
+        let allowing = connector(client.revocation(revocation.allow_unknown_status(true)));
+        handshake(&acceptor, &allowing).await.unwrap();
+    }
+
+    /// The DER encoded certificate of the test identity `name`.
+    fn der(name: &str) -> Vec<u8> {
+        let pem = read(&format!("{name}.pem"));
+        let cert = rustls_pemfile::certs(&mut &pem[..])
+            .next()
+            .unwrap()
+            .unwrap();
+        cert.to_vec()
+    }
+
+    /// Connects a client asking for `domain` through SNI, returning the
+    /// certificate the server presented and what the server knows of the
+    /// connection.
+    async fn presented(acceptor: &TlsAcceptor, domain: &str) -> (Vec<u8>, TlsConnectInfo<()>) {
+        let presented = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = presented.clone();
+        let client = ClientTlsConfig::new()
+            .ca_certificate(ca())
+            .domain_name(domain)
+            .certificate_verifier(
+                move |chain: &[CertificateDer<'_>]| -> Result<(), crate::Error> {
+                    *record.lock().unwrap() = chain[0].to_vec();
+                    Ok(())
+                },
+            );
+
+        let info = handshake(acceptor, &connector(client)).await.unwrap();
+        let cert = presented.lock().unwrap().clone();
+        (cert, info)
+    }
+
+    #[tokio::test]
+    async fn sni_identities() {
+        let acceptor = ServerTlsConfig::new()
+            .identity(identity("server"))
+            .sni_identity("foo.test", identity("foo"))
+            .sni_identity("BAR.test", identity("bar"))
+            .tls_acceptor()
+            .unwrap();
+
+        let (cert, info) = presented(&acceptor, "foo.test").await;
+        assert_eq!(cert, der("foo"));
+        assert_eq!(info.server_name(), Some("foo.test"));
+
+        let (cert, info) = presented(&acceptor, "bar.test").await;
+        assert_eq!(cert, der("bar"));
+        assert_eq!(info.server_name(), Some("bar.test"));
+
+        let (cert, info) = presented(&acceptor, "localhost").await;
+        assert_eq!(cert, der("server"));
+        assert_eq!(info.server_name(), Some("localhost"));
+    }
+
+    #[tokio::test]
+    async fn sni_identities_without_default() {
+        let acceptor = ServerTlsConfig::new()
+            .sni_identity("foo.test", identity("foo"))
+            .tls_acceptor()
+            .unwrap();
+
+        let client = ClientTlsConfig::new().ca_certificate(ca());
+        handshake(
+            &acceptor,
+            &connector(client.clone().domain_name("foo.test")),
         )
+        .await
+        .unwrap();
+        assert!(handshake(&acceptor, &connector(client)).await.is_err());
     }
 }
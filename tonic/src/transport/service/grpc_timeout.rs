@@ -104,7 +104,7 @@ const SECONDS_IN_MINUTE: u64 = 60;
 /// the value we attempted to parse.
 ///
 /// Follows the [gRPC over HTTP2 spec](https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md).
-fn try_parse_grpc_timeout(
+pub(crate) fn try_parse_grpc_timeout(
     headers: &HeaderMap<HeaderValue>,
 ) -> Result<Option<Duration>, &HeaderValue> {
     match headers.get(GRPC_TIMEOUT_HEADER) {
@@ -0,0 +1,188 @@
+use crate::body::{boxed, BoxBody};
+use bytes::Bytes;
+#[cfg(feature = "server")]
+use http::Request;
+use http::Response;
+use http_body::{Body, Frame, SizeHint};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+#[cfg(feature = "server")]
+use tower_service::Service;
+
+/// Tracks the calls in flight on a connection, to find out when it has had
+/// none for a while.
+///
+/// A call is in flight from the time it is made until the body of its
+/// response is dropped.
+#[derive(Clone)]
+pub(crate) struct IdleTracker {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    in_flight: usize,
+    last_active: Instant,
+}
+
+impl IdleTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                in_flight: 0,
+                last_active: Instant::now(),
+            })),
+        }
+    }
+
+    fn start(&self) -> ActiveGuard {
+        self.state.lock().unwrap().in_flight += 1;
+        ActiveGuard(self.clone())
+    }
+
+    /// Track the call answered by `fut`.
+    pub(crate) fn track<F, E>(
+        &self,
+        fut: F,
+    ) -> impl Future<Output = Result<Response<BoxBody>, E>> + Send + 'static
+    where
+        F: Future<Output = Result<Response<BoxBody>, E>> + Send + 'static,
+        E: 'static,
+    {
+        let guard = self.start();
+
+        async move {
+            let res = fut.await?;
+            Ok(res.map(|body| {
+                boxed(TrackedBody {
+                    inner: body,
+                    _guard: guard,
+                })
+            }))
+        }
+    }
+
+    /// The number of calls in flight.
+    #[cfg(feature = "server")]
+    pub(crate) fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Resolves once no call has been in flight for `timeout`.
+    pub(crate) async fn idle(&self, timeout: Duration) {
+        loop {
+            let deadline = {
+                let state = self.state.lock().unwrap();
+                if state.in_flight == 0 {
+                    state.last_active + timeout
+                } else {
+                    Instant::now() + timeout
+                }
+            };
+
+            if deadline <= Instant::now() {
+                return;
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
+
+/// Marks a call as in flight until dropped.
+struct ActiveGuard(IdleTracker);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.in_flight -= 1;
+        state.last_active = Instant::now();
+    }
+}
+
+/// Response body keeping its call in flight.
+struct TrackedBody {
+    inner: BoxBody,
+    _guard: ActiveGuard,
+}
+
+impl Body for TrackedBody {
+    type Data = Bytes;
+    type Error = crate::Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Tracks the calls made to `S` with an [`IdleTracker`].
+#[cfg(feature = "server")]
+#[derive(Clone)]
+pub(crate) struct TrackIdle<S> {
+    inner: S,
+    tracker: IdleTracker,
+}
+
+#[cfg(feature = "server")]
+impl<S> TrackIdle<S> {
+    pub(crate) fn new(inner: S, tracker: IdleTracker) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+#[cfg(feature = "server")]
+impl<S, ReqBody> Service<Request<ReqBody>> for TrackIdle<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+    S::Future: Send + 'static,
+    S::Error: 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<BoxBody>, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        Box::pin(self.tracker.track(self.inner.call(req)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_after_last_call() {
+        let tracker = IdleTracker::new();
+        let timeout = Duration::from_secs(10);
+        let start = Instant::now();
+
+        let res = tracker
+            .track(async { Ok::<_, ()>(Response::new(crate::body::empty_body())) })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        drop(res);
+
+        tracker.idle(timeout).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(40));
+    }
+}
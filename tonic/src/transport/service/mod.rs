@@ -1,5 +1,23 @@
 pub(crate) mod grpc_timeout;
-#[cfg(feature = "tls")]
+pub(crate) mod idle;
+#[cfg(feature = "_tls-any")]
 pub(crate) mod tls;
 
 pub(crate) use self::grpc_timeout::GrpcTimeout;
+pub(crate) use self::idle::IdleTracker;
+#[cfg(feature = "server")]
+pub(crate) use self::idle::TrackIdle;
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A uniformly distributed number in `[0, 1]`.
+pub(crate) fn random() -> f64 {
+    // `RandomState` is randomly seeded for every instance, which is plenty for
+    // spreading out retries, balancing calls and jittering timers.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish() as f64 / u64::MAX as f64
+}
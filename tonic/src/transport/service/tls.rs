@@ -1,11 +1,24 @@
-use std::{fmt, io::Cursor};
+use std::{fmt, io::Cursor, iter, sync::Arc};
 
+#[cfg(feature = "server")]
+use tokio_rustls::rustls::server::ProducesTickets;
 use tokio_rustls::rustls::{
-    pki_types::{CertificateDer, PrivateKeyDer},
-    RootCertStore,
+    self,
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, CertificateRevocationListDer, PrivateKeyDer},
+    version::{TLS12, TLS13},
+    CertificateError, OtherError, RootCertStore, SupportedProtocolVersion,
 };
 
-use crate::transport::Identity;
+#[cfg(all(feature = "tls-aws-lc", not(feature = "tls-ring")))]
+use tokio_rustls::rustls::crypto::aws_lc_rs as provider;
+#[cfg(feature = "tls-ring")]
+use tokio_rustls::rustls::crypto::ring as provider;
+
+use crate::transport::{
+    tls::{ProtocolPolicy, SharedVerifier},
+    Identity, RevocationConfig, TlsVersion,
+};
 
 /// h2 alpn in plain format for rustls.
 pub(crate) const ALPN_H2: &[u8] = b"h2";
@@ -14,7 +27,14 @@ pub(crate) const ALPN_H2: &[u8] = b"h2";
 pub(crate) enum TlsError {
     #[cfg(feature = "channel")]
     H2NotNegotiated,
+    #[cfg(feature = "server")]
+    MissingIdentity,
     CertificateParseError,
+    CrlParseError,
+    NoCipherSuites,
+    NoCryptoProvider,
+    #[cfg(feature = "server")]
+    NoTicketer,
     PrivateKeyParseError,
 }
 
@@ -23,7 +43,22 @@ impl fmt::Display for TlsError {
         match self {
             #[cfg(feature = "channel")]
             TlsError::H2NotNegotiated => write!(f, "HTTP/2 was not negotiated."),
+            #[cfg(feature = "server")]
+            TlsError::MissingIdentity => write!(f, "No server identity was configured."),
             TlsError::CertificateParseError => write!(f, "Error parsing TLS certificate."),
+            TlsError::CrlParseError => {
+                write!(f, "Error parsing certificate revocation list.")
+            }
+            TlsError::NoCipherSuites => write!(f, "None of the cipher suites are supported."),
+            TlsError::NoCryptoProvider => write!(
+                f,
+                "No crypto provider was configured, installed as the default, or enabled."
+            ),
+            #[cfg(feature = "server")]
+            TlsError::NoTicketer => write!(
+                f,
+                "Session tickets need a crypto provider from the tls-ring or tls-aws-lc feature."
+            ),
             TlsError::PrivateKeyParseError => write!(
                 f,
                 "Error parsing TLS private key - no RSA or PKCS8-encoded keys found."
@@ -60,3 +95,287 @@ pub(crate) fn add_certs_from_pem(
 
     Ok(())
 }
+
+/// The crypto provider to build configurations with, restricted to the
+/// cipher suites allowed by `policy`.
+///
+/// This is the provider set on `policy`, or else the process default, or
+/// else the one enabled by the `tls-ring` or `tls-aws-lc` feature, `ring`
+/// first.
+pub(crate) fn crypto_provider(policy: &ProtocolPolicy) -> Result<Arc<CryptoProvider>, TlsError> {
+    let provider = match (&policy.provider, CryptoProvider::get_default()) {
+        (Some(provider), _) | (None, Some(provider)) => provider.clone(),
+        (None, None) => Arc::new(enabled_provider().ok_or(TlsError::NoCryptoProvider)?),
+    };
+
+    let Some(allowed) = &policy.cipher_suites else {
+        return Ok(provider);
+    };
+
+    let mut provider = (*provider).clone();
+    provider
+        .cipher_suites
+        .retain(|suite| allowed.contains(&suite.suite()));
+    if provider.cipher_suites.is_empty() {
+        return Err(TlsError::NoCipherSuites);
+    }
+
+    Ok(Arc::new(provider))
+}
+
+#[cfg(any(feature = "tls-ring", feature = "tls-aws-lc"))]
+fn enabled_provider() -> Option<CryptoProvider> {
+    Some(provider::default_provider())
+}
+
+#[cfg(not(any(feature = "tls-ring", feature = "tls-aws-lc")))]
+fn enabled_provider() -> Option<CryptoProvider> {
+    None
+}
+
+/// Creates a ticketer with the same crypto library as the provider
+/// configurations are built with, see [`crypto_provider`].
+///
+/// Tickets are only encrypted with `ring` or `aws-lc-rs`, whichever the
+/// cipher suites of the provider come from.
+#[cfg(all(feature = "server", any(feature = "tls-ring", feature = "tls-aws-lc")))]
+pub(crate) fn ticketer(policy: &ProtocolPolicy) -> Result<Arc<dyn ProducesTickets>, crate::Error> {
+    use rustls::SupportedCipherSuite;
+
+    /// Whether `a` and `b` are the same implementation of a cipher suite,
+    /// rather than only the same cipher suite.
+    fn same_suite(a: &SupportedCipherSuite, b: &SupportedCipherSuite) -> bool {
+        match (a, b) {
+            (SupportedCipherSuite::Tls12(a), SupportedCipherSuite::Tls12(b)) => {
+                std::ptr::eq(*a, *b)
+            }
+            (SupportedCipherSuite::Tls13(a), SupportedCipherSuite::Tls13(b)) => {
+                std::ptr::eq(*a, *b)
+            }
+            _ => false,
+        }
+    }
+
+    let provider = crypto_provider(policy)?;
+    let uses = |suites: &[SupportedCipherSuite]| {
+        provider
+            .cipher_suites
+            .iter()
+            .any(|suite| suites.iter().any(|known| same_suite(suite, known)))
+    };
+
+    #[cfg(feature = "tls-ring")]
+    if uses(rustls::crypto::ring::ALL_CIPHER_SUITES) {
+        return Ok(rustls::crypto::ring::Ticketer::new()?);
+    }
+    #[cfg(feature = "tls-aws-lc")]
+    if uses(rustls::crypto::aws_lc_rs::ALL_CIPHER_SUITES) {
+        return Ok(rustls::crypto::aws_lc_rs::Ticketer::new()?);
+    }
+
+    Err(TlsError::NoTicketer.into())
+}
+
+#[cfg(all(
+    feature = "server",
+    not(any(feature = "tls-ring", feature = "tls-aws-lc"))
+))]
+pub(crate) fn ticketer(_: &ProtocolPolicy) -> Result<Arc<dyn ProducesTickets>, crate::Error> {
+    Err(TlsError::NoTicketer.into())
+}
+
+pub(crate) fn protocol_versions(policy: &ProtocolPolicy) -> Vec<&'static SupportedProtocolVersion> {
+    match &policy.versions {
+        Some(versions) => versions
+            .iter()
+            .map(|version| match version {
+                TlsVersion::Tls12 => &TLS12,
+                TlsVersion::Tls13 => &TLS13,
+            })
+            .collect(),
+        None => rustls::DEFAULT_VERSIONS.to_vec(),
+    }
+}
+
+pub(crate) fn alpn_protocols(policy: &ProtocolPolicy) -> Vec<Vec<u8>> {
+    match &policy.alpn_protocols {
+        Some(protocols) => protocols.clone(),
+        None => vec![ALPN_H2.into()],
+    }
+}
+
+pub(crate) fn load_crls(
+    revocation: &RevocationConfig,
+) -> Result<Vec<CertificateRevocationListDer<'static>>, TlsError> {
+    let mut crls = Vec::new();
+    for crl in &revocation.crls {
+        if crl.is_pem() {
+            for der in rustls_pemfile::crls(&mut Cursor::new(&crl.data)) {
+                crls.push(der.map_err(|_| TlsError::CrlParseError)?);
+            }
+        } else {
+            crls.push(CertificateRevocationListDer::from(crl.data.clone()));
+        }
+    }
+
+    Ok(crls)
+}
+
+/// Runs a [`CertificateVerifier`] on the chain presented by a peer.
+///
+/// [`CertificateVerifier`]: crate::transport::CertificateVerifier
+pub(crate) fn verify_chain(
+    verifier: &SharedVerifier,
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+) -> Result<(), rustls::Error> {
+    let chain = iter::once(end_entity.clone())
+        .chain(intermediates.iter().cloned())
+        .collect::<Vec<_>>();
+
+    verifier.0.verify(&chain).map_err(|err| {
+        rustls::Error::InvalidCertificate(CertificateError::Other(OtherError(Arc::from(err))))
+    })
+}
+
+/// Certificates and handshakes for tests, between the TLS configurations of
+/// clients and servers over in-memory streams.
+///
+/// The certificates are generated by `testdata/tls/generate.sh`.
+#[cfg(test)]
+pub(crate) mod test_util {
+    #[cfg(all(feature = "channel", feature = "server"))]
+    use crate::transport::{
+        channel::service::TlsConnector,
+        server::{service::TlsAcceptor, Connected, TlsConnectInfo},
+        Certificate, Identity,
+    };
+    #[cfg(all(feature = "channel", feature = "server"))]
+    use hyper_util::rt::TokioIo;
+    #[cfg(all(feature = "channel", feature = "server"))]
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    pub(crate) fn read(file: &str) -> Vec<u8> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tls/");
+        std::fs::read(format!("{path}{file}")).unwrap()
+    }
+
+    #[cfg(all(feature = "channel", feature = "server"))]
+    pub(crate) fn identity(name: &str) -> Identity {
+        Identity::from_pem(read(&format!("{name}.pem")), read(&format!("{name}.key")))
+    }
+
+    #[cfg(all(feature = "channel", feature = "server"))]
+    pub(crate) fn ca() -> Certificate {
+        Certificate::from_pem(read("ca.pem"))
+    }
+
+    /// Connects `connector` to `acceptor`, returning what the server knows
+    /// of the connection.
+    ///
+    /// The client reads until the server closes the connection, so that it
+    /// receives the session tickets sent after the handshake.
+    #[cfg(all(feature = "channel", feature = "server"))]
+    pub(crate) async fn handshake(
+        acceptor: &TlsAcceptor,
+        connector: &TlsConnector,
+    ) -> Result<TlsConnectInfo<()>, crate::Error> {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+
+        let server = async {
+            let mut stream = acceptor.accept(server).await?;
+            let info = stream.connect_info();
+            stream.write_all(b"bye").await?;
+            stream.shutdown().await?;
+            Ok::<_, crate::Error>(info)
+        };
+        let client = async {
+            let mut stream = TokioIo::new(connector.connect(client).await?);
+            stream.read_to_end(&mut Vec::new()).await?;
+            Ok::<_, crate::Error>(())
+        };
+
+        let (server, client) = tokio::join!(server, client);
+        client?;
+        server
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::CertificateRevocationList;
+
+    #[test]
+    fn restrict_cipher_suites() {
+        let policy = ProtocolPolicy {
+            cipher_suites: Some(vec![rustls::CipherSuite::TLS13_AES_256_GCM_SHA384]),
+            ..Default::default()
+        };
+        let suites = crypto_provider(&policy)
+            .unwrap()
+            .cipher_suites
+            .iter()
+            .map(|suite| suite.suite())
+            .collect::<Vec<_>>();
+        assert_eq!(suites, vec![rustls::CipherSuite::TLS13_AES_256_GCM_SHA384]);
+
+        let policy = ProtocolPolicy {
+            cipher_suites: Some(vec![rustls::CipherSuite::TLS_NULL_WITH_NULL_NULL]),
+            ..Default::default()
+        };
+        assert!(crypto_provider(&policy).is_err());
+    }
+
+    #[cfg(any(feature = "tls-ring", feature = "tls-aws-lc"))]
+    #[test]
+    fn explicit_crypto_provider() {
+        let mut provider = enabled_provider().unwrap();
+        provider.cipher_suites.truncate(1);
+        let suite = provider.cipher_suites[0].suite();
+
+        let policy = ProtocolPolicy {
+            provider: Some(Arc::new(provider)),
+            ..Default::default()
+        };
+        let suites = crypto_provider(&policy)
+            .unwrap()
+            .cipher_suites
+            .iter()
+            .map(|suite| suite.suite())
+            .collect::<Vec<_>>();
+        assert_eq!(suites, vec![suite]);
+    }
+
+    #[cfg(all(feature = "server", any(feature = "tls-ring", feature = "tls-aws-lc")))]
+    #[test]
+    fn ticketer_needs_known_provider() {
+        let mut provider = enabled_provider().unwrap();
+        let policy = ProtocolPolicy {
+            provider: Some(Arc::new(provider.clone())),
+            ..Default::default()
+        };
+        assert!(ticketer(&policy).is_ok());
+
+        // Cipher suites implemented elsewhere.
+        provider.cipher_suites.clear();
+        let policy = ProtocolPolicy {
+            provider: Some(Arc::new(provider)),
+            ..Default::default()
+        };
+        assert!(ticketer(&policy).is_err());
+    }
+
+    #[test]
+    fn load_pem_and_der_crls() {
+        let pem = "-----BEGIN X509 CRL-----\nAAEC\n-----END X509 CRL-----\n\
+                   -----BEGIN X509 CRL-----\nAwQF\n-----END X509 CRL-----\n";
+        let revocation = RevocationConfig::new()
+            .crl(CertificateRevocationList::from_pem(pem))
+            .crl(CertificateRevocationList::from_der([6, 7]));
+
+        let crls = load_crls(&revocation).unwrap();
+        let crls: Vec<&[u8]> = crls.iter().map(|crl| crl.as_ref()).collect();
+        assert_eq!(crls, vec![&[0, 1, 2][..], &[3, 4, 5], &[6, 7]]);
+    }
+}
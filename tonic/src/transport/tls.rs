@@ -1,3 +1,8 @@
+use std::{fmt, sync::Arc};
+#[cfg(feature = "server")]
+use tokio_rustls::rustls::ProtocolVersion;
+use tokio_rustls::rustls::{crypto::CryptoProvider, pki_types::CertificateDer, CipherSuite};
+
 /// Represents a X509 certificate.
 #[derive(Debug, Clone)]
 pub struct Certificate {
@@ -58,3 +63,220 @@ impl Identity {
         Self { cert, key }
     }
 }
+
+#[derive(Debug, Clone)]
+enum Encoding {
+    Pem,
+    Der,
+}
+
+/// Represents a X509 certificate revocation list (CRL).
+#[derive(Debug, Clone)]
+pub struct CertificateRevocationList {
+    pub(crate) data: Vec<u8>,
+    encoding: Encoding,
+}
+
+impl CertificateRevocationList {
+    /// Parse PEM encoded certificate revocation lists.
+    ///
+    /// The provided PEM may include several PEM encoded lists.
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> Self {
+        Self {
+            data: pem.as_ref().into(),
+            encoding: Encoding::Pem,
+        }
+    }
+
+    /// Parse a DER encoded certificate revocation list.
+    pub fn from_der(der: impl AsRef<[u8]>) -> Self {
+        Self {
+            data: der.as_ref().into(),
+            encoding: Encoding::Der,
+        }
+    }
+
+    pub(crate) fn is_pem(&self) -> bool {
+        matches!(self.encoding, Encoding::Pem)
+    }
+}
+
+/// Configures how peer certificates are checked for revocation, against
+/// [`CertificateRevocationList`]s.
+///
+/// By default, every certificate of the chain is checked, and certificates
+/// whose status cannot be determined from the lists are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct RevocationConfig {
+    pub(crate) crls: Vec<CertificateRevocationList>,
+    pub(crate) end_entity_only: bool,
+    pub(crate) allow_unknown_status: bool,
+}
+
+impl RevocationConfig {
+    /// Creates a new `RevocationConfig` without any revocation list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a revocation list.
+    pub fn crl(self, crl: CertificateRevocationList) -> Self {
+        let mut crls = self.crls;
+        crls.push(crl);
+        RevocationConfig { crls, ..self }
+    }
+
+    /// Adds several revocation lists.
+    pub fn crls(self, crls: impl IntoIterator<Item = CertificateRevocationList>) -> Self {
+        let mut all = self.crls;
+        all.extend(crls);
+        RevocationConfig { crls: all, ..self }
+    }
+
+    /// Sets whether only the end-entity certificate is checked, rather than
+    /// the whole chain.
+    pub fn only_check_end_entity(self, enabled: bool) -> Self {
+        RevocationConfig {
+            end_entity_only: enabled,
+            ..self
+        }
+    }
+
+    /// Sets whether certificates whose revocation status is unknown, because
+    /// no list was issued by their issuer, are accepted.
+    pub fn allow_unknown_status(self, allow: bool) -> Self {
+        RevocationConfig {
+            allow_unknown_status: allow,
+            ..self
+        }
+    }
+}
+
+/// A version of the TLS protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TlsVersion {
+    /// TLS 1.2
+    Tls12,
+    /// TLS 1.3
+    Tls13,
+}
+
+#[cfg(feature = "server")]
+impl TlsVersion {
+    pub(crate) fn from_rustls(version: ProtocolVersion) -> Option<Self> {
+        match version {
+            ProtocolVersion::TLSv1_2 => Some(TlsVersion::Tls12),
+            ProtocolVersion::TLSv1_3 => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+}
+
+/// The crypto provider, protocol versions, cipher suites and ALPN protocols a
+/// TLS endpoint negotiates with, when they differ from the defaults.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProtocolPolicy {
+    pub(crate) provider: Option<Arc<CryptoProvider>>,
+    pub(crate) versions: Option<Vec<TlsVersion>>,
+    pub(crate) cipher_suites: Option<Vec<CipherSuite>>,
+    pub(crate) alpn_protocols: Option<Vec<Vec<u8>>>,
+}
+
+impl PartialEq for ProtocolPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        let same_provider = match (&self.provider, &other.provider) {
+            (Some(provider), Some(other)) => Arc::ptr_eq(provider, other),
+            (provider, other) => provider.is_none() && other.is_none(),
+        };
+        same_provider
+            && self.versions == other.versions
+            && self.cipher_suites == other.cipher_suites
+            && self.alpn_protocols == other.alpn_protocols
+    }
+}
+
+/// Decides whether to trust the certificate chain presented by a peer, once
+/// it has been verified against the CA certificates.
+///
+/// This lets peers be authorized by custom rules, for example by the SPIFFE
+/// ID found in their URI subject alternative names with [`san_uris`]. On
+/// clients, it replaces the check that the server certificate is valid for
+/// the domain name.
+///
+/// It is implemented for closures:
+///
+/// ```
+/// # use tonic::transport::{san_uris, CertificateDer, ClientTlsConfig};
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// let tls = ClientTlsConfig::new().certificate_verifier(
+///     |chain: &[CertificateDer<'_>]| -> Result<(), Error> {
+///         let uris = san_uris(&chain[0]);
+///         if uris.iter().any(|uri| uri == "spiffe://example.org/backend") {
+///             Ok(())
+///         } else {
+///             Err("unexpected SPIFFE ID".into())
+///         }
+///     },
+/// );
+/// ```
+pub trait CertificateVerifier: Send + Sync + 'static {
+    /// Verifies `chain`, which starts with the end-entity certificate,
+    /// returning an error to reject it.
+    fn verify(&self, chain: &[CertificateDer<'_>]) -> Result<(), crate::Error>;
+}
+
+impl<F> CertificateVerifier for F
+where
+    F: Fn(&[CertificateDer<'_>]) -> Result<(), crate::Error> + Send + Sync + 'static,
+{
+    fn verify(&self, chain: &[CertificateDer<'_>]) -> Result<(), crate::Error> {
+        self(chain)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedVerifier(pub(crate) Arc<dyn CertificateVerifier>);
+
+impl PartialEq for SharedVerifier {
+    fn eq(&self, other: &Self) -> bool {
+        // Only the addresses are compared, as vtables may be duplicated.
+        Arc::as_ptr(&self.0).cast::<()>() == Arc::as_ptr(&other.0).cast::<()>()
+    }
+}
+
+impl fmt::Debug for SharedVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertificateVerifier").finish()
+    }
+}
+
+/// Returns the URIs listed in the subject alternative names of a DER encoded
+/// X509 certificate, such as SPIFFE IDs.
+///
+/// Nothing is returned for certificates that cannot be parsed.
+pub fn san_uris(cert: &CertificateDer<'_>) -> Vec<String> {
+    match webpki::EndEntityCert::try_from(cert) {
+        Ok(cert) => cert.valid_uri_names().map(String::from).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::service::tls::test_util::read;
+
+    #[test]
+    fn reads_uri_sans() {
+        let certs = rustls_pemfile::certs(&mut &read("spiffe.pem")[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(san_uris(&certs[0]), vec!["spiffe://example.org/backend"]);
+    }
+
+    #[test]
+    fn ignores_invalid_certificates() {
+        assert!(san_uris(&CertificateDer::from(&b"not a certificate"[..])).is_empty());
+    }
+}
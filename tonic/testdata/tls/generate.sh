@@ -0,0 +1,67 @@
+#!/usr/bin/env bash
+# Generates the certificates used by the TLS handshake tests.
+set -euo pipefail
+cd "$(dirname "$0")"
+
+DAYS=36500
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+key() {
+    openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out "$1"
+}
+
+ca() {
+    local name=$1
+    key "$work/$name.key"
+    openssl req -x509 -new -key "$work/$name.key" -days $DAYS -subj "/CN=tonic test $name" \
+        -addext "basicConstraints=critical,CA:true" \
+        -addext "keyUsage=critical,keyCertSign,cRLSign" \
+        -out "$name.pem"
+    mkdir -p "$work/$name.db"
+    : >"$work/$name.db/index.txt"
+    echo 01 >"$work/$name.db/crlnumber"
+    cat >"$work/$name.cnf" <<CNF
+[ca]
+default_ca = default
+[default]
+database = $work/$name.db/index.txt
+crlnumber = $work/$name.db/crlnumber
+default_md = sha256
+default_crl_days = $DAYS
+CNF
+}
+
+# leaf <name> <ca> <extended key usage> <subject alternative names>
+leaf() {
+    local name=$1 ca=$2 usage=$3 san=$4
+    key "$name.key"
+    openssl req -new -key "$name.key" -subj "/CN=$name" -out "$work/$name.csr"
+    openssl x509 -req -in "$work/$name.csr" -CA "$ca.pem" -CAkey "$work/$ca.key" \
+        -days $DAYS -set_serial "0x$(openssl rand -hex 8)" -out "$name.pem" \
+        -extfile <(printf 'basicConstraints=critical,CA:false\nkeyUsage=critical,digitalSignature\nextendedKeyUsage=%s\nsubjectAltName=%s\n' "$usage" "$san")
+}
+
+revoke() {
+    openssl ca -config "$work/$2.cnf" -keyfile "$work/$2.key" -cert "$2.pem" -revoke "$1.pem"
+}
+
+crl() {
+    openssl ca -config "$work/$1.cnf" -keyfile "$work/$1.key" -cert "$1.pem" -gencrl -out "$1.crl.pem"
+}
+
+ca ca
+ca other-ca
+
+leaf server ca serverAuth DNS:localhost
+leaf foo ca serverAuth DNS:foo.test
+leaf bar ca serverAuth DNS:bar.test
+leaf spiffe ca serverAuth URI:spiffe://example.org/backend
+leaf server-revoked ca serverAuth DNS:localhost
+leaf client ca clientAuth URI:spiffe://example.org/client
+leaf client-revoked ca clientAuth URI:spiffe://example.org/client
+
+revoke server-revoked ca
+revoke client-revoked ca
+crl ca
+crl other-ca